@@ -1,10 +1,20 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
 use std::path::PathBuf;
 
 use crate::tools::ToolCategory;
+use crate::tools::adb_apk::InstallOptions;
+use crate::tools::adb_backup::AppBackupOptions;
+use crate::tools::adb_files::RemoteNavigation;
+use crate::tools::adb_forward::{ForwardDirection, ForwardProfile, SocketKind};
+use crate::tools::adb_logcat::{LogcatFilter, DEFAULT_LOGCAT_BUFFER_LINES};
+use crate::tools::adb_packages::PackageListFilter;
+use crate::tools::adb_screen::{ScreenMirrorOptions, ScreenRecordOptions};
+use crate::tools::adb_snippets::SnippetLibrary;
+use crate::tools::adb_sync::SyncOptions;
 use crate::tools::adb_tools::{AdbFunction, AdbToolsState};
+use crate::tools::adb_wireless::{WirelessEndpoint, DEFAULT_TCPIP_PORT};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
@@ -29,16 +39,65 @@ pub struct ToolSettings {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AdbToolsConfig {
     pub selected_device: Option<String>,
+    #[serde(default)]
+    pub wireless_endpoints: Vec<WirelessEndpoint>,
+    #[serde(default)]
+    pub wireless_address: String,
+    #[serde(default = "default_wireless_tcpip_port")]
+    pub wireless_tcpip_port: String,
     pub package_filter: String,
+    #[serde(default)]
+    pub package_list_filter: PackageListFilter,
+    #[serde(default = "default_apk_pull_dir")]
+    pub apk_pull_dir: String,
+    #[serde(default)]
+    pub app_backup_options: AppBackupOptions,
     pub apk_path: String,
+    #[serde(default)]
+    pub install_options: InstallOptions,
     pub local_path: String,
     pub remote_path: String,
+    #[serde(default)]
+    pub remote_navigation: RemoteNavigation,
+    #[serde(default)]
+    pub sync_options: SyncOptions,
     pub shell_command: String,
-    pub logcat_filter: String,
+    #[serde(default)]
+    pub shell_history: Vec<String>,
+    #[serde(default = "default_heap_dump_dir")]
+    pub heap_dump_dir: String,
+    #[serde(default)]
+    pub thermal_thresholds: BTreeMap<String, f64>,
+    #[serde(default)]
+    pub thermal_plot_zones: BTreeSet<String>,
+    #[serde(default)]
+    pub snippet_library: SnippetLibrary,
+    #[serde(default = "default_snippet_file_path")]
+    pub snippet_file_path: String,
+    #[serde(default)]
+    pub logcat_filters: LogcatFilter,
+    #[serde(default = "default_logcat_buffer_lines")]
+    pub logcat_buffer_lines: usize,
+    #[serde(default)]
+    pub logcat_import_path: String,
+    #[serde(default)]
+    pub logcat_export_path: String,
     pub screenshot_path: String,
     pub screen_record_path: String,
+    #[serde(default)]
+    pub screen_record_options: ScreenRecordOptions,
+    #[serde(default)]
+    pub screen_mirror_options: ScreenMirrorOptions,
+    #[serde(default)]
+    pub forward_direction: ForwardDirection,
+    #[serde(default)]
+    pub forward_local_kind: SocketKind,
     pub local_port: String,
+    #[serde(default)]
+    pub forward_remote_kind: SocketKind,
     pub remote_port: String,
+    #[serde(default)]
+    pub forward_profiles: Vec<ForwardProfile>,
     pub monitor_interval: f32,
     pub show_plots: bool,
     pub adb_function_visibility: HashMap<AdbFunction, bool>,
@@ -48,6 +107,26 @@ pub struct AdbToolsConfig {
     pub systemd_unit_filter: String,
 }
 
+fn default_logcat_buffer_lines() -> usize {
+    DEFAULT_LOGCAT_BUFFER_LINES
+}
+
+fn default_apk_pull_dir() -> String {
+    "apks".to_string()
+}
+
+fn default_wireless_tcpip_port() -> String {
+    DEFAULT_TCPIP_PORT.to_string()
+}
+
+fn default_heap_dump_dir() -> String {
+    "heapdumps".to_string()
+}
+
+fn default_snippet_file_path() -> String {
+    "snippets.toml".to_string()
+}
+
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
@@ -91,16 +170,40 @@ impl Default for AdbToolsConfig {
         
         Self {
             selected_device: None,
+            wireless_endpoints: Vec::new(),
+            wireless_address: String::new(),
+            wireless_tcpip_port: default_wireless_tcpip_port(),
             package_filter: String::new(),
+            package_list_filter: PackageListFilter::default(),
+            apk_pull_dir: default_apk_pull_dir(),
+            app_backup_options: AppBackupOptions::default(),
             apk_path: String::new(),
+            install_options: InstallOptions::default(),
             local_path: String::new(),
             remote_path: String::new(),
+            remote_navigation: RemoteNavigation::default(),
+            sync_options: SyncOptions::default(),
             shell_command: String::new(),
-            logcat_filter: String::new(),
+            shell_history: Vec::new(),
+            heap_dump_dir: default_heap_dump_dir(),
+            thermal_thresholds: BTreeMap::new(),
+            thermal_plot_zones: BTreeSet::new(),
+            snippet_library: SnippetLibrary::default(),
+            snippet_file_path: default_snippet_file_path(),
+            logcat_filters: LogcatFilter::default(),
+            logcat_buffer_lines: default_logcat_buffer_lines(),
+            logcat_import_path: String::new(),
+            logcat_export_path: "logcat.txt".to_string(),
             screenshot_path: "screenshot.png".to_string(),
             screen_record_path: "recording.mp4".to_string(),
+            screen_record_options: ScreenRecordOptions::default(),
+            screen_mirror_options: ScreenMirrorOptions::default(),
+            forward_direction: ForwardDirection::default(),
+            forward_local_kind: SocketKind::default(),
             local_port: "8080".to_string(),
+            forward_remote_kind: SocketKind::default(),
             remote_port: "8080".to_string(),
+            forward_profiles: Vec::new(),
             monitor_interval: 1.0,
             show_plots: true,
             adb_function_visibility,
@@ -227,16 +330,40 @@ impl ConfigManager {
         let adb_config = &mut self.config.tool_settings.adb_tools;
         
         adb_config.selected_device = adb_state.selected_device.clone();
+        adb_config.wireless_endpoints = adb_state.wireless_endpoints.clone();
+        adb_config.wireless_address = adb_state.wireless_address.clone();
+        adb_config.wireless_tcpip_port = adb_state.wireless_tcpip_port.clone();
         adb_config.package_filter = adb_state.package_filter.clone();
+        adb_config.package_list_filter = adb_state.package_list_filter;
+        adb_config.apk_pull_dir = adb_state.apk_pull_dir.clone();
+        adb_config.app_backup_options = adb_state.app_backup_options.clone();
         adb_config.apk_path = adb_state.apk_path.clone();
+        adb_config.install_options = adb_state.install_options.clone();
         adb_config.local_path = adb_state.local_path.clone();
         adb_config.remote_path = adb_state.remote_path.clone();
+        adb_config.remote_navigation = adb_state.remote_navigation.clone();
+        adb_config.sync_options = adb_state.sync_options.clone();
         adb_config.shell_command = adb_state.shell_command.clone();
-        adb_config.logcat_filter = adb_state.logcat_filter.clone();
+        adb_config.shell_history = adb_state.shell_history.clone();
+        adb_config.heap_dump_dir = adb_state.heap_dump_dir.clone();
+        adb_config.thermal_thresholds = adb_state.thermal_thresholds.clone();
+        adb_config.thermal_plot_zones = adb_state.thermal_plot_zones.clone();
+        adb_config.snippet_library = adb_state.snippet_library.clone();
+        adb_config.snippet_file_path = adb_state.snippet_file_path.clone();
+        adb_config.logcat_filters = adb_state.logcat_filters.clone();
+        adb_config.logcat_buffer_lines = adb_state.logcat_buffer_lines;
+        adb_config.logcat_import_path = adb_state.logcat_import_path.clone();
+        adb_config.logcat_export_path = adb_state.logcat_export_path.clone();
         adb_config.screenshot_path = adb_state.screenshot_path.clone();
         adb_config.screen_record_path = adb_state.screen_record_path.clone();
+        adb_config.screen_record_options = adb_state.screen_record_options.clone();
+        adb_config.screen_mirror_options = adb_state.screen_mirror_options.clone();
+        adb_config.forward_direction = adb_state.forward_direction;
+        adb_config.forward_local_kind = adb_state.forward_local_kind;
         adb_config.local_port = adb_state.local_port.clone();
+        adb_config.forward_remote_kind = adb_state.forward_remote_kind;
         adb_config.remote_port = adb_state.remote_port.clone();
+        adb_config.forward_profiles = adb_state.forward_profiles.clone();
         adb_config.monitor_interval = adb_state.monitor_interval;
         adb_config.show_plots = adb_state.show_plots;
         adb_config.adb_function_visibility = adb_state.adb_function_visibility.clone();
@@ -250,16 +377,40 @@ impl ConfigManager {
         let adb_config = &self.config.tool_settings.adb_tools;
         
         adb_state.selected_device = adb_config.selected_device.clone();
+        adb_state.wireless_endpoints = adb_config.wireless_endpoints.clone();
+        adb_state.wireless_address = adb_config.wireless_address.clone();
+        adb_state.wireless_tcpip_port = adb_config.wireless_tcpip_port.clone();
         adb_state.package_filter = adb_config.package_filter.clone();
+        adb_state.package_list_filter = adb_config.package_list_filter;
+        adb_state.apk_pull_dir = adb_config.apk_pull_dir.clone();
+        adb_state.app_backup_options = adb_config.app_backup_options.clone();
         adb_state.apk_path = adb_config.apk_path.clone();
+        adb_state.install_options = adb_config.install_options.clone();
         adb_state.local_path = adb_config.local_path.clone();
         adb_state.remote_path = adb_config.remote_path.clone();
+        adb_state.remote_navigation = adb_config.remote_navigation.clone();
+        adb_state.sync_options = adb_config.sync_options.clone();
         adb_state.shell_command = adb_config.shell_command.clone();
-        adb_state.logcat_filter = adb_config.logcat_filter.clone();
+        adb_state.shell_history = adb_config.shell_history.clone();
+        adb_state.heap_dump_dir = adb_config.heap_dump_dir.clone();
+        adb_state.thermal_thresholds = adb_config.thermal_thresholds.clone();
+        adb_state.thermal_plot_zones = adb_config.thermal_plot_zones.clone();
+        adb_state.snippet_library = adb_config.snippet_library.clone();
+        adb_state.snippet_file_path = adb_config.snippet_file_path.clone();
+        adb_state.logcat_filters = adb_config.logcat_filters.clone();
+        adb_state.logcat_buffer_lines = adb_config.logcat_buffer_lines;
+        adb_state.logcat_import_path = adb_config.logcat_import_path.clone();
+        adb_state.logcat_export_path = adb_config.logcat_export_path.clone();
         adb_state.screenshot_path = adb_config.screenshot_path.clone();
         adb_state.screen_record_path = adb_config.screen_record_path.clone();
+        adb_state.screen_record_options = adb_config.screen_record_options.clone();
+        adb_state.screen_mirror_options = adb_config.screen_mirror_options.clone();
+        adb_state.forward_direction = adb_config.forward_direction;
+        adb_state.forward_local_kind = adb_config.forward_local_kind;
         adb_state.local_port = adb_config.local_port.clone();
+        adb_state.forward_remote_kind = adb_config.forward_remote_kind;
         adb_state.remote_port = adb_config.remote_port.clone();
+        adb_state.forward_profiles = adb_config.forward_profiles.clone();
         adb_state.monitor_interval = adb_config.monitor_interval;
         adb_state.show_plots = adb_config.show_plots;
         adb_state.adb_function_visibility = adb_config.adb_function_visibility.clone();
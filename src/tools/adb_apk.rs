@@ -0,0 +1,775 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{Cursor, Read, Seek, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::tools::adb_client::{shell_quote, AdbClient};
+use crate::tools::adb_jobs::JobContext;
+
+const MANIFEST_ENTRY: &str = "AndroidManifest.xml";
+const BUNDLE_EXTENSIONS: [&str; 2] = ["apks", "xapk"];
+const STREAM_CHUNK: usize = 64 * 1024;
+
+const ABI_NAMES: [&str; 7] = ["armeabi", "armeabi-v7a", "arm64-v8a", "x86", "x86_64", "mips", "mips64"];
+const DENSITY_BUCKETS: [(&str, u32); 7] = [
+    ("ldpi", 120),
+    ("mdpi", 160),
+    ("tvdpi", 213),
+    ("hdpi", 240),
+    ("xhdpi", 320),
+    ("xxhdpi", 480),
+    ("xxxhdpi", 640),
+];
+
+const RES_STRING_POOL_TYPE: u16 = 0x0001;
+const RES_XML_TYPE: u16 = 0x0003;
+const RES_XML_START_ELEMENT_TYPE: u16 = 0x0102;
+const RES_XML_END_ELEMENT_TYPE: u16 = 0x0103;
+const RES_XML_RESOURCE_MAP_TYPE: u16 = 0x0180;
+
+const UTF8_FLAG: u32 = 0x100;
+const NO_INDEX: u32 = 0xffff_ffff;
+
+const TYPE_REFERENCE: u8 = 0x01;
+const TYPE_STRING: u8 = 0x03;
+const TYPE_FLOAT: u8 = 0x04;
+const TYPE_INT_DEC: u8 = 0x10;
+const TYPE_INT_HEX: u8 = 0x11;
+const TYPE_INT_BOOLEAN: u8 = 0x12;
+
+/// `android:` attributes we read, by resource ID, for manifests whose attribute names were stripped
+const KNOWN_ATTRIBUTES: [(u32, &str); 5] = [
+    (0x0101_0003, "name"),
+    (0x0101_020c, "minSdkVersion"),
+    (0x0101_021b, "versionCode"),
+    (0x0101_021c, "versionName"),
+    (0x0101_0270, "targetSdkVersion"),
+];
+
+const ACTION_MAIN: &str = "android.intent.action.MAIN";
+const LAUNCHER_CATEGORIES: [&str; 2] = ["android.intent.category.LAUNCHER", "android.intent.category.LEANBACK_LAUNCHER"];
+
+/// What the manifest and native libraries of an APK say about it
+#[derive(Debug, Clone, Default)]
+pub struct ApkInfo {
+    pub path: String,
+    pub file_size: u64,
+    pub package: String,
+    pub version_code: String,
+    pub version_name: String,
+    pub min_sdk: Option<u32>,
+    pub target_sdk: Option<u32>,
+    /// Empty when the APK ships no native code
+    pub abis: Vec<String>,
+    pub permissions: Vec<String>,
+    pub launchable_activities: Vec<String>,
+}
+
+impl ApkInfo {
+    /// Reasons the APK will not install on a device with the given primary ABI and API level
+    pub fn compatibility_warnings(&self, device_abi: Option<&str>, device_sdk: Option<u32>) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if let (Some(min_sdk), Some(device_sdk)) = (self.min_sdk, device_sdk) {
+            if min_sdk > device_sdk {
+                warnings.push(format!("Requires API {} but the device runs API {}", min_sdk, device_sdk));
+            }
+        }
+        if let Some(device_abi) = device_abi.filter(|abi| !abi.is_empty()) {
+            if !self.abis.is_empty() && !self.abis.iter().any(|abi| abi_runs_on(abi, device_abi)) {
+                warnings.push(format!("No native libraries for the device ABI {} (APK has {})", device_abi, self.abis.join(", ")));
+            }
+        }
+        warnings
+    }
+}
+
+/// Whether libraries built for `abi` load on a device whose primary ABI is `device_abi`
+fn abi_runs_on(abi: &str, device_abi: &str) -> bool {
+    match device_abi {
+        "arm64-v8a" => matches!(abi, "arm64-v8a" | "armeabi-v7a" | "armeabi"),
+        "armeabi-v7a" => matches!(abi, "armeabi-v7a" | "armeabi"),
+        "x86_64" => matches!(abi, "x86_64" | "x86"),
+        other => abi == other,
+    }
+}
+
+/// Open an APK and decode its binary manifest, without touching any device
+pub fn inspect_apk(path: &Path) -> Result<ApkInfo, String> {
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let file_size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let mut info = inspect_apk_archive(file, &path.display().to_string())?;
+    info.file_size = file_size;
+    Ok(info)
+}
+
+fn inspect_apk_archive<R: Read + Seek>(reader: R, label: &str) -> Result<ApkInfo, String> {
+    let mut archive = zip::ZipArchive::new(reader).map_err(|e| format!("{} is not a valid APK: {}", label, e))?;
+
+    let abis: BTreeSet<String> = archive
+        .file_names()
+        .filter_map(|name| {
+            let mut parts = name.split('/');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some("lib"), Some(abi), Some(library)) if library.ends_with(".so") => Some(abi.to_string()),
+                _ => None,
+            }
+        })
+        .collect();
+
+    let mut manifest = Vec::new();
+    archive
+        .by_name(MANIFEST_ENTRY)
+        .map_err(|_| format!("{} has no {}", label, MANIFEST_ENTRY))?
+        .read_to_end(&mut manifest)
+        .map_err(|e| format!("Failed to read {}: {}", MANIFEST_ENTRY, e))?;
+
+    let mut info = read_manifest(&parse_axml(&manifest)?);
+    info.path = label.to_string();
+    info.abis = abis.into_iter().collect();
+    Ok(info)
+}
+
+#[derive(Debug)]
+enum XmlEvent {
+    Start { name: String, attributes: Vec<(String, String)> },
+    End { name: String },
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16, String> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| format!("Truncated manifest at offset {}", offset))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32, String> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| format!("Truncated manifest at offset {}", offset))
+}
+
+/// Decode a `ResStringPool` chunk starting at `start`
+fn parse_string_pool(data: &[u8], start: usize) -> Result<Vec<String>, String> {
+    let header_size = u16_at(data, start + 2)? as usize;
+    let chunk_end = (start + u32_at(data, start + 4)? as usize).min(data.len());
+    let count = u32_at(data, start + 8)? as usize;
+    let flags = u32_at(data, start + 16)?;
+    let strings_start = start + u32_at(data, start + 20)? as usize;
+
+    // The count comes from the file; each entry needs a 4-byte offset inside the chunk
+    let max_count = chunk_end.saturating_sub(start + header_size) / 4;
+    if count > max_count {
+        return Err(format!("Corrupt string pool: {} entries in {} bytes", count, chunk_end - start));
+    }
+    let mut strings = Vec::with_capacity(count);
+    for i in 0..count {
+        let offset = strings_start + u32_at(data, start + header_size + i * 4)? as usize;
+        let string = if flags & UTF8_FLAG != 0 {
+            decode_utf8_string(data, offset)
+        } else {
+            decode_utf16_string(data, offset)
+        };
+        strings.push(string.unwrap_or_default());
+    }
+    Ok(strings)
+}
+
+/// UTF-8 pool entries hold the UTF-16 length, then the byte length, each one or two bytes long
+fn decode_utf8_string(data: &[u8], offset: usize) -> Option<String> {
+    let read_len = |pos: usize| -> Option<(usize, usize)> {
+        let first = *data.get(pos)? as usize;
+        if first & 0x80 != 0 {
+            Some((((first & 0x7f) << 8) | *data.get(pos + 1)? as usize, pos + 2))
+        } else {
+            Some((first, pos + 1))
+        }
+    };
+    let (_, pos) = read_len(offset)?;
+    let (len, pos) = read_len(pos)?;
+    data.get(pos..pos + len).map(|bytes| String::from_utf8_lossy(bytes).to_string())
+}
+
+fn decode_utf16_string(data: &[u8], offset: usize) -> Option<String> {
+    let first = u16_at(data, offset).ok()? as usize;
+    let (len, pos) = if first & 0x8000 != 0 {
+        (((first & 0x7fff) << 16) | u16_at(data, offset + 2).ok()? as usize, offset + 4)
+    } else {
+        (first, offset + 2)
+    };
+    let units: Vec<u16> = data
+        .get(pos..pos + len * 2)?
+        .chunks_exact(2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .collect();
+    Some(String::from_utf16_lossy(&units))
+}
+
+/// Walk the chunks of a compiled XML document, keeping only element starts and ends
+fn parse_axml(data: &[u8]) -> Result<Vec<XmlEvent>, String> {
+    if u16_at(data, 0)? != RES_XML_TYPE {
+        return Err("AndroidManifest.xml is not a compiled binary XML file".to_string());
+    }
+    let mut pos = u16_at(data, 2)? as usize;
+    let mut strings = Vec::new();
+    let mut resource_ids = Vec::new();
+    let mut events = Vec::new();
+    let string = |strings: &[String], index: u32| strings.get(index as usize).cloned().unwrap_or_default();
+
+    while pos + 8 <= data.len() {
+        let chunk_type = u16_at(data, pos)?;
+        let header_size = u16_at(data, pos + 2)? as usize;
+        let chunk_size = u32_at(data, pos + 4)? as usize;
+        if chunk_size < 8 {
+            return Err(format!("Corrupt manifest chunk at offset {}", pos));
+        }
+
+        match chunk_type {
+            RES_STRING_POOL_TYPE => strings = parse_string_pool(data, pos)?,
+            RES_XML_RESOURCE_MAP_TYPE => {
+                resource_ids = (pos + header_size..pos + chunk_size)
+                    .step_by(4)
+                    .map(|offset| u32_at(data, offset))
+                    .collect::<Result<_, _>>()?;
+            }
+            RES_XML_START_ELEMENT_TYPE => {
+                let ext = pos + header_size;
+                let name = string(&strings, u32_at(data, ext + 4)?);
+                let attribute_start = u16_at(data, ext + 8)? as usize;
+                let attribute_size = u16_at(data, ext + 10)? as usize;
+                let attribute_count = u16_at(data, ext + 12)? as usize;
+                let chunk_end = (pos + chunk_size).min(data.len());
+                if attribute_count > 0
+                    && ext + attribute_start + attribute_count * attribute_size.max(1) > chunk_end
+                {
+                    return Err(format!("Corrupt manifest element at offset {}", pos));
+                }
+
+                let mut attributes = Vec::with_capacity(attribute_count);
+                for i in 0..attribute_count {
+                    let attr = ext + attribute_start + i * attribute_size;
+                    let name_index = u32_at(data, attr + 4)?;
+                    let raw_value = u32_at(data, attr + 8)?;
+                    let data_type = *data.get(attr + 15).ok_or("Truncated manifest attribute")?;
+                    let value_data = u32_at(data, attr + 16)?;
+
+                    // Prefer the resource ID, since obfuscators rename or blank attribute strings
+                    let attribute_name = resource_ids
+                        .get(name_index as usize)
+                        .and_then(|id| KNOWN_ATTRIBUTES.iter().find(|(known, _)| known == id))
+                        .map(|(_, name)| name.to_string())
+                        .unwrap_or_else(|| string(&strings, name_index));
+                    let value = if raw_value != NO_INDEX {
+                        string(&strings, raw_value)
+                    } else {
+                        match data_type {
+                            TYPE_STRING => string(&strings, value_data),
+                            TYPE_INT_DEC => (value_data as i32).to_string(),
+                            TYPE_INT_HEX => format!("0x{:x}", value_data),
+                            TYPE_INT_BOOLEAN => (value_data != 0).to_string(),
+                            TYPE_FLOAT => f32::from_bits(value_data).to_string(),
+                            TYPE_REFERENCE => format!("@0x{:08x}", value_data),
+                            _ => format!("0x{:08x}", value_data),
+                        }
+                    };
+                    attributes.push((attribute_name, value));
+                }
+                events.push(XmlEvent::Start { name, attributes });
+            }
+            RES_XML_END_ELEMENT_TYPE => {
+                let name = string(&strings, u32_at(data, pos + header_size + 4)?);
+                events.push(XmlEvent::End { name });
+            }
+            _ => {}
+        }
+        pos += chunk_size;
+    }
+    Ok(events)
+}
+
+fn attribute<'a>(attributes: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attributes.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+}
+
+/// Expand `.Main` and `Main` class names relative to the package
+fn qualify_class_name(package: &str, name: &str) -> String {
+    if name.starts_with('.') {
+        format!("{}{}", package, name)
+    } else if !name.contains('.') {
+        format!("{}.{}", package, name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn read_manifest(events: &[XmlEvent]) -> ApkInfo {
+    let mut info = ApkInfo::default();
+    let mut activity: Option<String> = None;
+    let mut in_intent_filter = false;
+    let mut has_main = false;
+    let mut has_launcher = false;
+
+    for event in events {
+        match event {
+            XmlEvent::Start { name, attributes } => match name.as_str() {
+                "manifest" => {
+                    info.package = attribute(attributes, "package").unwrap_or_default().to_string();
+                    info.version_code = attribute(attributes, "versionCode").unwrap_or_default().to_string();
+                    info.version_name = attribute(attributes, "versionName").unwrap_or_default().to_string();
+                }
+                "uses-sdk" => {
+                    info.min_sdk = attribute(attributes, "minSdkVersion").and_then(|v| v.parse().ok());
+                    info.target_sdk = attribute(attributes, "targetSdkVersion").and_then(|v| v.parse().ok());
+                }
+                "uses-permission" | "uses-permission-sdk-23" | "uses-permission-sdk-m" => {
+                    if let Some(permission) = attribute(attributes, "name") {
+                        info.permissions.push(permission.to_string());
+                    }
+                }
+                "activity" | "activity-alias" => {
+                    activity = attribute(attributes, "name").map(|n| qualify_class_name(&info.package, n));
+                }
+                "intent-filter" => {
+                    in_intent_filter = true;
+                    has_main = false;
+                    has_launcher = false;
+                }
+                "action" if in_intent_filter => {
+                    has_main |= attribute(attributes, "name") == Some(ACTION_MAIN);
+                }
+                "category" if in_intent_filter => {
+                    has_launcher |= attribute(attributes, "name").is_some_and(|c| LAUNCHER_CATEGORIES.contains(&c));
+                }
+                _ => {}
+            },
+            XmlEvent::End { name } => match name.as_str() {
+                "intent-filter" => {
+                    in_intent_filter = false;
+                    if let Some(activity) = activity.as_ref().filter(|_| has_main && has_launcher) {
+                        if !info.launchable_activities.contains(activity) {
+                            info.launchable_activities.push(activity.clone());
+                        }
+                    }
+                }
+                "activity" | "activity-alias" => activity = None,
+                _ => {}
+            },
+        }
+    }
+
+    // Without `uses-sdk` the platform assumes API 1, and the target defaults to the minimum
+    let min_sdk = *info.min_sdk.get_or_insert(1);
+    info.target_sdk.get_or_insert(min_sdk);
+    info.permissions.sort();
+    info.permissions.dedup();
+    info
+}
+
+/// Flags passed to `adb install` and `pm install-create`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstallOptions {
+    pub replace: bool,
+    pub downgrade: bool,
+    pub grant_permissions: bool,
+    pub allow_test: bool,
+    /// Target user ID; empty installs for the default user
+    pub user: String,
+}
+
+impl Default for InstallOptions {
+    fn default() -> Self {
+        Self {
+            replace: true,
+            downgrade: false,
+            grant_permissions: false,
+            allow_test: false,
+            user: String::new(),
+        }
+    }
+}
+
+impl InstallOptions {
+    pub fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        for (enabled, flag) in [
+            (self.replace, "-r"),
+            (self.downgrade, "-d"),
+            (self.grant_permissions, "-g"),
+            (self.allow_test, "-t"),
+        ] {
+            if enabled {
+                args.push(flag.to_string());
+            }
+        }
+        if !self.user.trim().is_empty() {
+            args.push("--user".to_string());
+            args.push(self.user.trim().to_string());
+        }
+        args
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SplitKind {
+    Base,
+    Feature(String),
+    Abi(String),
+    Density(String),
+    Locale(String),
+}
+
+impl SplitKind {
+    /// Classify a split from its file name, as written by bundletool (`base-xxhdpi.apk`),
+    /// XAPK packers (`config.arm64_v8a.apk`) or `pm path` (`split_config.en.apk`)
+    fn from_file_name(file_name: &str) -> Self {
+        let stem = file_name.strip_suffix(".apk").unwrap_or(file_name);
+        let stem = stem.strip_prefix("split_").unwrap_or(stem);
+        let (module, config) = match stem.strip_prefix("config.") {
+            Some(config) => ("base", Some(config)),
+            None => match stem.rsplit_once('-') {
+                Some((module, config)) => (module, Some(config)),
+                None => (stem, None),
+            },
+        };
+
+        let Some(config) = config.filter(|c| *c != "master") else {
+            // XAPKs name the base split after the package
+            return if module == "base" || module.contains('.') {
+                Self::Base
+            } else {
+                Self::Feature(module.to_string())
+            };
+        };
+        // Split names use `_` where ABI names use `-`, except in `x86_64`
+        let abi = ABI_NAMES.iter().find(|abi| abi.replace('-', "_") == config);
+        let language = config.split(['_', '-']).next().unwrap_or_default();
+        if let Some(abi) = abi {
+            Self::Abi(abi.to_string())
+        } else if DENSITY_BUCKETS.iter().any(|(name, _)| *name == config) {
+            Self::Density(config.to_string())
+        } else if (2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_lowercase()) {
+            Self::Locale(config.to_string())
+        } else {
+            Self::Feature(stem.to_string())
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::Base => "Base".to_string(),
+            Self::Feature(name) => format!("Feature: {}", name),
+            Self::Abi(abi) => format!("ABI: {}", abi),
+            Self::Density(density) => format!("Density: {}", density),
+            Self::Locale(locale) => format!("Locale: {}", locale),
+        }
+    }
+}
+
+/// One APK of a split set, either a file on disk or an entry inside a bundle
+#[derive(Debug, Clone)]
+pub struct ApkSplit {
+    pub name: String,
+    /// Bundle entry name, or the full path for a folder of splits
+    pub entry: String,
+    pub kind: SplitKind,
+    pub size: u64,
+    pub selected: bool,
+}
+
+/// What the device reports for choosing configuration splits
+#[derive(Debug, Clone, Default)]
+pub struct DeviceSplitConfig {
+    pub abis: Vec<String>,
+    pub density: u32,
+    pub locales: Vec<String>,
+}
+
+impl DeviceSplitConfig {
+    pub fn summary(&self) -> String {
+        format!("{}, {} dpi, {}", self.abis.join("/"), self.density, self.locales.join("/"))
+    }
+}
+
+pub fn is_bundle(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| BUNDLE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Whether the path holds several APKs rather than a single one
+pub fn is_split_set(path: &Path) -> bool {
+    path.is_dir() || is_bundle(path)
+}
+
+fn getprop(client: &AdbClient, serial: &str, property: &str) -> String {
+    client
+        .shell(serial, &format!("getprop {}", property))
+        .map(|v| v.trim().to_string())
+        .unwrap_or_default()
+}
+
+pub fn query_device_split_config(client: &AdbClient, serial: &str) -> Result<DeviceSplitConfig, String> {
+    let mut abis: Vec<String> = getprop(client, serial, "ro.product.cpu.abilist")
+        .split(',')
+        .filter(|abi| !abi.is_empty())
+        .map(str::to_string)
+        .collect();
+    if abis.is_empty() {
+        abis.push(getprop(client, serial, "ro.product.cpu.abi"));
+    }
+
+    // `wm density` prints the physical density and, if set, an override that takes precedence
+    let density_output = client.shell(serial, "wm density").map_err(|e| format!("wm density failed: {}", e))?;
+    let density = density_output
+        .lines()
+        .rev()
+        .filter_map(|line| line.split_once(':'))
+        .find_map(|(_, value)| value.trim().parse().ok())
+        .ok_or_else(|| format!("Unexpected wm density output: {}", density_output.trim()))?;
+
+    let mut locales = client
+        .shell(serial, "settings get system system_locales")
+        .map(|v| v.trim().to_string())
+        .unwrap_or_default();
+    if locales.is_empty() || locales == "null" {
+        locales = getprop(client, serial, "persist.sys.locale");
+    }
+    if locales.is_empty() {
+        locales = getprop(client, serial, "ro.product.locale");
+    }
+
+    Ok(DeviceSplitConfig {
+        abis,
+        density,
+        locales: locales.split(',').filter(|l| !l.is_empty()).map(str::to_string).collect(),
+    })
+}
+
+/// What inspecting the base APK of a split set gave; `None` when there is no base
+pub type BaseInspection = Result<Option<ApkInfo>, String>;
+
+/// List the APKs of a bundle or folder, with base and feature splits preselected, and inspect the base.
+/// A base whose manifest cannot be decoded does not stop the listing; its error is returned instead.
+pub fn load_apk_splits(path: &Path) -> Result<(Vec<ApkSplit>, BaseInspection), String> {
+    let mut splits = Vec::new();
+    let mut base_info = Ok(None);
+
+    if path.is_dir() {
+        let entries = fs::read_dir(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.to_lowercase().ends_with(".apk") {
+                continue;
+            }
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            splits.push(new_split(name, entry.path().display().to_string(), size));
+        }
+        if let Some(base) = splits.iter().find(|s| s.kind == SplitKind::Base) {
+            base_info = inspect_apk(Path::new(&base.entry)).map(Some);
+        }
+    } else {
+        let file = fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("{} is not a valid bundle: {}", path.display(), e))?;
+        for i in 0..archive.len() {
+            let entry = archive.by_index(i).map_err(|e| e.to_string())?;
+            let entry_name = entry.name().to_string();
+            // bundletool also ships pre-L `standalones/`, which must not be mixed with the splits
+            if !entry_name.to_lowercase().ends_with(".apk") || entry_name.starts_with("standalones/") {
+                continue;
+            }
+            let name = entry_name.rsplit('/').next().unwrap_or(&entry_name).to_string();
+            splits.push(new_split(name, entry_name, entry.size()));
+        }
+        if let Some(base) = splits.iter().find(|s| s.kind == SplitKind::Base) {
+            let mut data = Vec::new();
+            archive
+                .by_name(&base.entry)
+                .map_err(|e| e.to_string())?
+                .read_to_end(&mut data)
+                .map_err(|e| format!("Failed to read {}: {}", base.entry, e))?;
+            base_info = inspect_apk_archive(Cursor::new(data), &path.display().to_string()).map(|mut info| {
+                info.file_size = base.size;
+                Some(info)
+            });
+        }
+    }
+
+    if splits.is_empty() {
+        return Err(format!("No APKs found in {}", path.display()));
+    }
+    splits.sort_by(|a, b| a.kind.label().cmp(&b.kind.label()).then_with(|| a.name.cmp(&b.name)));
+    Ok((splits, base_info))
+}
+
+fn new_split(name: String, entry: String, size: u64) -> ApkSplit {
+    let kind = SplitKind::from_file_name(&name);
+    let selected = matches!(kind, SplitKind::Base | SplitKind::Feature(_));
+    ApkSplit { name, entry, kind, size, selected }
+}
+
+fn density_dpi(bucket: &str) -> Option<u32> {
+    DENSITY_BUCKETS.iter().find(|(name, _)| *name == bucket).map(|(_, dpi)| *dpi)
+}
+
+/// Select the configuration splits the Play Store would deliver to this device
+pub fn select_splits(splits: &mut [ApkSplit], device: &DeviceSplitConfig) {
+    let abi = device
+        .abis
+        .iter()
+        .find(|abi| splits.iter().any(|s| s.kind == SplitKind::Abi(abi.to_string())))
+        .cloned();
+
+    // The closest bucket at or above the device density, otherwise the largest one available
+    let densities: Vec<u32> = splits
+        .iter()
+        .filter_map(|s| match &s.kind {
+            SplitKind::Density(bucket) => density_dpi(bucket),
+            _ => None,
+        })
+        .collect();
+    let density = densities
+        .iter()
+        .filter(|dpi| **dpi >= device.density)
+        .min()
+        .or_else(|| densities.iter().max())
+        .copied();
+
+    let languages: Vec<&str> = device
+        .locales
+        .iter()
+        .filter_map(|locale| locale.split(['-', '_']).next())
+        .collect();
+
+    for split in splits.iter_mut() {
+        split.selected = match &split.kind {
+            SplitKind::Base | SplitKind::Feature(_) => true,
+            SplitKind::Abi(split_abi) => abi.as_ref() == Some(split_abi),
+            SplitKind::Density(bucket) => density.is_some() && density_dpi(bucket) == density,
+            SplitKind::Locale(locale) => languages.contains(&locale.split(['-', '_']).next().unwrap_or_default()),
+        };
+    }
+}
+
+/// Parse the session ID from `Success: created install session [1234]`
+fn parse_session_id(output: &str) -> Option<u32> {
+    let start = output.find('[')? + 1;
+    let end = start + output[start..].find(']')?;
+    output[start..end].parse().ok()
+}
+
+/// Install a single APK through the same `pm` install session as a split set
+pub fn install_single_apk<T>(
+    job: &JobContext<T>,
+    client: &AdbClient,
+    serial: &str,
+    path: &Path,
+    options: &InstallOptions,
+) -> Result<String, String> {
+    let size = fs::metadata(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?.len();
+    let split = ApkSplit {
+        name: "base.apk".to_string(),
+        entry: path.to_string_lossy().to_string(),
+        kind: SplitKind::Base,
+        size,
+        selected: true,
+    };
+    install_splits(job, client, serial, path, &[split], options)
+}
+
+/// Install the selected splits as one package through a `pm` install session, streaming each APK
+/// over `exec:` so bundles never need extracting; returns one result line per split
+pub fn install_splits<T>(
+    job: &JobContext<T>,
+    client: &AdbClient,
+    serial: &str,
+    source: &Path,
+    splits: &[ApkSplit],
+    options: &InstallOptions,
+) -> Result<String, String> {
+    let selected: Vec<&ApkSplit> = splits.iter().filter(|s| s.selected).collect();
+    if selected.is_empty() {
+        return Err("No splits selected".to_string());
+    }
+    let total_bytes: u64 = selected.iter().map(|s| s.size).sum();
+    let mut archive = if is_bundle(source) {
+        let file = fs::File::open(source).map_err(|e| format!("Failed to open {}: {}", source.display(), e))?;
+        Some(zip::ZipArchive::new(file).map_err(|e| e.to_string())?)
+    } else {
+        None
+    };
+
+    let create = format!("pm install-create -S {} {}", total_bytes, options.args().join(" "));
+    let output = client.shell(serial, &create).map_err(|e| format!("install-create failed: {}", e))?;
+    let session = parse_session_id(&output).ok_or_else(|| format!("install-create failed: {}", output.trim()))?;
+    job.log(format!("Created install session {}", session));
+    // Abandoning the session needs `client` itself, which a cancel leaves usable
+    let device = job.client(client);
+
+    let abandon = |report: &mut Vec<String>, reason: String| {
+        let _ = client.shell(serial, &format!("pm install-abandon {}", session));
+        report.push(format!("❌ {}", reason));
+        report.join("\n")
+    };
+
+    let mut report = Vec::new();
+    let mut done_bytes = 0u64;
+    for (index, split) in selected.iter().enumerate() {
+        if job.is_cancelled() {
+            return Err(abandon(&mut report, "Cancelled".to_string()));
+        }
+        job.log(format!("Writing {}", split.name));
+
+        let mut reader: Box<dyn Read + '_> = match archive.as_mut() {
+            Some(archive) => Box::new(archive.by_name(&split.entry).map_err(|e| format!("{}: {}", split.entry, e))?),
+            None => Box::new(fs::File::open(&split.entry).map_err(|e| format!("Failed to open {}: {}", split.entry, e))?),
+        };
+        let command = format!(
+            "exec:pm install-write -S {} {} {} -",
+            split.size,
+            session,
+            shell_quote(&format!("{}_{}", index, split.name))
+        );
+        let result = device.open_service(serial, &command).map_err(|e| e.to_string()).and_then(|mut stream| {
+            let _watch = device.watch(&stream).map_err(|e| e.to_string())?;
+            let mut buffer = vec![0u8; STREAM_CHUNK];
+            loop {
+                if job.is_cancelled() {
+                    return Err("Cancelled".to_string());
+                }
+                let read = reader.read(&mut buffer).map_err(|e| e.to_string())?;
+                if read == 0 {
+                    break;
+                }
+                stream.write_all(&buffer[..read]).map_err(|e| e.to_string())?;
+                done_bytes += read as u64;
+                job.set_progress(done_bytes as f32 / total_bytes.max(1) as f32);
+            }
+            let mut response = String::new();
+            stream.read_to_string(&mut response).map_err(|e| e.to_string())?;
+            Ok(response.trim().to_string())
+        });
+
+        match result {
+            Ok(response) if response.starts_with("Success") => {
+                report.push(format!("✅ {} ({}): {}", split.name, split.kind.label(), response));
+            }
+            Ok(response) | Err(response) => {
+                return Err(abandon(&mut report, format!("{} ({}): {}", split.name, split.kind.label(), response)));
+            }
+        }
+    }
+
+    let commit = client
+        .shell_v2(serial, &format!("pm install-commit {}", session))
+        .map_err(|e| format!("install-commit failed: {}", e))?;
+    let message = format!("{}{}", commit.stdout_string(), commit.stderr_string()).trim().to_string();
+    if message.contains("Success") {
+        report.push(format!("✅ Committed {} split(s): {}", selected.len(), message));
+        Ok(report.join("\n"))
+    } else {
+        report.push(format!("❌ Commit failed: {}", message));
+        Err(report.join("\n"))
+    }
+}
+
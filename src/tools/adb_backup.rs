@@ -0,0 +1,292 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::tools::adb_client::{shell_quote, AdbClient};
+use crate::tools::adb_jobs::JobContext;
+use crate::tools::adb_packages::parse_dumpsys_package;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const DATA_ENTRY: &str = "data.tar";
+/// Left alone on backup and restore: native libraries are a symlink into the APK, caches are disposable
+const SKIPPED_ENTRIES: &str = "lib|cache|code_cache";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackupMethod {
+    /// `run-as` when the app is debuggable, otherwise `su`
+    #[default]
+    Auto,
+    RunAs,
+    Root,
+}
+
+impl BackupMethod {
+    pub fn all() -> [Self; 3] {
+        [Self::Auto, Self::RunAs, Self::Root]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Auto => "Auto",
+            Self::RunAs => "run-as (debuggable)",
+            Self::Root => "su (rooted)",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppBackupOptions {
+    pub method: BackupMethod,
+    pub backup_dir: String,
+    pub restore_path: String,
+}
+
+impl Default for AppBackupOptions {
+    fn default() -> Self {
+        Self {
+            method: BackupMethod::Auto,
+            backup_dir: "backups".to_string(),
+            restore_path: String::new(),
+        }
+    }
+}
+
+/// Describes a backup archive, stored next to the data as `manifest.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub package: String,
+    pub version_code: String,
+    pub version_name: String,
+    pub device_serial: String,
+    pub device_model: String,
+    pub created: String,
+    pub method: String,
+}
+
+/// How commands reach the app's private data directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DataAccess {
+    RunAs,
+    /// Magisk and SuperSU style `su -c <command>`
+    SuDashC,
+    /// AOSP userdebug `su <uid> <command...>`
+    SuUid,
+}
+
+impl DataAccess {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::RunAs => "run-as",
+            Self::SuDashC | Self::SuUid => "su",
+        }
+    }
+
+    /// Wrap a script so it runs with access to the package's data
+    fn wrap(&self, package: &str, script: &str) -> String {
+        match self {
+            Self::RunAs => format!("run-as {} sh -c {}", shell_quote(package), shell_quote(script)),
+            Self::SuDashC => format!("su -c {}", shell_quote(script)),
+            Self::SuUid => format!("su 0 sh -c {}", shell_quote(script)),
+        }
+    }
+}
+
+fn detect_access(client: &AdbClient, serial: &str, package: &str, method: BackupMethod) -> Result<DataAccess, String> {
+    let id_output = |command: &str| {
+        client
+            .shell_v2(serial, command)
+            .map(|output| output.stdout_string())
+            .unwrap_or_default()
+    };
+
+    if method != BackupMethod::Root && id_output(&format!("run-as {} id", shell_quote(package))).contains("uid=") {
+        return Ok(DataAccess::RunAs);
+    }
+    if method != BackupMethod::RunAs {
+        if id_output("su -c id").contains("uid=0") {
+            return Ok(DataAccess::SuDashC);
+        }
+        if id_output("su 0 id").contains("uid=0") {
+            return Ok(DataAccess::SuUid);
+        }
+    }
+    Err(match method {
+        BackupMethod::RunAs => format!("run-as failed: {} is not debuggable", package),
+        BackupMethod::Root => "su is not available on this device".to_string(),
+        BackupMethod::Auto => format!("{} is not debuggable and the device is not rooted", package),
+    })
+}
+
+/// Shell commands fail here on a non-zero exit code, with stderr as the message
+fn shell(client: &AdbClient, serial: &str, command: &str) -> Result<String, String> {
+    client.shell(serial, command).map_err(|e| e.to_string())
+}
+
+/// Data directory, versionCode and versionName of an installed package
+fn package_state(client: &AdbClient, serial: &str, package: &str) -> Result<(String, String, String), String> {
+    let dumpsys = shell(client, serial, &format!("dumpsys package {}", shell_quote(package)))?;
+    let details =
+        parse_dumpsys_package(&dumpsys, package).ok_or_else(|| format!("{} is not installed on {}", package, serial))?;
+    let data_dir = if details.data_dir.is_empty() {
+        format!("/data/data/{}", package)
+    } else {
+        details.data_dir
+    };
+    Ok((data_dir, details.version_code, details.version_name))
+}
+
+fn device_temp_tar(package: &str) -> String {
+    format!("/data/local/tmp/ohmytoolboxs-{}.tar", package)
+}
+
+/// Archive the package's private data into `<backup_dir>/<package>_<timestamp>.zip`
+pub fn backup_app_data<T>(
+    job: &JobContext<T>,
+    client: &AdbClient,
+    serial: &str,
+    package: &str,
+    method: BackupMethod,
+    backup_dir: &Path,
+) -> Result<PathBuf, String> {
+    // Cleanup below uses `client` directly so it still runs after a cancel
+    let device = job.client(client);
+    let access = detect_access(&device, serial, package, method)?;
+    let (data_dir, version_code, version_name) = package_state(&device, serial, package)?;
+    job.log(format!("Backing up {} with {}", data_dir, access.name()));
+
+    // Stop the app so its databases are not written mid-copy
+    shell(&device, serial, &format!("am force-stop {}", shell_quote(package)))?;
+
+    let remote_tar = device_temp_tar(package);
+    // Entries are collected as positional parameters so names with spaces stay whole; with
+    // nothing left to archive, two zero blocks make an empty tar instead of a tar error
+    let script = format!(
+        "cd {dir} || exit 1; set --; for f in .* *; do case \"$f\" in .|..|{skip}) ;; \
+         *) if [ -e \"$f\" ] || [ -L \"$f\" ]; then set -- \"$@\" \"$f\"; fi ;; esac; done; \
+         if [ $# -gt 0 ]; then tar -cf - \"$@\"; else head -c 1024 /dev/zero; fi",
+        dir = shell_quote(&data_dir),
+        skip = SKIPPED_ENTRIES
+    );
+    let command = format!("{} > {}", access.wrap(package, &script), shell_quote(&remote_tar));
+    let result = shell(&device, serial, &command);
+
+    let local_tar = std::env::temp_dir().join(format!("ohmytoolboxs-{}-{}.tar", package, std::process::id()));
+    let pulled = result.and_then(|_| device.pull(serial, &remote_tar, &local_tar).map_err(|e| e.to_string()));
+    let _ = client.shell(serial, &format!("rm -f {}", shell_quote(&remote_tar)));
+    pulled.map_err(|e| format!("Backup failed: {}", e))?;
+
+    let manifest = BackupManifest {
+        package: package.to_string(),
+        version_code,
+        version_name,
+        device_serial: serial.to_string(),
+        device_model: device.shell(serial, "getprop ro.product.model").unwrap_or_default().trim().to_string(),
+        created: chrono::Local::now().to_rfc3339(),
+        method: access.name().to_string(),
+    };
+
+    fs::create_dir_all(backup_dir).map_err(|e| format!("Failed to create {}: {}", backup_dir.display(), e))?;
+    let archive_path = backup_dir.join(format!("{}_{}.zip", package, chrono::Local::now().format("%Y%m%d_%H%M%S")));
+    job.log(format!("Writing {}", archive_path.display()));
+    let written = write_backup_archive(&archive_path, &manifest, &local_tar);
+    let _ = fs::remove_file(&local_tar);
+    written?;
+    Ok(archive_path)
+}
+
+fn write_backup_archive(path: &Path, manifest: &BackupManifest, tar: &Path) -> Result<(), String> {
+    let file = fs::File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let manifest_json = serde_json::to_string_pretty(manifest).map_err(|e| e.to_string())?;
+    zip.start_file(MANIFEST_ENTRY, options).map_err(|e| e.to_string())?;
+    io::Write::write_all(&mut zip, manifest_json.as_bytes()).map_err(|e| e.to_string())?;
+
+    let mut data = fs::File::open(tar).map_err(|e| e.to_string())?;
+    zip.start_file(DATA_ENTRY, options.large_file(true)).map_err(|e| e.to_string())?;
+    io::copy(&mut data, &mut zip).map_err(|e| e.to_string())?;
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn read_backup_manifest(path: &Path) -> Result<BackupManifest, String> {
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("{} is not a backup archive: {}", path.display(), e))?;
+    let mut json = String::new();
+    archive
+        .by_name(MANIFEST_ENTRY)
+        .map_err(|_| format!("{} has no {}", path.display(), MANIFEST_ENTRY))?
+        .read_to_string(&mut json)
+        .map_err(|e| e.to_string())?;
+    serde_json::from_str(&json).map_err(|e| format!("Invalid {}: {}", MANIFEST_ENTRY, e))
+}
+
+/// Replace the package's data on `serial` with the archive's; the package must already be installed
+pub fn restore_app_data<T>(
+    job: &JobContext<T>,
+    client: &AdbClient,
+    serial: &str,
+    archive_path: &Path,
+    method: BackupMethod,
+) -> Result<String, String> {
+    let manifest = read_backup_manifest(archive_path)?;
+    let package = manifest.package.as_str();
+    // Cleanup below uses `client` directly so it still runs after a cancel
+    let device = job.client(client);
+    let access = detect_access(&device, serial, package, method)?;
+    let (data_dir, version_code, _) = package_state(&device, serial, package)?;
+
+    let local_tar = std::env::temp_dir().join(format!("ohmytoolboxs-restore-{}-{}.tar", package, std::process::id()));
+    {
+        let file = fs::File::open(archive_path).map_err(|e| e.to_string())?;
+        let mut archive = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
+        let mut data = archive
+            .by_name(DATA_ENTRY)
+            .map_err(|_| format!("{} has no {}", archive_path.display(), DATA_ENTRY))?;
+        let mut out = fs::File::create(&local_tar).map_err(|e| e.to_string())?;
+        io::copy(&mut data, &mut out).map_err(|e| e.to_string())?;
+    }
+
+    let remote_tar = device_temp_tar(package);
+    job.log(format!("Uploading data for {}", package));
+    let pushed = device.push(serial, &local_tar, &remote_tar).map_err(|e| e.to_string());
+    let _ = fs::remove_file(&local_tar);
+    pushed.map_err(|e| format!("Upload failed: {}", e))?;
+
+    shell(&device, serial, &format!("am force-stop {}", shell_quote(package)))?;
+
+    // Clear the current data first so the result matches the backup exactly
+    let mut script = format!(
+        "cd {dir} && ls -A | grep -vxE '{skip}' | while read -r f; do rm -rf \"$f\"; done && tar -xf -",
+        dir = shell_quote(&data_dir),
+        skip = SKIPPED_ENTRIES
+    );
+    if access != DataAccess::RunAs {
+        // Files come back owned by the source device's UID; hand them to this device's app UID
+        script.push_str(&format!(
+            " && u=$(stat -c %u .) && g=$(stat -c %g .) && ls -A | grep -vxE '{}' | while read -r f; do chown -R $u:$g \"$f\"; done && restorecon -R .",
+            SKIPPED_ENTRIES
+        ));
+    }
+    job.log(format!("Restoring into {} with {}", data_dir, access.name()));
+    let command = format!("{} < {}", access.wrap(package, &script), shell_quote(&remote_tar));
+    let result = shell(&device, serial, &command);
+    let _ = client.shell(serial, &format!("rm -f {}", shell_quote(&remote_tar)));
+    result.map_err(|e| format!("Restore failed: {}", e))?;
+
+    let mut message = format!(
+        "✅ Restored {} (backed up from {} {} on {})",
+        package, manifest.device_model, manifest.device_serial, manifest.created
+    );
+    if version_code != manifest.version_code {
+        message.push_str(&format!(
+            "\n⚠️ Installed versionCode {} differs from the backup's {}",
+            version_code, manifest.version_code
+        ));
+    }
+    Ok(message)
+}
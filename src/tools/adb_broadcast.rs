@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::tools::adb_client::AdbClient;
+use crate::tools::adb_jobs::JobContext;
+
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BroadcastView {
+    #[default]
+    Table,
+    SideBySide,
+}
+
+impl BroadcastView {
+    pub fn all() -> [Self; 2] {
+        [Self::Table, Self::SideBySide]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Table => "Table",
+            Self::SideBySide => "Side by side",
+        }
+    }
+}
+
+/// The command's outcome on one device
+#[derive(Debug, Clone)]
+pub struct BroadcastResult {
+    pub serial: String,
+    pub model: String,
+    /// `None` when the device only speaks the legacy shell protocol, or the command never ran
+    pub exit_code: Option<u8>,
+    pub duration: Duration,
+    pub stdout: String,
+    pub stderr: String,
+    /// Transport failure; the command did not run
+    pub error: Option<String>,
+    /// Output differs from the majority of devices
+    pub differs: bool,
+}
+
+impl BroadcastResult {
+    pub fn success(&self) -> bool {
+        self.error.is_none() && self.exit_code.is_none_or(|code| code == 0)
+    }
+
+    /// stdout followed by stderr, or the transport error
+    pub fn output(&self) -> String {
+        if let Some(error) = &self.error {
+            return error.clone();
+        }
+        match (self.stdout.trim_end().is_empty(), self.stderr.trim_end().is_empty()) {
+            (_, true) => self.stdout.trim_end().to_string(),
+            (true, false) => self.stderr.trim_end().to_string(),
+            (false, false) => format!("{}\n{}", self.stdout.trim_end(), self.stderr.trim_end()),
+        }
+    }
+
+    /// What two devices must share to count as giving the same answer
+    fn comparison_key(&self) -> (Option<u8>, String) {
+        (self.exit_code, self.output())
+    }
+}
+
+/// One command run on several devices
+#[derive(Debug, Clone)]
+pub struct BroadcastRun {
+    pub command: String,
+    pub results: Vec<BroadcastResult>,
+}
+
+impl BroadcastRun {
+    /// Sort results by serial and flag those outside the largest group of identical outputs.
+    /// When the largest groups tie there is no majority and every device is flagged.
+    pub fn new(command: String, mut results: Vec<BroadcastResult>) -> Self {
+        results.sort_by(|a, b| a.serial.cmp(&b.serial));
+
+        let mut groups: HashMap<(Option<u8>, String), usize> = HashMap::new();
+        for result in &results {
+            *groups.entry(result.comparison_key()).or_default() += 1;
+        }
+        let largest = groups.values().copied().max().unwrap_or(0);
+        let mut leaders = groups.iter().filter(|(_, count)| **count == largest).map(|(key, _)| key);
+        let majority = match (leaders.next(), leaders.next()) {
+            (Some(key), None) => Some(key.clone()),
+            _ => None,
+        };
+
+        for result in &mut results {
+            result.differs = majority.as_ref() != Some(&result.comparison_key());
+        }
+        Self { command, results }
+    }
+
+    pub fn failed_count(&self) -> usize {
+        self.results.iter().filter(|r| !r.success()).count()
+    }
+
+    pub fn differing_count(&self) -> usize {
+        self.results.iter().filter(|r| r.differs).count()
+    }
+}
+
+fn run_on_device(client: &AdbClient, serial: &str, model: &str, command: &str) -> BroadcastResult {
+    let started = Instant::now();
+    let output = client.shell_v2(serial, command);
+    let mut result = BroadcastResult {
+        serial: serial.to_string(),
+        model: model.to_string(),
+        exit_code: None,
+        duration: started.elapsed(),
+        stdout: String::new(),
+        stderr: String::new(),
+        error: None,
+        differs: false,
+    };
+    match output {
+        Ok(output) => {
+            result.exit_code = output.exit_code;
+            result.stdout = output.stdout_string();
+            result.stderr = output.stderr_string();
+        }
+        Err(e) => result.error = Some(e.to_string()),
+    }
+    result
+}
+
+/// Run `command` on every `(serial, model)` target at once, waiting for all of them
+pub fn run_broadcast<T>(
+    job: &JobContext<T>,
+    client: &AdbClient,
+    targets: Vec<(String, String)>,
+    command: &str,
+) -> Result<BroadcastRun, String> {
+    let total = targets.len();
+    let (sender, receiver) = mpsc::channel();
+    // Every worker shares the job's cancel token, so cancelling closes all their shells at once
+    let client = job.client(client);
+    for (serial, model) in targets {
+        let client = client.clone();
+        let command = command.to_string();
+        let sender = sender.clone();
+        thread::spawn(move || {
+            let _ = sender.send(run_on_device(&client, &serial, &model, &command));
+        });
+    }
+    drop(sender);
+
+    let mut results = Vec::with_capacity(total);
+    while results.len() < total {
+        if job.is_cancelled() {
+            return Err("Cancelled".to_string());
+        }
+        match receiver.recv_timeout(CANCEL_POLL_INTERVAL) {
+            Ok(result) => {
+                job.log(format!(
+                    "{}: {} in {:.1}s",
+                    result.serial,
+                    if result.success() { "done" } else { "failed" },
+                    result.duration.as_secs_f32()
+                ));
+                results.push(result);
+                job.set_progress(results.len() as f32 / total as f32);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    Ok(BroadcastRun::new(command.to_string(), results))
+}
@@ -0,0 +1,1018 @@
+use std::fs;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::tools::adb_tools::AdbDevice;
+
+pub type AdbResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const DEFAULT_ADB_HOST: &str = "127.0.0.1";
+const DEFAULT_ADB_PORT: u16 = 5037;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const SYNC_DATA_MAX: usize = 64 * 1024;
+
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
+/// Result of a `shell,v2` invocation with separated streams and exit code
+#[derive(Debug, Clone, Default)]
+pub struct ShellOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub exit_code: Option<u8>,
+}
+
+impl ShellOutput {
+    pub fn stdout_string(&self) -> String {
+        String::from_utf8_lossy(&self.stdout).to_string()
+    }
+
+    pub fn stderr_string(&self) -> String {
+        String::from_utf8_lossy(&self.stderr).to_string()
+    }
+
+    pub fn success(&self) -> bool {
+        self.exit_code.unwrap_or(0) == 0
+    }
+}
+
+/// A `FAIL` reply from the server, carrying its reason
+#[derive(Debug, Clone)]
+pub struct AdbServerError(pub String);
+
+impl std::fmt::Display for AdbServerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ADB server error: {}", self.0)
+    }
+}
+
+impl std::error::Error for AdbServerError {}
+
+/// Whether `error` means the device refused the service itself, rather than e.g. being offline.
+/// adbd closes the connection on services it does not know, which the server reports as `closed`.
+pub fn is_unsupported_service(error: &(dyn std::error::Error + Send + Sync + 'static)) -> bool {
+    error.downcast_ref::<AdbServerError>().is_some_and(|e| {
+        let reason = e.0.to_ascii_lowercase();
+        reason == "closed" || reason.contains("unknown service") || reason.contains("unsupported")
+    })
+}
+
+/// File metadata as reported by the sync `STAT`/`LIST` requests
+#[derive(Debug, Clone, Default)]
+pub struct RemoteStat {
+    pub mode: u32,
+    pub size: u32,
+    pub mtime: u32,
+}
+
+impl RemoteStat {
+    pub fn exists(&self) -> bool {
+        self.mode != 0
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.mode & S_IFMT == S_IFDIR
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.mode & S_IFMT == S_IFREG
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.mode & S_IFMT == S_IFLNK
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RemoteDirEntry {
+    pub name: String,
+    pub stat: RemoteStat,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TransferStats {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    next_id: AtomicU64,
+    streams: Mutex<HashMap<u64, TcpStream>>,
+}
+
+/// Cancellation shared between a job and the clients it uses.
+///
+/// Cancelling shuts down every connection currently watched, so reads blocked
+/// on the device return straight away instead of waiting for it to answer.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    state: Arc<CancelState>,
+}
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Relaxed);
+        let streams = std::mem::take(&mut *self.state.streams.lock().unwrap_or_else(|e| e.into_inner()));
+        for stream in streams.values() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Shut `stream` down on cancel for as long as the returned watch is alive
+    fn watch(&self, stream: &TcpStream) -> AdbResult<StreamWatch> {
+        let clone = stream.try_clone()?;
+        let id = self.state.next_id.fetch_add(1, Ordering::Relaxed);
+        self.state.streams.lock().unwrap_or_else(|e| e.into_inner()).insert(id, clone);
+        // A cancel racing the insert above would have missed this stream
+        if self.is_cancelled() {
+            let _ = stream.shutdown(Shutdown::Both);
+            return Err(cancelled_error());
+        }
+        Ok(StreamWatch { token: self.clone(), id })
+    }
+}
+
+/// Keeps a connection registered with a [`CancelToken`]; dropping it releases the socket clone
+#[derive(Debug)]
+pub struct StreamWatch {
+    token: CancelToken,
+    id: u64,
+}
+
+impl Drop for StreamWatch {
+    fn drop(&mut self) {
+        self.token.state.streams.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.id);
+    }
+}
+
+fn cancelled_error() -> Box<dyn std::error::Error + Send + Sync> {
+    "Cancelled".into()
+}
+
+/// Client for the ADB server's smart-socket protocol.
+///
+/// Every request opens a fresh connection to the server, so the client is
+/// cheap to clone and safe to hand to worker threads.
+#[derive(Debug, Clone)]
+pub struct AdbClient {
+    host: String,
+    port: u16,
+    cancel: Option<CancelToken>,
+}
+
+impl Default for AdbClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AdbClient {
+    pub fn new() -> Self {
+        let port = std::env::var("ANDROID_ADB_SERVER_PORT")
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(DEFAULT_ADB_PORT);
+
+        Self {
+            host: DEFAULT_ADB_HOST.to_string(),
+            port,
+            cancel: None,
+        }
+    }
+
+    /// A client whose requests fail fast, and whose open connections are shut down, once `token` is cancelled
+    pub fn with_cancel(&self, token: CancelToken) -> Self {
+        Self {
+            cancel: Some(token),
+            ..self.clone()
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+    }
+
+    fn check_cancelled(&self) -> AdbResult<()> {
+        if self.is_cancelled() {
+            Err(cancelled_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Register a stream from [`open_service`](Self::open_service) with this client's cancel token, if any
+    pub fn watch(&self, stream: &TcpStream) -> AdbResult<Option<StreamWatch>> {
+        self.cancel.as_ref().map(|token| token.watch(stream)).transpose()
+    }
+
+    /// A shutdown surfaces as an I/O error or an early end of stream; report it as the cancellation it was
+    fn finish<T>(&self, result: AdbResult<T>) -> AdbResult<T> {
+        self.check_cancelled()?;
+        result
+    }
+
+    fn socket_addr(&self) -> AdbResult<SocketAddr> {
+        (self.host.as_str(), self.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| format!("Cannot resolve ADB server address {}:{}", self.host, self.port).into())
+    }
+
+    /// Open a connection to the server, starting it through the `adb` binary if needed
+    fn connect(&self) -> AdbResult<TcpStream> {
+        self.check_cancelled()?;
+        let addr = self.socket_addr()?;
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(stream) => Ok(stream),
+            Err(first_err) => {
+                log::info!("ADB server not reachable ({}), trying `adb start-server`", first_err);
+                let started = Command::new("adb")
+                    .arg("start-server")
+                    .output()
+                    .map(|o| o.status.success())
+                    .unwrap_or(false);
+                if !started {
+                    return Err(format!("ADB server not running on {}: {}", addr, first_err).into());
+                }
+                Ok(TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?)
+            }
+        }
+    }
+
+    fn send_request(stream: &mut TcpStream, request: &str) -> AdbResult<()> {
+        let message = format!("{:04x}{}", request.len(), request);
+        stream.write_all(message.as_bytes())?;
+        Ok(())
+    }
+
+    fn read_length_prefixed(stream: &mut TcpStream) -> AdbResult<String> {
+        let mut len_buf = [0u8; 4];
+        stream.read_exact(&mut len_buf)?;
+        let len = usize::from_str_radix(std::str::from_utf8(&len_buf)?, 16)?;
+        let mut payload = vec![0u8; len];
+        stream.read_exact(&mut payload)?;
+        Ok(String::from_utf8_lossy(&payload).to_string())
+    }
+
+    fn read_status(stream: &mut TcpStream) -> AdbResult<()> {
+        let mut status = [0u8; 4];
+        stream.read_exact(&mut status)?;
+        match &status {
+            b"OKAY" => Ok(()),
+            b"FAIL" => Err(Box::new(AdbServerError(Self::read_length_prefixed(stream)?))),
+            other => Err(format!("Unexpected ADB response: {:?}", String::from_utf8_lossy(other)).into()),
+        }
+    }
+
+    /// Run a `host:` service and return its length-prefixed reply
+    pub fn host_query(&self, service: &str) -> AdbResult<String> {
+        let mut stream = self.connect()?;
+        let _watch = self.watch(&stream)?;
+        let reply = Self::send_request(&mut stream, service)
+            .and_then(|_| Self::read_status(&mut stream))
+            .and_then(|_| Self::read_length_prefixed(&mut stream));
+        self.finish(reply)
+    }
+
+    /// List devices known to the server (`host:devices-l`)
+    pub fn devices(&self) -> AdbResult<Vec<AdbDevice>> {
+        let output = self.host_query("host:devices-l")?;
+        Ok(parse_device_list(&output))
+    }
+
+    /// Open a connection switched to the given device's transport
+    pub fn open_transport(&self, serial: &str) -> AdbResult<TcpStream> {
+        let mut stream = self.connect()?;
+        let _watch = self.watch(&stream)?;
+        let status = Self::send_request(&mut stream, &format!("host:transport:{}", serial))
+            .and_then(|_| Self::read_status(&mut stream));
+        self.finish(status)?;
+        Ok(stream)
+    }
+
+    /// Open a device service (e.g. `shell:`, `exec:`, `sync:`) and return the raw stream
+    pub fn open_service(&self, serial: &str, service: &str) -> AdbResult<TcpStream> {
+        let mut stream = self.open_transport(serial)?;
+        let _watch = self.watch(&stream)?;
+        let status = Self::send_request(&mut stream, service).and_then(|_| Self::read_status(&mut stream));
+        self.finish(status)?;
+        Ok(stream)
+    }
+
+    /// Run a shell command, returning stdout; fails with stderr on a non-zero exit code
+    pub fn shell(&self, serial: &str, command: &str) -> AdbResult<String> {
+        let output = self.shell_v2(serial, command)?;
+        if output.success() {
+            Ok(output.stdout_string())
+        } else {
+            let stderr = output.stderr_string();
+            let message = if stderr.trim().is_empty() { output.stdout_string() } else { stderr };
+            Err(format!(
+                "Shell command exited with code {}: {}",
+                output.exit_code.unwrap_or(0),
+                message.trim()
+            )
+            .into())
+        }
+    }
+
+    /// Run a shell command using the `shell,v2` protocol, falling back to legacy `shell:`
+    /// on devices that do not offer it
+    pub fn shell_v2(&self, serial: &str, command: &str) -> AdbResult<ShellOutput> {
+        let mut stream = match self.open_service(serial, &format!("shell,v2,raw:{}", command)) {
+            Ok(stream) => stream,
+            Err(e) if !is_unsupported_service(e.as_ref()) => return Err(e),
+            Err(_) => {
+                let mut stream = self.open_service(serial, &format!("shell:{}", command))?;
+                let _watch = self.watch(&stream)?;
+                let mut stdout = Vec::new();
+                let read = stream.read_to_end(&mut stdout).map_err(Into::into);
+                self.finish(read)?;
+                return Ok(ShellOutput {
+                    stdout,
+                    stderr: Vec::new(),
+                    exit_code: None,
+                });
+            }
+        };
+
+        let _watch = self.watch(&stream)?;
+        let output = self.read_shell_v2(&mut stream);
+        self.finish(output)
+    }
+
+    fn read_shell_v2(&self, stream: &mut TcpStream) -> AdbResult<ShellOutput> {
+        let mut output = ShellOutput::default();
+        let mut header = [0u8; 5];
+        while !self.is_cancelled() {
+            match stream.read_exact(&mut header) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e.into()),
+            }
+            let len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]) as usize;
+            let mut data = vec![0u8; len];
+            stream.read_exact(&mut data)?;
+            match header[0] {
+                1 => output.stdout.extend_from_slice(&data),
+                2 => output.stderr.extend_from_slice(&data),
+                3 => {
+                    output.exit_code = data.first().copied();
+                    break;
+                }
+                _ => {}
+            }
+        }
+        Ok(output)
+    }
+
+    /// Run a command through `exec:` and return its raw, unmangled output
+    pub fn exec(&self, serial: &str, command: &str) -> AdbResult<Vec<u8>> {
+        let mut stream = self.open_service(serial, &format!("exec:{}", command))?;
+        let _watch = self.watch(&stream)?;
+        let mut data = Vec::new();
+        let read = stream.read_to_end(&mut data).map_err(Into::into);
+        self.finish(read)?;
+        Ok(data)
+    }
+
+    /// Run a `forward:`/`killforward:` request for one device through the server
+    pub fn host_forward(&self, serial: &str, request: &str) -> AdbResult<Option<String>> {
+        let mut stream = self.connect()?;
+        Self::send_request(&mut stream, &format!("host-serial:{}:{}", serial, request))?;
+        Self::read_status(&mut stream)?;
+        Self::read_forward_reply(stream)
+    }
+
+    /// Run a `reverse:` request on the device, which answers like its host-side counterpart
+    pub fn reverse_forward(&self, serial: &str, request: &str) -> AdbResult<Option<String>> {
+        let stream = self.open_service(serial, &format!("reverse:{}", request))?;
+        Self::read_forward_reply(stream)
+    }
+
+    /// List the device's reverse forwards. Unlike the other `reverse:` requests, adbd sends the
+    /// listing straight after opening the stream, without a status of its own.
+    pub fn list_reverse_forwards(&self, serial: &str) -> AdbResult<String> {
+        let mut stream = self.open_service(serial, "reverse:list-forward")?;
+        let _watch = self.watch(&stream)?;
+        let listing = Self::read_length_prefixed(&mut stream);
+        self.finish(listing)
+    }
+
+    /// The forward handler's own status, then an optional payload (a listing or an allocated port)
+    fn read_forward_reply(mut stream: TcpStream) -> AdbResult<Option<String>> {
+        Self::read_status(&mut stream)?;
+        Ok(Self::read_length_prefixed(&mut stream).ok())
+    }
+
+    /// Open a sync session on the given device
+    pub fn sync(&self, serial: &str) -> AdbResult<SyncConnection> {
+        let stream = self.open_service(serial, "sync:")?;
+        let watch = self.watch(&stream)?;
+        Ok(SyncConnection {
+            stream,
+            cancel: self.cancel.clone(),
+            _watch: watch,
+        })
+    }
+
+    pub fn stat(&self, serial: &str, remote: &str) -> AdbResult<RemoteStat> {
+        let mut sync = self.sync(serial)?;
+        let stat = sync.stat(remote)?;
+        sync.quit();
+        Ok(stat)
+    }
+
+    /// Push a local file or directory, mirroring `adb push` target resolution
+    pub fn push(&self, serial: &str, local: &Path, remote: &str) -> AdbResult<TransferStats> {
+        let mut sync = self.sync(serial)?;
+        let mut stats = TransferStats::default();
+
+        let local_name = local
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| format!("Invalid local path: {}", local.display()))?;
+        let target = if remote.ends_with('/') || sync.stat(remote)?.is_dir() {
+            remote_join(remote, &local_name)
+        } else {
+            remote.to_string()
+        };
+
+        if local.is_dir() {
+            sync.push_dir(local, &target, &mut stats)?;
+        } else {
+            stats.bytes += sync.send_file(local, &target)?;
+            stats.files += 1;
+        }
+
+        sync.quit();
+        Ok(stats)
+    }
+
+    /// Pull a remote file or directory, mirroring `adb pull` target resolution
+    pub fn pull(&self, serial: &str, remote: &str, local: &Path) -> AdbResult<TransferStats> {
+        let mut sync = self.sync(serial)?;
+        let mut stats = TransferStats::default();
+
+        let remote_stat = sync.stat(remote)?;
+        if !remote_stat.exists() {
+            return Err(format!("Remote object '{}' does not exist", remote).into());
+        }
+
+        let remote_name = remote
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or(remote)
+            .to_string();
+        let target = if local.is_dir() {
+            local.join(&remote_name)
+        } else {
+            local.to_path_buf()
+        };
+
+        if remote_stat.is_dir() {
+            sync.pull_dir(remote, &target, &mut stats)?;
+        } else {
+            stats.bytes += sync.recv_file(remote, &target)?;
+            stats.files += 1;
+        }
+
+        sync.quit();
+        Ok(stats)
+    }
+}
+
+/// An open `sync:` session. Requests are `ID` + little-endian length + payload.
+pub struct SyncConnection {
+    stream: TcpStream,
+    cancel: Option<CancelToken>,
+    _watch: Option<StreamWatch>,
+}
+
+impl SyncConnection {
+    fn check_cancelled(&self) -> AdbResult<()> {
+        if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            Err(cancelled_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Every transfer chunk passes through here and [`write_all`](Self::write_all), so both
+    /// stop a cancelled session between chunks and report its shutdown as a cancellation
+    fn read_exact(&mut self, buf: &mut [u8]) -> AdbResult<()> {
+        self.check_cancelled()?;
+        let read = self.stream.read_exact(buf);
+        self.check_cancelled()?;
+        Ok(read?)
+    }
+
+    fn write_all(&mut self, data: &[u8]) -> AdbResult<()> {
+        self.check_cancelled()?;
+        let written = self.stream.write_all(data);
+        self.check_cancelled()?;
+        Ok(written?)
+    }
+
+    fn send_request(&mut self, id: &[u8; 4], payload: &[u8]) -> AdbResult<()> {
+        let mut message = Vec::with_capacity(8 + payload.len());
+        message.extend_from_slice(id);
+        message.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        message.extend_from_slice(payload);
+        self.write_all(&message)
+    }
+
+    fn read_u32(&mut self) -> AdbResult<u32> {
+        let mut buf = [0u8; 4];
+        self.read_exact(&mut buf)?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn read_id(&mut self) -> AdbResult<[u8; 4]> {
+        let mut id = [0u8; 4];
+        self.read_exact(&mut id)?;
+        Ok(id)
+    }
+
+    fn read_fail_message(&mut self) -> AdbResult<String> {
+        let len = self.read_u32()? as usize;
+        let mut message = vec![0u8; len];
+        self.read_exact(&mut message)?;
+        Ok(String::from_utf8_lossy(&message).to_string())
+    }
+
+    pub fn stat(&mut self, remote: &str) -> AdbResult<RemoteStat> {
+        self.send_request(b"STAT", remote.as_bytes())?;
+        let id = self.read_id()?;
+        if &id != b"STAT" {
+            return Err(format!("Unexpected sync response to STAT: {:?}", String::from_utf8_lossy(&id)).into());
+        }
+        Ok(RemoteStat {
+            mode: self.read_u32()?,
+            size: self.read_u32()?,
+            mtime: self.read_u32()?,
+        })
+    }
+
+    pub fn list(&mut self, remote: &str) -> AdbResult<Vec<RemoteDirEntry>> {
+        self.send_request(b"LIST", remote.as_bytes())?;
+        let mut entries = Vec::new();
+        loop {
+            let id = self.read_id()?;
+            let stat = RemoteStat {
+                mode: self.read_u32()?,
+                size: self.read_u32()?,
+                mtime: self.read_u32()?,
+            };
+            let name_len = self.read_u32()? as usize;
+            match &id {
+                b"DENT" => {
+                    let mut name = vec![0u8; name_len];
+                    self.read_exact(&mut name)?;
+                    let name = String::from_utf8_lossy(&name).to_string();
+                    if name != "." && name != ".." {
+                        entries.push(RemoteDirEntry { name, stat });
+                    }
+                }
+                b"DONE" => break,
+                other => {
+                    return Err(format!("Unexpected sync response to LIST: {:?}", String::from_utf8_lossy(other)).into());
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Send one local file to `remote`; the device creates missing parent directories
+    pub fn send_file(&mut self, local: &Path, remote: &str) -> AdbResult<u64> {
+        let mut file = fs::File::open(local)?;
+        let metadata = file.metadata()?;
+        let mode = local_file_mode(&metadata);
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as u32)
+            .unwrap_or(0);
+
+        self.send_request(b"SEND", format!("{},{}", remote, mode).as_bytes())?;
+
+        let mut buffer = vec![0u8; SYNC_DATA_MAX];
+        let mut total = 0u64;
+        loop {
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            self.send_request(b"DATA", &buffer[..read])?;
+            total += read as u64;
+        }
+
+        self.write_all(b"DONE")?;
+        self.write_all(&mtime.to_le_bytes())?;
+
+        let id = self.read_id()?;
+        match &id {
+            b"OKAY" => {
+                self.read_u32()?;
+                Ok(total)
+            }
+            b"FAIL" => Err(format!("Push of '{}' failed: {}", remote, self.read_fail_message()?).into()),
+            other => Err(format!("Unexpected sync response to SEND: {:?}", String::from_utf8_lossy(other)).into()),
+        }
+    }
+
+    /// Receive one remote file into `local`, creating parent directories as needed
+    pub fn recv_file(&mut self, remote: &str, local: &Path) -> AdbResult<u64> {
+        if let Some(parent) = local.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        self.send_request(b"RECV", remote.as_bytes())?;
+        let mut file = fs::File::create(local)?;
+        let mut total = 0u64;
+        loop {
+            let id = self.read_id()?;
+            match &id {
+                b"DATA" => {
+                    let len = self.read_u32()? as usize;
+                    let mut data = vec![0u8; len];
+                    self.read_exact(&mut data)?;
+                    file.write_all(&data)?;
+                    total += len as u64;
+                }
+                b"DONE" => {
+                    self.read_u32()?;
+                    break;
+                }
+                b"FAIL" => {
+                    let message = self.read_fail_message()?;
+                    drop(file);
+                    let _ = fs::remove_file(local);
+                    return Err(format!("Pull of '{}' failed: {}", remote, message).into());
+                }
+                other => {
+                    return Err(format!("Unexpected sync response to RECV: {:?}", String::from_utf8_lossy(other)).into());
+                }
+            }
+        }
+        Ok(total)
+    }
+
+    fn push_dir(&mut self, local: &Path, remote: &str, stats: &mut TransferStats) -> AdbResult<()> {
+        for entry in fs::read_dir(local)? {
+            let entry = entry?;
+            let path = entry.path();
+            let target = remote_join(remote, &entry.file_name().to_string_lossy());
+            if path.is_dir() {
+                self.push_dir(&path, &target, stats)?;
+            } else {
+                stats.bytes += self.send_file(&path, &target)?;
+                stats.files += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn pull_dir(&mut self, remote: &str, local: &Path, stats: &mut TransferStats) -> AdbResult<()> {
+        fs::create_dir_all(local)?;
+        for entry in self.list(remote)? {
+            let source = remote_join(remote, &entry.name);
+            let target: PathBuf = local.join(&entry.name);
+            if entry.stat.is_dir() {
+                self.pull_dir(&source, &target, stats)?;
+            } else if entry.stat.is_file() || entry.stat.is_symlink() {
+                stats.bytes += self.recv_file(&source, &target)?;
+                stats.files += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// End the session; errors are irrelevant since the socket is dropped anyway
+    pub fn quit(mut self) {
+        let _ = self.send_request(b"QUIT", &[]);
+    }
+}
+
+/// Parse `host:devices-l` (or `adb devices -l`) output into devices
+pub fn parse_device_list(output: &str) -> Vec<AdbDevice> {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with("List of devices") && !line.starts_with('*'))
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 2 {
+                return None;
+            }
+            Some(AdbDevice {
+                id: parts[0].to_string(),
+                status: parts[1].to_string(),
+                model: extract_device_property(line, "model:"),
+                product: extract_device_property(line, "product:"),
+                transport_id: extract_device_property(line, "transport_id:"),
+            })
+        })
+        .collect()
+}
+
+fn extract_device_property(line: &str, property: &str) -> String {
+    line.split_whitespace()
+        .find_map(|part| part.strip_prefix(property))
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Quote an argument for the device's `sh`, leaving plain words untouched
+pub fn shell_quote(arg: &str) -> String {
+    let is_plain = !arg.is_empty()
+        && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c));
+    if is_plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+pub fn remote_join(dir: &str, name: &str) -> String {
+    if dir.ends_with('/') {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+#[cfg(unix)]
+fn local_file_mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    S_IFREG | (metadata.permissions().mode() & 0o777)
+}
+
+#[cfg(not(unix))]
+fn local_file_mode(_metadata: &fs::Metadata) -> u32 {
+    S_IFREG | 0o644
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    type Handler = Box<dyn FnOnce(&mut TcpStream) + Send>;
+
+    /// Serve one scripted handler per incoming connection, in order
+    fn fake_server(handlers: Vec<Handler>) -> (AdbClient, JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            for handler in handlers {
+                let (mut stream, _) = listener.accept().unwrap();
+                handler(&mut stream);
+            }
+        });
+        let client = AdbClient {
+            host: "127.0.0.1".to_string(),
+            port,
+            cancel: None,
+        };
+        (client, server)
+    }
+
+    fn read_request(stream: &mut TcpStream) -> String {
+        let mut len = [0u8; 4];
+        stream.read_exact(&mut len).unwrap();
+        let len = usize::from_str_radix(std::str::from_utf8(&len).unwrap(), 16).unwrap();
+        let mut payload = vec![0u8; len];
+        stream.read_exact(&mut payload).unwrap();
+        String::from_utf8(payload).unwrap()
+    }
+
+    fn expect_request(stream: &mut TcpStream, expected: &str) {
+        assert_eq!(read_request(stream), expected);
+    }
+
+    fn reply_fail(stream: &mut TcpStream, reason: &str) {
+        stream.write_all(format!("FAIL{:04x}{}", reason.len(), reason).as_bytes()).unwrap();
+    }
+
+    fn read_sync_request(stream: &mut TcpStream) -> ([u8; 4], Vec<u8>) {
+        let mut header = [0u8; 8];
+        stream.read_exact(&mut header).unwrap();
+        let id = [header[0], header[1], header[2], header[3]];
+        let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let mut payload = vec![0u8; len];
+        stream.read_exact(&mut payload).unwrap();
+        (id, payload)
+    }
+
+    fn shell_packet(id: u8, data: &[u8]) -> Vec<u8> {
+        let mut packet = vec![id];
+        packet.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        packet.extend_from_slice(data);
+        packet
+    }
+
+    /// Accept `host:transport:<serial>` and then expect `service`
+    fn open_service(stream: &mut TcpStream, serial: &str, service: &str) {
+        expect_request(stream, &format!("host:transport:{}", serial));
+        stream.write_all(b"OKAY").unwrap();
+        expect_request(stream, service);
+        stream.write_all(b"OKAY").unwrap();
+    }
+
+    #[test]
+    fn host_query_reads_length_prefixed_reply() {
+        let (client, server) = fake_server(vec![Box::new(|stream| {
+            expect_request(stream, "host:devices-l");
+            let body = "emulator-5554          device product:sdk model:Pixel transport_id:1\n";
+            stream.write_all(format!("OKAY{:04x}{}", body.len(), body).as_bytes()).unwrap();
+        })]);
+
+        let devices = client.devices().unwrap();
+        server.join().unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].id, "emulator-5554");
+        assert_eq!(devices[0].model, "Pixel");
+        assert_eq!(devices[0].transport_id, "1");
+    }
+
+    #[test]
+    fn fail_reply_carries_server_reason() {
+        let (client, server) = fake_server(vec![Box::new(|stream| {
+            expect_request(stream, "host:devices-l");
+            reply_fail(stream, "protocol fault");
+        })]);
+
+        let error = client.devices().unwrap_err();
+        server.join().unwrap();
+        assert_eq!(error.to_string(), "ADB server error: protocol fault");
+        assert!(!is_unsupported_service(error.as_ref()));
+    }
+
+    #[test]
+    fn reverse_listing_follows_the_stream_open_directly() {
+        let (client, server) = fake_server(vec![Box::new(|stream| {
+            open_service(stream, "serial1", "reverse:list-forward");
+            let body = "UsbFfs tcp:8081 tcp:8080\n";
+            stream.write_all(format!("{:04x}{}", body.len(), body).as_bytes()).unwrap();
+        })]);
+
+        let listing = client.list_reverse_forwards("serial1").unwrap();
+        server.join().unwrap();
+        assert_eq!(listing, "UsbFfs tcp:8081 tcp:8080\n");
+    }
+
+    #[test]
+    fn shell_v2_demultiplexes_streams_and_exit_code() {
+        let (client, server) = fake_server(vec![Box::new(|stream| {
+            open_service(stream, "serial1", "shell,v2,raw:id");
+            stream.write_all(&shell_packet(1, b"uid=2000")).unwrap();
+            stream.write_all(&shell_packet(2, b"warning")).unwrap();
+            stream.write_all(&shell_packet(3, &[1])).unwrap();
+        })]);
+
+        let output = client.shell_v2("serial1", "id").unwrap();
+        server.join().unwrap();
+        assert_eq!(output.stdout_string(), "uid=2000");
+        assert_eq!(output.stderr_string(), "warning");
+        assert_eq!(output.exit_code, Some(1));
+        assert!(!output.success());
+    }
+
+    #[test]
+    fn shell_v2_falls_back_when_service_is_unsupported() {
+        let (client, server) = fake_server(vec![
+            Box::new(|stream| {
+                expect_request(stream, "host:transport:old");
+                stream.write_all(b"OKAY").unwrap();
+                expect_request(stream, "shell,v2,raw:echo hi");
+                reply_fail(stream, "closed");
+            }),
+            Box::new(|stream| {
+                open_service(stream, "old", "shell:echo hi");
+                stream.write_all(b"hi\n").unwrap();
+            }),
+        ]);
+
+        let output = client.shell_v2("old", "echo hi").unwrap();
+        server.join().unwrap();
+        assert_eq!(output.stdout_string(), "hi\n");
+        assert_eq!(output.exit_code, None);
+    }
+
+    #[test]
+    fn shell_v2_reports_transport_errors_without_fallback() {
+        // A second connection would never be accepted, so a retry would fail differently
+        let (client, server) = fake_server(vec![Box::new(|stream| {
+            expect_request(stream, "host:transport:gone");
+            reply_fail(stream, "device offline");
+        })]);
+
+        let error = client.shell_v2("gone", "id").unwrap_err();
+        server.join().unwrap();
+        assert_eq!(error.to_string(), "ADB server error: device offline");
+    }
+
+    #[test]
+    fn cancel_interrupts_a_blocked_shell() {
+        let (release, released) = std::sync::mpsc::channel::<()>();
+        let (client, server) = fake_server(vec![Box::new(move |stream| {
+            open_service(stream, "serial1", "shell,v2,raw:sleep 60");
+            // Never answer; the client has to give up on its own
+            let _ = released.recv();
+        })]);
+
+        let token = CancelToken::default();
+        let client = client.with_cancel(token.clone());
+        let shell = thread::spawn(move || client.shell_v2("serial1", "sleep 60"));
+        thread::sleep(Duration::from_millis(100));
+        token.cancel();
+
+        let error = shell.join().unwrap().unwrap_err();
+        release.send(()).unwrap();
+        server.join().unwrap();
+        assert_eq!(error.to_string(), "Cancelled");
+    }
+
+    #[test]
+    fn sync_stat_and_recv_use_sync_packets() {
+        let (client, server) = fake_server(vec![Box::new(|stream| {
+            open_service(stream, "serial1", "sync:");
+
+            let (id, path) = read_sync_request(stream);
+            assert_eq!(&id, b"STAT");
+            assert_eq!(path, b"/sdcard/a.txt");
+            stream.write_all(b"STAT").unwrap();
+            for value in [S_IFREG | 0o644, 5, 1_700_000_000] {
+                stream.write_all(&u32::to_le_bytes(value)).unwrap();
+            }
+
+            let (id, path) = read_sync_request(stream);
+            assert_eq!(&id, b"RECV");
+            assert_eq!(path, b"/sdcard/a.txt");
+            stream.write_all(b"DATA").unwrap();
+            stream.write_all(&3u32.to_le_bytes()).unwrap();
+            stream.write_all(b"hel").unwrap();
+            stream.write_all(b"DATA").unwrap();
+            stream.write_all(&2u32.to_le_bytes()).unwrap();
+            stream.write_all(b"lo").unwrap();
+            stream.write_all(b"DONE").unwrap();
+            stream.write_all(&0u32.to_le_bytes()).unwrap();
+
+            let (id, _) = read_sync_request(stream);
+            assert_eq!(&id, b"QUIT");
+        })]);
+
+        let local = std::env::temp_dir().join(format!("ohmytoolboxs_sync_test_{}.txt", std::process::id()));
+        let mut sync = client.sync("serial1").unwrap();
+        let stat = sync.stat("/sdcard/a.txt").unwrap();
+        let bytes = sync.recv_file("/sdcard/a.txt", &local).unwrap();
+        sync.quit();
+        server.join().unwrap();
+
+        assert!(stat.is_file());
+        assert_eq!(stat.size, 5);
+        assert_eq!(bytes, 5);
+        assert_eq!(fs::read_to_string(&local).unwrap(), "hello");
+        let _ = fs::remove_file(&local);
+    }
+
+    #[test]
+    fn sync_recv_fail_removes_partial_file() {
+        let (client, server) = fake_server(vec![Box::new(|stream| {
+            open_service(stream, "serial1", "sync:");
+            let (id, _) = read_sync_request(stream);
+            assert_eq!(&id, b"RECV");
+            let reason = b"No such file or directory";
+            stream.write_all(b"FAIL").unwrap();
+            stream.write_all(&(reason.len() as u32).to_le_bytes()).unwrap();
+            stream.write_all(reason).unwrap();
+        })]);
+
+        let local = std::env::temp_dir().join(format!("ohmytoolboxs_sync_fail_{}.txt", std::process::id()));
+        let mut sync = client.sync("serial1").unwrap();
+        let error = sync.recv_file("/sdcard/missing", &local).unwrap_err();
+        server.join().unwrap();
+
+        assert!(error.to_string().contains("No such file or directory"));
+        assert!(!local.exists());
+    }
+}
@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+
+const MAX_HISTORY: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteFileType {
+    Directory,
+    File,
+    Symlink,
+    CharDevice,
+    BlockDevice,
+    Pipe,
+    Socket,
+    Other,
+}
+
+impl RemoteFileType {
+    fn from_mode_char(c: char) -> Self {
+        match c {
+            'd' => Self::Directory,
+            '-' => Self::File,
+            'l' => Self::Symlink,
+            'c' => Self::CharDevice,
+            'b' => Self::BlockDevice,
+            'p' => Self::Pipe,
+            's' => Self::Socket,
+            _ => Self::Other,
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Self::Directory => "📁",
+            Self::File => "📄",
+            Self::Symlink => "🔗",
+            Self::CharDevice | Self::BlockDevice => "⚙",
+            Self::Pipe | Self::Socket => "🔌",
+            Self::Other => "❔",
+        }
+    }
+}
+
+/// One line of `ls -la`
+#[derive(Debug, Clone)]
+pub struct RemoteFileEntry {
+    pub name: String,
+    pub file_type: RemoteFileType,
+    /// Missing for device nodes (which show `major, minor`) and on toolbox `ls` for directories
+    pub size: Option<u64>,
+    pub permissions: String,
+    pub owner: String,
+    pub group: String,
+    pub modified: String,
+    pub link_target: Option<String>,
+}
+
+impl RemoteFileEntry {
+    /// Whether clicking the entry should navigate into it
+    pub fn is_browsable(&self) -> bool {
+        matches!(self.file_type, RemoteFileType::Directory | RemoteFileType::Symlink)
+    }
+}
+
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+fn is_iso_date(token: &str) -> bool {
+    let bytes = token.as_bytes();
+    bytes.len() == 10
+        && bytes[4] == b'-'
+        && bytes[7] == b'-'
+        && bytes.iter().enumerate().all(|(i, b)| i == 4 || i == 7 || b.is_ascii_digit())
+}
+
+/// Parse `ls -la` output from toybox, toolbox or busybox `ls`
+pub fn parse_ls_la(output: &str) -> Vec<RemoteFileEntry> {
+    output.lines().filter_map(parse_ls_line).collect()
+}
+
+fn parse_ls_line(line: &str) -> Option<RemoteFileEntry> {
+    let line = line.trim_end();
+    let tokens: Vec<(usize, &str)> = line
+        .split_whitespace()
+        .map(|token| (token.as_ptr() as usize - line.as_ptr() as usize, token))
+        .collect();
+
+    let permissions = tokens.first()?.1;
+    if permissions.len() < 10 || line.starts_with("total ") {
+        return None;
+    }
+    let file_type = RemoteFileType::from_mode_char(permissions.chars().next()?);
+
+    // The date is either `YYYY-MM-DD HH:MM` or `Mon DD HH:MM|YYYY`; everything after it is the name
+    let (date_index, date_len) = tokens.iter().enumerate().skip(1).find_map(|(i, (_, token))| {
+        if is_iso_date(token) {
+            Some((i, 2))
+        } else if MONTHS.contains(token) && tokens.len() > i + 3 {
+            Some((i, 3))
+        } else {
+            None
+        }
+    })?;
+    let name_index = date_index + date_len;
+    let name_start = tokens.get(name_index)?.0;
+    let last_date_token = tokens[name_index - 1];
+    let modified = &line[tokens[date_index].0..last_date_token.0 + last_date_token.1.len()];
+
+    // Between the permissions and the date: [links] owner group [size | major, minor]
+    let mut middle: Vec<&str> = tokens[1..date_index].iter().map(|(_, t)| *t).collect();
+    if middle.len() >= 3 && middle[0].chars().all(|c| c.is_ascii_digit()) {
+        middle.remove(0);
+    }
+    let owner = middle.first().copied().unwrap_or_default();
+    let group = middle.get(1).copied().unwrap_or_default();
+    let size = match middle.get(2..) {
+        Some([size]) => size.parse().ok(),
+        _ => None,
+    };
+
+    let full_name = &line[name_start..];
+    let (name, link_target) = match (file_type, full_name.split_once(" -> ")) {
+        (RemoteFileType::Symlink, Some((name, target))) => (name, Some(target.to_string())),
+        _ => (full_name, None),
+    };
+    if name == "." || name == ".." {
+        return None;
+    }
+
+    Some(RemoteFileEntry {
+        name: name.to_string(),
+        file_type,
+        size,
+        permissions: permissions.to_string(),
+        owner: owner.to_string(),
+        group: group.to_string(),
+        modified: modified.to_string(),
+        link_target,
+    })
+}
+
+/// Parent of a remote directory, `/` being its own parent
+pub fn remote_parent(path: &str) -> String {
+    let trimmed = path.trim_end_matches('/');
+    match trimmed.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(pos) => trimmed[..pos].to_string(),
+    }
+}
+
+/// `(label, path)` pairs for each component of a remote path, starting at `/`
+pub fn remote_breadcrumbs(path: &str) -> Vec<(String, String)> {
+    let mut crumbs = vec![("/".to_string(), "/".to_string())];
+    let mut current = String::new();
+    for component in path.split('/').filter(|c| !c.is_empty()) {
+        current.push('/');
+        current.push_str(component);
+        crumbs.push((component.to_string(), current.clone()));
+    }
+    crumbs
+}
+
+/// Normalise a typed remote directory: absolute, no trailing slash except for `/`
+pub fn normalize_remote_dir(path: &str) -> String {
+    let path = path.trim();
+    let path = if path.starts_with('/') { path.to_string() } else { format!("/{}", path) };
+    match path.trim_end_matches('/') {
+        "" => "/".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// Back/forward history of the remote file browser, kept in the config
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteNavigation {
+    pub back: Vec<String>,
+    pub forward: Vec<String>,
+}
+
+impl RemoteNavigation {
+    /// Move `current` to `to`, recording where we came from
+    pub fn navigate(&mut self, current: &mut String, to: String) {
+        if *current == to {
+            return;
+        }
+        if !current.is_empty() {
+            self.back.push(std::mem::replace(current, to));
+            if self.back.len() > MAX_HISTORY {
+                self.back.remove(0);
+            }
+        } else {
+            *current = to;
+        }
+        self.forward.clear();
+    }
+
+    pub fn go_back(&mut self, current: &mut String) -> bool {
+        match self.back.pop() {
+            Some(previous) => {
+                self.forward.push(std::mem::replace(current, previous));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn go_forward(&mut self, current: &mut String) -> bool {
+        match self.forward.pop() {
+            Some(next) => {
+                self.back.push(std::mem::replace(current, next));
+                true
+            }
+            None => false,
+        }
+    }
+}
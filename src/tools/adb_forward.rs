@@ -0,0 +1,231 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::tools::adb_client::{is_unsupported_service, AdbClient};
+
+/// How often devices are checked for coming online, to re-apply auto-apply profiles
+pub const PROFILE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ForwardDirection {
+    /// Host socket to device socket (`adb forward`)
+    #[default]
+    Forward,
+    /// Device socket to host socket (`adb reverse`)
+    Reverse,
+}
+
+impl ForwardDirection {
+    pub fn all() -> [Self; 2] {
+        [Self::Forward, Self::Reverse]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Forward => "Forward (host → device)",
+            Self::Reverse => "Reverse (device → host)",
+        }
+    }
+
+    pub fn arrow(&self) -> &'static str {
+        match self {
+            Self::Forward => "→",
+            Self::Reverse => "←",
+        }
+    }
+}
+
+/// Socket types accepted in forward specs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SocketKind {
+    #[default]
+    Tcp,
+    LocalAbstract,
+    LocalReserved,
+    LocalFilesystem,
+    /// A debuggable process on the device, by PID
+    Jdwp,
+}
+
+impl SocketKind {
+    pub fn all() -> [Self; 5] {
+        [Self::Tcp, Self::LocalAbstract, Self::LocalReserved, Self::LocalFilesystem, Self::Jdwp]
+    }
+
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Self::Tcp => "tcp",
+            Self::LocalAbstract => "localabstract",
+            Self::LocalReserved => "localreserved",
+            Self::LocalFilesystem => "localfilesystem",
+            Self::Jdwp => "jdwp",
+        }
+    }
+
+    /// `jdwp:` only makes sense as the device end of a forward
+    pub fn allowed(&self, direction: ForwardDirection, device_side: bool) -> bool {
+        *self != Self::Jdwp || (device_side && direction == ForwardDirection::Forward)
+    }
+
+    /// Placeholder for the value after the prefix
+    pub fn hint(&self) -> &'static str {
+        match self {
+            Self::Tcp => "port",
+            Self::LocalAbstract | Self::LocalReserved => "socket name",
+            Self::LocalFilesystem => "socket path",
+            Self::Jdwp => "pid",
+        }
+    }
+
+    pub fn spec(&self, value: &str) -> String {
+        format!("{}:{}", self.prefix(), value.trim())
+    }
+}
+
+/// One forward or reverse, as listed by the server or stored in a profile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForwardRule {
+    pub direction: ForwardDirection,
+    /// Host end, e.g. `tcp:8080`
+    pub local: String,
+    /// Device end, e.g. `localabstract:chrome_devtools_remote`
+    pub remote: String,
+}
+
+impl ForwardRule {
+    pub fn describe(&self) -> String {
+        format!("{} {} {}", self.local, self.direction.arrow(), self.remote)
+    }
+}
+
+/// A named set of rules, applied on request or whenever a matching device comes online
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForwardProfile {
+    pub name: String,
+    /// Serial the profile is limited to; empty applies it to every device
+    pub device: String,
+    pub auto_apply: bool,
+    pub rules: Vec<ForwardRule>,
+}
+
+impl Default for ForwardProfile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            device: String::new(),
+            auto_apply: true,
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl ForwardProfile {
+    pub fn matches(&self, serial: &str) -> bool {
+        self.device.trim().is_empty() || self.device.trim() == serial
+    }
+}
+
+/// Parse `host:list-forward` (`<serial> <local> <remote>`), keeping one device's entries
+pub fn parse_forward_list(output: &str, serial: &str) -> Vec<ForwardRule> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let [owner, local, remote] = parts.as_slice() else {
+                return None;
+            };
+            (*owner == serial).then(|| ForwardRule {
+                direction: ForwardDirection::Forward,
+                local: local.to_string(),
+                remote: remote.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parse `reverse:list-forward` (`<transport> <device end> <host end>`)
+pub fn parse_reverse_list(output: &str) -> Vec<ForwardRule> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let [_, remote, local] = parts.as_slice() else {
+                return None;
+            };
+            Some(ForwardRule {
+                direction: ForwardDirection::Reverse,
+                local: local.to_string(),
+                remote: remote.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Everything the server and the device currently forward for `serial`
+pub fn list_forwards(client: &AdbClient, serial: &str) -> Result<Vec<ForwardRule>, String> {
+    let forwards = client.host_query("host:list-forward").map_err(|e| format!("forward --list failed: {}", e))?;
+    let mut rules = parse_forward_list(&forwards, serial);
+    match client.list_reverse_forwards(serial) {
+        Ok(reverses) => rules.extend(parse_reverse_list(&reverses)),
+        // Devices older than Android 5 have no reverse support; they simply list none
+        Err(e) if is_unsupported_service(e.as_ref()) => {}
+        Err(e) => return Err(format!("reverse --list failed: {}", e)),
+    }
+    Ok(rules)
+}
+
+/// Set up one rule; returns the port the server picked when the host end is `tcp:0`
+pub fn add_forward(client: &AdbClient, serial: &str, rule: &ForwardRule) -> Result<Option<String>, String> {
+    let reply = match rule.direction {
+        ForwardDirection::Forward => client.host_forward(serial, &format!("forward:{};{}", rule.local, rule.remote)),
+        ForwardDirection::Reverse => client.reverse_forward(serial, &format!("forward:{};{}", rule.remote, rule.local)),
+    };
+    let reply = reply.map_err(|e| format!("Failed to add {}: {}", rule.describe(), e))?;
+    Ok(reply.filter(|port| !port.trim().is_empty()))
+}
+
+pub fn remove_forward(client: &AdbClient, serial: &str, rule: &ForwardRule) -> Result<(), String> {
+    let reply = match rule.direction {
+        ForwardDirection::Forward => client.host_forward(serial, &format!("killforward:{}", rule.local)),
+        ForwardDirection::Reverse => client.reverse_forward(serial, &format!("killforward:{}", rule.remote)),
+    };
+    reply.map(|_| ()).map_err(|e| format!("Failed to remove {}: {}", rule.describe(), e))
+}
+
+/// Remove every rule of `direction` on `serial`, returning how many there were. The server's
+/// `killforward-all` would drop host forwards of every device, so those go one at a time.
+pub fn remove_all_forwards(client: &AdbClient, serial: &str, direction: ForwardDirection) -> Result<usize, String> {
+    match direction {
+        ForwardDirection::Forward => {
+            let rules: Vec<ForwardRule> = list_forwards(client, serial)?
+                .into_iter()
+                .filter(|rule| rule.direction == ForwardDirection::Forward)
+                .collect();
+            for rule in &rules {
+                remove_forward(client, serial, rule)?;
+            }
+            Ok(rules.len())
+        }
+        ForwardDirection::Reverse => {
+            let count = list_forwards(client, serial)?
+                .iter()
+                .filter(|rule| rule.direction == ForwardDirection::Reverse)
+                .count();
+            client.reverse_forward(serial, "killforward-all").map_err(|e| e.to_string())?;
+            Ok(count)
+        }
+    }
+}
+
+/// Apply every rule of a profile that is not already in place, returning how many were added
+pub fn apply_profile(client: &AdbClient, serial: &str, profile: &ForwardProfile) -> Result<usize, String> {
+    let existing = list_forwards(client, serial)?;
+    let mut added = 0;
+    for rule in profile.rules.iter().filter(|rule| !existing.contains(rule)) {
+        add_forward(client, serial, rule)?;
+        added += 1;
+    }
+    Ok(added)
+}
@@ -0,0 +1,480 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::tools::adb_client::{shell_quote, AdbClient};
+use crate::tools::adb_jobs::JobContext;
+
+const ANDROID_HEADER: &[u8] = b"JAVA PROFILE 1.0.3";
+const STANDARD_HEADER: &[u8] = b"JAVA PROFILE 1.0.2";
+
+/// The dump is complete once its size stops changing for this many checks in a row
+const STABLE_SIZE_CHECKS: u32 = 3;
+const SIZE_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+const DUMP_TIMEOUT: Duration = Duration::from_secs(300);
+
+// Top-level records
+const TAG_STRING: u8 = 0x01;
+const TAG_LOAD_CLASS: u8 = 0x02;
+const TAG_HEAP_DUMP: u8 = 0x0c;
+const TAG_HEAP_DUMP_SEGMENT: u8 = 0x1c;
+
+// Heap dump sub-records
+const ROOT_UNKNOWN: u8 = 0xff;
+const ROOT_JNI_GLOBAL: u8 = 0x01;
+const ROOT_JNI_LOCAL: u8 = 0x02;
+const ROOT_JAVA_FRAME: u8 = 0x03;
+const ROOT_NATIVE_STACK: u8 = 0x04;
+const ROOT_STICKY_CLASS: u8 = 0x05;
+const ROOT_THREAD_BLOCK: u8 = 0x06;
+const ROOT_MONITOR_USED: u8 = 0x07;
+const ROOT_THREAD_OBJECT: u8 = 0x08;
+const CLASS_DUMP: u8 = 0x20;
+const INSTANCE_DUMP: u8 = 0x21;
+const OBJECT_ARRAY_DUMP: u8 = 0x22;
+const PRIMITIVE_ARRAY_DUMP: u8 = 0x23;
+
+// Android additions, which standard tools reject
+const ANDROID_ROOT_INTERNED_STRING: u8 = 0x89;
+const ANDROID_ROOT_FINALIZING: u8 = 0x8a;
+const ANDROID_ROOT_DEBUGGER: u8 = 0x8b;
+const ANDROID_ROOT_REFERENCE_CLEANUP: u8 = 0x8c;
+const ANDROID_ROOT_VM_INTERNAL: u8 = 0x8d;
+const ANDROID_ROOT_JNI_MONITOR: u8 = 0x8e;
+const ANDROID_UNREACHABLE: u8 = 0x90;
+const ANDROID_PRIMITIVE_ARRAY_NODATA: u8 = 0xc3;
+const ANDROID_HEAP_DUMP_INFO: u8 = 0xfe;
+
+/// Instance count and shallow size of one class
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassHistogramEntry {
+    pub name: String,
+    pub instances: u64,
+    pub shallow_bytes: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct HeapDumpResult {
+    pub target: String,
+    /// The dump as written by the device
+    pub android_path: PathBuf,
+    /// Converted to standard hprof, for tools such as Eclipse MAT or VisualVM
+    pub path: PathBuf,
+    /// Largest shallow size first
+    pub histogram: Vec<ClassHistogramEntry>,
+}
+
+impl HeapDumpResult {
+    pub fn total_instances(&self) -> u64 {
+        self.histogram.iter().map(|e| e.instances).sum()
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.histogram.iter().map(|e| e.shallow_bytes).sum()
+    }
+}
+
+fn primitive_name(kind: u8) -> &'static str {
+    match kind {
+        4 => "boolean[]",
+        5 => "char[]",
+        6 => "float[]",
+        7 => "double[]",
+        8 => "byte[]",
+        9 => "short[]",
+        10 => "int[]",
+        11 => "long[]",
+        _ => "unknown[]",
+    }
+}
+
+fn value_size(kind: u8, id_size: usize) -> Result<usize, String> {
+    match kind {
+        2 => Ok(id_size),
+        4 | 8 => Ok(1),
+        5 | 9 => Ok(2),
+        6 | 10 => Ok(4),
+        7 | 11 => Ok(8),
+        _ => Err(format!("Unknown hprof value type {}", kind)),
+    }
+}
+
+/// Sizes of everything seen in the heap, keyed by class object or primitive type
+#[derive(Debug, Default)]
+struct Histogram {
+    classes: HashMap<u64, (u64, u64)>,
+    primitive_arrays: HashMap<u8, (u64, u64)>,
+}
+
+impl Histogram {
+    fn add_object(&mut self, class: u64, bytes: u64) {
+        let entry = self.classes.entry(class).or_default();
+        entry.0 += 1;
+        entry.1 += bytes;
+    }
+
+    fn add_primitive_array(&mut self, kind: u8, bytes: u64) {
+        let entry = self.primitive_arrays.entry(kind).or_default();
+        entry.0 += 1;
+        entry.1 += bytes;
+    }
+
+    fn entries(self, class_names: &HashMap<u64, u64>, strings: &HashMap<u64, String>) -> Vec<ClassHistogramEntry> {
+        let name_of = |class: u64| {
+            class_names
+                .get(&class)
+                .and_then(|name_id| strings.get(name_id))
+                .cloned()
+                .unwrap_or_else(|| format!("class@{:#x}", class))
+        };
+        let mut entries: Vec<ClassHistogramEntry> = self
+            .classes
+            .into_iter()
+            .map(|(class, (instances, shallow_bytes))| ClassHistogramEntry { name: name_of(class), instances, shallow_bytes })
+            .chain(self.primitive_arrays.into_iter().map(|(kind, (instances, shallow_bytes))| ClassHistogramEntry {
+                name: primitive_name(kind).to_string(),
+                instances,
+                shallow_bytes,
+            }))
+            .collect();
+        entries.sort_by(|a, b| b.shallow_bytes.cmp(&a.shallow_bytes).then_with(|| a.name.cmp(&b.name)));
+        entries
+    }
+}
+
+/// Bounds-checked reads over one record body
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+    id_size: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(data: &'a [u8], id_size: usize) -> Self {
+        Self { data, pos: 0, id_size }
+    }
+
+    fn is_done(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.data.len()).ok_or("Truncated heap dump record")?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), String> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn id(&mut self) -> Result<u64, String> {
+        let bytes = self.bytes(self.id_size)?;
+        Ok(bytes.iter().fold(0u64, |id, byte| (id << 8) | *byte as u64))
+    }
+
+    fn skip_ids(&mut self, count: usize) -> Result<(), String> {
+        self.skip(self.id_size * count)
+    }
+}
+
+/// Rewrite one heap dump body without Android records, counting objects on the way
+fn convert_heap_records(body: &[u8], id_size: usize, histogram: &mut Histogram) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(body.len());
+    let mut cursor = Cursor::new(body, id_size);
+    while !cursor.is_done() {
+        let start = cursor.pos;
+        let tag = cursor.u8()?;
+        match tag {
+            ROOT_UNKNOWN | ROOT_STICKY_CLASS | ROOT_MONITOR_USED => cursor.skip_ids(1)?,
+            ROOT_JNI_GLOBAL => cursor.skip_ids(2)?,
+            ROOT_JNI_LOCAL | ROOT_JAVA_FRAME | ROOT_THREAD_OBJECT => {
+                cursor.skip_ids(1)?;
+                cursor.skip(8)?;
+            }
+            ROOT_NATIVE_STACK | ROOT_THREAD_BLOCK => {
+                cursor.skip_ids(1)?;
+                cursor.skip(4)?;
+            }
+            ANDROID_ROOT_INTERNED_STRING
+            | ANDROID_ROOT_FINALIZING
+            | ANDROID_ROOT_DEBUGGER
+            | ANDROID_ROOT_REFERENCE_CLEANUP
+            | ANDROID_ROOT_VM_INTERNAL
+            | ANDROID_UNREACHABLE
+            | ANDROID_ROOT_JNI_MONITOR => {
+                let object = cursor.bytes(id_size)?;
+                if tag == ANDROID_ROOT_JNI_MONITOR {
+                    cursor.skip(8)?;
+                }
+                out.push(ROOT_UNKNOWN);
+                out.extend_from_slice(object);
+                continue;
+            }
+            ANDROID_HEAP_DUMP_INFO => {
+                // Which heap (app, image, zygote) the following objects live in; dropped
+                cursor.skip(4)?;
+                cursor.skip_ids(1)?;
+                continue;
+            }
+            CLASS_DUMP => {
+                cursor.skip_ids(1)?;
+                cursor.skip(4)?;
+                cursor.skip_ids(6)?;
+                cursor.skip(4)?;
+                for _ in 0..cursor.u16()? {
+                    cursor.skip(2)?;
+                    let kind = cursor.u8()?;
+                    cursor.skip(value_size(kind, id_size)?)?;
+                }
+                for _ in 0..cursor.u16()? {
+                    cursor.skip_ids(1)?;
+                    let kind = cursor.u8()?;
+                    cursor.skip(value_size(kind, id_size)?)?;
+                }
+                for _ in 0..cursor.u16()? {
+                    cursor.skip_ids(1)?;
+                    cursor.skip(1)?;
+                }
+            }
+            INSTANCE_DUMP => {
+                cursor.skip_ids(1)?;
+                cursor.skip(4)?;
+                let class = cursor.id()?;
+                let len = cursor.u32()? as usize;
+                cursor.skip(len)?;
+                histogram.add_object(class, len as u64);
+            }
+            OBJECT_ARRAY_DUMP => {
+                cursor.skip_ids(1)?;
+                cursor.skip(4)?;
+                let count = cursor.u32()? as usize;
+                let class = cursor.id()?;
+                cursor.skip_ids(count)?;
+                histogram.add_object(class, (count * id_size) as u64);
+            }
+            PRIMITIVE_ARRAY_DUMP => {
+                cursor.skip_ids(1)?;
+                cursor.skip(4)?;
+                let count = cursor.u32()? as usize;
+                let kind = cursor.u8()?;
+                let len = count * value_size(kind, id_size)?;
+                cursor.skip(len)?;
+                histogram.add_primitive_array(kind, len as u64);
+            }
+            ANDROID_PRIMITIVE_ARRAY_NODATA => {
+                // Written as a zero-filled array, like hprof-conv
+                let header = cursor.bytes(id_size + 4)?;
+                let count = cursor.u32()?;
+                let kind = cursor.u8()?;
+                let len = count as usize * value_size(kind, id_size)?;
+                out.push(PRIMITIVE_ARRAY_DUMP);
+                out.extend_from_slice(header);
+                out.extend_from_slice(&count.to_be_bytes());
+                out.push(kind);
+                out.resize(out.len() + len, 0);
+                histogram.add_primitive_array(kind, len as u64);
+                continue;
+            }
+            _ => return Err(format!("Unknown heap dump record 0x{:02x} at offset {}", tag, start)),
+        }
+        out.extend_from_slice(&body[start..cursor.pos]);
+    }
+    Ok(out)
+}
+
+fn read_record_header(reader: &mut impl Read) -> io::Result<Option<(u8, [u8; 4], u32)>> {
+    let mut tag = [0u8; 1];
+    if reader.read(&mut tag)? == 0 {
+        return Ok(None);
+    }
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header)?;
+    let time = [header[0], header[1], header[2], header[3]];
+    let len = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+    Ok(Some((tag[0], time, len)))
+}
+
+/// Convert an Android hprof to standard hprof and build a class histogram in the same pass.
+/// Files that are already standard are copied through unchanged.
+pub fn convert_hprof(input: &Path, output: &Path, mut progress: impl FnMut(f32)) -> Result<Vec<ClassHistogramEntry>, String> {
+    let io_error = |e: io::Error| format!("Failed to convert {}: {}", input.display(), e);
+    let file = File::open(input).map_err(io_error)?;
+    let file_size = file.metadata().map_err(io_error)?.len();
+    let total_size = file_size.max(1);
+    let mut reader = BufReader::new(file);
+    let mut writer = BufWriter::new(File::create(output).map_err(|e| format!("Failed to create {}: {}", output.display(), e))?);
+
+    let mut header = Vec::new();
+    loop {
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte).map_err(io_error)?;
+        if byte[0] == 0 {
+            break;
+        }
+        header.push(byte[0]);
+        if header.len() > 64 {
+            return Err(format!("{} is not an hprof file", input.display()));
+        }
+    }
+    if !header.starts_with(b"JAVA PROFILE") {
+        return Err(format!("{} is not an hprof file", input.display()));
+    }
+    let header = if header == ANDROID_HEADER { STANDARD_HEADER } else { &header[..] };
+    writer.write_all(header).map_err(io_error)?;
+    writer.write_all(&[0]).map_err(io_error)?;
+
+    let mut fields = [0u8; 12];
+    reader.read_exact(&mut fields).map_err(io_error)?;
+    writer.write_all(&fields).map_err(io_error)?;
+    let id_size = u32::from_be_bytes([fields[0], fields[1], fields[2], fields[3]]) as usize;
+    if !matches!(id_size, 1..=8) {
+        return Err(format!("Unsupported hprof identifier size {}", id_size));
+    }
+
+    let mut histogram = Histogram::default();
+    let mut strings: HashMap<u64, String> = HashMap::new();
+    let mut class_names: HashMap<u64, u64> = HashMap::new();
+    let mut position = (header.len() + 1 + fields.len()) as u64;
+    while let Some((tag, time, len)) = read_record_header(&mut reader).map_err(io_error)? {
+        // A corrupt length must not make us allocate more than the file could hold
+        position += 9;
+        if len as u64 > file_size.saturating_sub(position) {
+            return Err(format!(
+                "{} is truncated: record at offset {} claims {} bytes",
+                input.display(),
+                position - 9,
+                len
+            ));
+        }
+        let mut body = vec![0u8; len as usize];
+        reader.read_exact(&mut body).map_err(io_error)?;
+        position += len as u64;
+
+        let body = match tag {
+            TAG_HEAP_DUMP | TAG_HEAP_DUMP_SEGMENT => convert_heap_records(&body, id_size, &mut histogram)?,
+            TAG_STRING => {
+                let mut cursor = Cursor::new(&body, id_size);
+                let id = cursor.id()?;
+                strings.insert(id, String::from_utf8_lossy(&body[cursor.pos..]).to_string());
+                body
+            }
+            TAG_LOAD_CLASS => {
+                let mut cursor = Cursor::new(&body, id_size);
+                cursor.skip(4)?;
+                let class = cursor.id()?;
+                cursor.skip(4)?;
+                class_names.insert(class, cursor.id()?);
+                body
+            }
+            _ => body,
+        };
+        let len = u32::try_from(body.len()).map_err(|_| "Converted record is too large".to_string())?;
+        writer.write_all(&[tag]).map_err(io_error)?;
+        writer.write_all(&time).map_err(io_error)?;
+        writer.write_all(&len.to_be_bytes()).map_err(io_error)?;
+        writer.write_all(&body).map_err(io_error)?;
+        progress(position as f32 / total_size as f32);
+    }
+    writer.flush().map_err(io_error)?;
+    Ok(histogram.entries(&class_names, &strings))
+}
+
+/// Poll the dump's size until it stops growing; `am dumpheap` may return before ART is done
+fn wait_for_dump<T>(job: &JobContext<T>, client: &AdbClient, serial: &str, remote: &str) -> Result<u32, String> {
+    let started = Instant::now();
+    let mut last_size = 0;
+    let mut stable_checks = 0;
+    loop {
+        if job.is_cancelled() {
+            return Err("Cancelled".to_string());
+        }
+        if started.elapsed() > DUMP_TIMEOUT {
+            return Err(format!("Timed out waiting for {} to be written", remote));
+        }
+        // A file that doesn't exist yet stats as mode 0 and size 0; an error means the device is unreachable
+        let size = client
+            .stat(serial, remote)
+            .map_err(|e| format!("Failed to check {}: {}", remote, e))?
+            .size;
+        if size > 0 && size == last_size {
+            stable_checks += 1;
+            if stable_checks >= STABLE_SIZE_CHECKS {
+                return Ok(size);
+            }
+        } else {
+            stable_checks = 0;
+        }
+        last_size = size;
+        thread::sleep(SIZE_CHECK_INTERVAL);
+    }
+}
+
+/// Dump the Java heap of a package or PID, pull it into `local_dir` and convert it
+pub fn dump_heap<T>(
+    job: &JobContext<T>,
+    client: &AdbClient,
+    serial: &str,
+    target: &str,
+    local_dir: &Path,
+) -> Result<HeapDumpResult, String> {
+    let target = target.trim();
+    let file_stem = format!(
+        "{}_{}",
+        target.replace(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '_'), "_"),
+        chrono::Local::now().format("%Y%m%d_%H%M%S")
+    );
+    let remote = format!("/data/local/tmp/ohmytoolboxs-{}.hprof", file_stem);
+
+    // Cleanup below uses `client` directly so it still runs after a cancel
+    let device = job.client(client);
+    job.log(format!("Dumping the heap of {}", target));
+    job.set_progress(0.0);
+    let output = device
+        .shell_v2(serial, &format!("am dumpheap {} {}", shell_quote(target), shell_quote(&remote)))
+        .map_err(|e| format!("am dumpheap failed: {}", e))?;
+    let message = format!("{}{}", output.stdout_string(), output.stderr_string());
+    if output.exit_code.is_some_and(|code| code != 0) || message.contains("Exception") || message.contains("Error:") {
+        return Err(format!("am dumpheap failed: {}", message.trim()));
+    }
+
+    let size = wait_for_dump(job, &device, serial, &remote);
+    let pulled = size.and_then(|size| {
+        job.log(format!("Pulling {} ({} bytes)", remote, size));
+        job.set_progress(0.3);
+        fs::create_dir_all(local_dir).map_err(|e| format!("Failed to create {}: {}", local_dir.display(), e))?;
+        let android_path = local_dir.join(format!("{}.android.hprof", file_stem));
+        device.pull(serial, &remote, &android_path).map_err(|e| format!("Pull failed: {}", e))?;
+        Ok(android_path)
+    });
+    let _ = client.shell(serial, &format!("rm -f {}", shell_quote(&remote)));
+    let android_path = pulled?;
+
+    job.log("Converting to standard hprof");
+    let path = local_dir.join(format!("{}.hprof", file_stem));
+    let histogram = convert_hprof(&android_path, &path, |fraction| job.set_progress(0.4 + fraction * 0.6))?;
+    Ok(HeapDumpResult {
+        target: target.to_string(),
+        android_path,
+        path,
+        histogram,
+    })
+}
@@ -0,0 +1,359 @@
+use std::io::Read;
+use std::panic::{self, AssertUnwindSafe};
+use std::process::{Command, Output, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::tools::adb_client::{AdbClient, CancelToken};
+use crate::tools::adb_tools::AdbFunction;
+
+pub type JobId = u64;
+
+const PROCESS_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Lines kept per job; older ones are dropped as new ones arrive
+const MAX_LOG_LINES: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Self::Running => "⏳",
+            Self::Completed => "✅",
+            Self::Failed => "❌",
+            Self::Cancelled => "🚫",
+        }
+    }
+}
+
+#[derive(Debug)]
+enum JobEvent<T> {
+    Log(String),
+    Progress(f32),
+    Finished(Result<T, String>),
+}
+
+/// Handle given to a job's worker closure for reporting back to the UI
+pub struct JobContext<T> {
+    id: JobId,
+    sender: Sender<(JobId, JobEvent<T>)>,
+    cancel: CancelToken,
+    repaint: Option<egui::Context>,
+}
+
+impl<T> JobContext<T> {
+    fn send(&self, event: JobEvent<T>) {
+        let _ = self.sender.send((self.id, event));
+        if let Some(ctx) = &self.repaint {
+            ctx.request_repaint();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// A copy of `client` whose connections are shut down when this job is cancelled
+    pub fn client(&self, client: &AdbClient) -> AdbClient {
+        client.with_cancel(self.cancel.clone())
+    }
+
+    /// Append a line to the job's visible log
+    pub fn log(&self, line: impl Into<String>) {
+        self.send(JobEvent::Log(line.into()));
+    }
+
+    /// Report progress as a fraction between 0.0 and 1.0
+    pub fn set_progress(&self, fraction: f32) {
+        self.send(JobEvent::Progress(fraction.clamp(0.0, 1.0)));
+    }
+
+    /// Run an external process, killing it if the job gets cancelled
+    pub fn run_process(&self, mut command: Command) -> std::io::Result<Output> {
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let stdout_reader = child.stdout.take().map(|mut stdout| {
+            thread::spawn(move || {
+                let mut buffer = Vec::new();
+                let _ = stdout.read_to_end(&mut buffer);
+                buffer
+            })
+        });
+        let stderr_reader = child.stderr.take().map(|mut stderr| {
+            thread::spawn(move || {
+                let mut buffer = Vec::new();
+                let _ = stderr.read_to_end(&mut buffer);
+                buffer
+            })
+        });
+
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if self.is_cancelled() {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(std::io::Error::new(std::io::ErrorKind::Interrupted, "Cancelled"));
+            }
+            thread::sleep(PROCESS_POLL_INTERVAL);
+        };
+
+        Ok(Output {
+            status,
+            stdout: stdout_reader.and_then(|h| h.join().ok()).unwrap_or_default(),
+            stderr: stderr_reader.and_then(|h| h.join().ok()).unwrap_or_default(),
+        })
+    }
+
+    /// Run the `adb` binary with the given arguments (cancellable)
+    pub fn adb_output(&self, args: &[&str]) -> std::io::Result<Output> {
+        let mut command = Command::new("adb");
+        command.args(args);
+        self.run_process(command)
+    }
+}
+
+#[derive(Debug)]
+pub struct Job {
+    pub id: JobId,
+    pub function: AdbFunction,
+    pub label: String,
+    pub status: JobStatus,
+    pub progress: Option<f32>,
+    pub log: Vec<String>,
+    pub error: Option<String>,
+    pub started: Instant,
+    pub finished: Option<Instant>,
+    cancel: CancelToken,
+}
+
+impl Job {
+    pub fn is_running(&self) -> bool {
+        self.status == JobStatus::Running
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.finished.unwrap_or_else(Instant::now).duration_since(self.started)
+    }
+}
+
+/// A job that finished since the last poll, carrying its result
+pub struct FinishedJob<T> {
+    pub id: JobId,
+    pub function: AdbFunction,
+    pub label: String,
+    /// Submitted with [`JobManager::submit_background`]
+    pub background: bool,
+    pub result: Result<T, String>,
+}
+
+/// Runs actions on worker threads and collects their results through a channel
+#[derive(Debug)]
+pub struct JobManager<T> {
+    jobs: Vec<Job>,
+    /// Periodic checks; kept out of the jobs list, spinners and history
+    background: Vec<Job>,
+    next_id: JobId,
+    sender: Sender<(JobId, JobEvent<T>)>,
+    receiver: Receiver<(JobId, JobEvent<T>)>,
+    repaint: Option<egui::Context>,
+    max_finished: usize,
+}
+
+impl<T: Send + 'static> Default for JobManager<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Send + 'static> JobManager<T> {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            jobs: Vec::new(),
+            background: Vec::new(),
+            next_id: 1,
+            sender,
+            receiver,
+            repaint: None,
+            max_finished: 50,
+        }
+    }
+
+    /// Remember the egui context so workers can wake the UI when they report
+    pub fn set_repaint_context(&mut self, ctx: &egui::Context) {
+        if self.repaint.is_none() {
+            self.repaint = Some(ctx.clone());
+        }
+    }
+
+    /// Start `work` on a worker thread and track it under `function`
+    pub fn submit<F>(&mut self, function: AdbFunction, label: impl Into<String>, work: F) -> JobId
+    where
+        F: FnOnce(&JobContext<T>) -> Result<T, String> + Send + 'static,
+    {
+        self.spawn(function, label.into(), false, work)
+    }
+
+    /// Like [`submit`](Self::submit), for a periodic check that shouldn't show up in the jobs
+    /// panel or the function's spinner. It is forgotten as soon as its result is polled.
+    pub fn submit_background<F>(&mut self, function: AdbFunction, label: impl Into<String>, work: F) -> JobId
+    where
+        F: FnOnce(&JobContext<T>) -> Result<T, String> + Send + 'static,
+    {
+        self.spawn(function, label.into(), true, work)
+    }
+
+    fn spawn<F>(&mut self, function: AdbFunction, label: String, background: bool, work: F) -> JobId
+    where
+        F: FnOnce(&JobContext<T>) -> Result<T, String> + Send + 'static,
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let cancel = CancelToken::default();
+        log::debug!("Starting job {} ({:?}): {}", id, function, label);
+
+        let job = Job {
+            id,
+            function,
+            label,
+            status: JobStatus::Running,
+            progress: None,
+            log: Vec::new(),
+            error: None,
+            started: Instant::now(),
+            finished: None,
+            cancel: cancel.clone(),
+        };
+        if background {
+            self.background.push(job);
+        } else {
+            self.jobs.push(job);
+        }
+
+        let context = JobContext {
+            id,
+            sender: self.sender.clone(),
+            cancel,
+            repaint: self.repaint.clone(),
+        };
+
+        thread::spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| work(&context)))
+                .unwrap_or_else(|_| Err("Job panicked".to_string()));
+            context.send(JobEvent::Finished(result));
+        });
+
+        id
+    }
+
+    /// Drain worker events, returning the jobs that finished since the last call
+    pub fn poll(&mut self) -> Vec<FinishedJob<T>> {
+        let mut finished = Vec::new();
+
+        while let Ok((id, event)) = self.receiver.try_recv() {
+            let background = self.background.iter().any(|j| j.id == id);
+            let Some(job) = self.jobs.iter_mut().chain(self.background.iter_mut()).find(|j| j.id == id) else {
+                continue;
+            };
+
+            match event {
+                JobEvent::Log(line) => {
+                    if job.log.len() >= MAX_LOG_LINES {
+                        job.log.remove(0);
+                    }
+                    job.log.push(line);
+                }
+                JobEvent::Progress(fraction) => job.progress = Some(fraction),
+                JobEvent::Finished(result) => {
+                    // Results of cancelled jobs are dropped on the floor
+                    if job.status != JobStatus::Running {
+                        continue;
+                    }
+                    job.finished = Some(Instant::now());
+                    match &result {
+                        Ok(_) => job.status = JobStatus::Completed,
+                        Err(e) => {
+                            job.status = JobStatus::Failed;
+                            job.error = Some(e.clone());
+                        }
+                    }
+                    finished.push(FinishedJob {
+                        id,
+                        function: job.function,
+                        label: job.label.clone(),
+                        background,
+                        result,
+                    });
+                }
+            }
+        }
+
+        self.background.retain(|j| j.is_running());
+        self.trim_finished();
+        finished
+    }
+
+    pub fn cancel(&mut self, id: JobId) {
+        if let Some(job) = self
+            .jobs
+            .iter_mut()
+            .chain(self.background.iter_mut())
+            .find(|j| j.id == id && j.is_running())
+        {
+            job.cancel.cancel();
+            job.status = JobStatus::Cancelled;
+            job.finished = Some(Instant::now());
+        }
+    }
+
+    pub fn is_running(&self, function: AdbFunction) -> bool {
+        self.jobs.iter().any(|j| j.function == function && j.is_running())
+    }
+
+    pub fn running_jobs(&self, function: AdbFunction) -> impl Iterator<Item = &Job> {
+        self.jobs.iter().filter(move |j| j.function == function && j.is_running())
+    }
+
+    pub fn job(&self, id: JobId) -> Option<&Job> {
+        self.jobs.iter().chain(&self.background).find(|j| j.id == id)
+    }
+
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    pub fn running_count(&self) -> usize {
+        self.jobs.iter().filter(|j| j.is_running()).count()
+    }
+
+    pub fn clear_finished(&mut self) {
+        self.jobs.retain(|j| j.is_running());
+    }
+
+    fn trim_finished(&mut self) {
+        let finished_count = self.jobs.iter().filter(|j| !j.is_running()).count();
+        let mut excess = finished_count.saturating_sub(self.max_finished);
+        self.jobs.retain(|j| {
+            if excess > 0 && !j.is_running() {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
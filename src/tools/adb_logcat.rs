@@ -0,0 +1,890 @@
+use std::collections::{HashSet, VecDeque};
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::net::{Shutdown, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::tools::adb_client::{shell_quote, AdbClient};
+
+pub const DEFAULT_LOGCAT_BUFFER_LINES: usize = 50_000;
+
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+const RECONNECT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_BATCH_LINES: usize = 1_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogcatStatus {
+    Connecting,
+    Streaming,
+    Reconnecting(String),
+    Stopped,
+}
+
+impl LogcatStatus {
+    pub fn description(&self) -> String {
+        match self {
+            Self::Connecting => "🔌 Connecting...".to_string(),
+            Self::Streaming => "🟢 Streaming".to_string(),
+            Self::Reconnecting(reason) => format!("🟡 Waiting for device ({})", reason),
+            Self::Stopped => "⏹️ Stopped".to_string(),
+        }
+    }
+}
+
+enum LogcatEvent {
+    Lines(Vec<String>),
+    Status(LogcatStatus),
+}
+
+/// Background thread following `logcat` on one device, reconnecting when it re-enumerates
+struct LogcatReader {
+    stop: Arc<AtomicBool>,
+    stream: Arc<Mutex<Option<TcpStream>>>,
+    receiver: Receiver<LogcatEvent>,
+}
+
+impl LogcatReader {
+    fn spawn(client: AdbClient, serial: String, args: Vec<String>, repaint: Option<egui::Context>) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stream = Arc::new(Mutex::new(None));
+        let (sender, receiver) = mpsc::channel();
+
+        {
+            let stop = stop.clone();
+            let stream = stream.clone();
+            thread::spawn(move || {
+                let reader = ReaderThread {
+                    client,
+                    serial,
+                    args,
+                    stop,
+                    stream,
+                    sender,
+                    repaint,
+                };
+                reader.run();
+            });
+        }
+
+        Self { stop, stream, receiver }
+    }
+
+    /// Ask the thread to finish without waiting for it; it may be stuck talking to the server
+    fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // Unblock a pending read so the thread notices the stop flag
+        if let Ok(guard) = self.stream.lock() {
+            if let Some(stream) = guard.as_ref() {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+        }
+    }
+}
+
+impl Drop for LogcatReader {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+struct ReaderThread {
+    client: AdbClient,
+    serial: String,
+    args: Vec<String>,
+    stop: Arc<AtomicBool>,
+    stream: Arc<Mutex<Option<TcpStream>>>,
+    sender: Sender<LogcatEvent>,
+    repaint: Option<egui::Context>,
+}
+
+impl ReaderThread {
+    fn send(&self, event: LogcatEvent) {
+        let _ = self.sender.send(event);
+        if let Some(ctx) = &self.repaint {
+            ctx.request_repaint();
+        }
+    }
+
+    fn stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    fn run(self) {
+        // Where the last stream stopped, used with `-T` to resume without replaying the buffer
+        let mut resume: Option<ResumePoint> = None;
+
+        while !self.stopped() {
+            self.send(LogcatEvent::Status(LogcatStatus::Connecting));
+
+            let mut command = "logcat -v threadtime".to_string();
+            if let Some(point) = &resume {
+                command.push_str(&format!(" -T {}", shell_quote(&point.timestamp)));
+            }
+            for arg in &self.args {
+                command.push(' ');
+                command.push_str(&shell_quote(arg));
+            }
+
+            let reason = match self.client.open_service(&self.serial, &format!("shell:{}", command)) {
+                Ok(stream) => {
+                    self.send(LogcatEvent::Status(LogcatStatus::Streaming));
+                    match self.follow(stream, &mut resume) {
+                        Ok(()) => "stream ended".to_string(),
+                        Err(e) => e.to_string(),
+                    }
+                }
+                Err(e) => e.to_string(),
+            };
+
+            if self.stopped() {
+                break;
+            }
+            log::info!("Logcat for {} interrupted: {}", self.serial, reason);
+            self.send(LogcatEvent::Status(LogcatStatus::Reconnecting(reason)));
+            self.wait_for_device();
+        }
+
+        self.send(LogcatEvent::Status(LogcatStatus::Stopped));
+    }
+
+    fn follow(&self, stream: TcpStream, resume: &mut Option<ResumePoint>) -> std::io::Result<()> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        if let Ok(mut guard) = self.stream.lock() {
+            *guard = stream.try_clone().ok();
+        }
+
+        let mut reader = BufReader::new(stream);
+        let mut line = Vec::new();
+        let mut batch = Vec::new();
+        // `-T` starts at the resume timestamp, replaying the lines at it that were already seen
+        let mut replay = resume.clone();
+
+        let result = loop {
+            if self.stopped() {
+                break Ok(());
+            }
+
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) => break Ok(()),
+                Ok(_) => {
+                    if line.ends_with(b"\n") {
+                        let text = String::from_utf8_lossy(&line).trim_end_matches(['\r', '\n']).to_string();
+                        line.clear();
+                        let timestamp = threadtime_timestamp(&text);
+                        if let Some(point) = &mut replay {
+                            if point.skips(timestamp) {
+                                continue;
+                            }
+                            replay = None;
+                        }
+                        if let Some(timestamp) = timestamp {
+                            match resume {
+                                Some(point) if point.timestamp == timestamp => point.seen += 1,
+                                _ => {
+                                    *resume = Some(ResumePoint {
+                                        timestamp: timestamp.to_string(),
+                                        seen: 1,
+                                    })
+                                }
+                            }
+                        }
+                        batch.push(text);
+                        if batch.len() >= MAX_BATCH_LINES {
+                            self.send(LogcatEvent::Lines(std::mem::take(&mut batch)));
+                        }
+                    }
+                }
+                // Read timeout: flush what we have and keep any partial line
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    if !batch.is_empty() {
+                        self.send(LogcatEvent::Lines(std::mem::take(&mut batch)));
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => break Err(e),
+            }
+        };
+
+        if !batch.is_empty() {
+            self.send(LogcatEvent::Lines(batch));
+        }
+        if let Ok(mut guard) = self.stream.lock() {
+            *guard = None;
+        }
+        result
+    }
+
+    fn wait_for_device(&self) {
+        while !self.stopped() {
+            let online = self
+                .client
+                .devices()
+                .map(|devices| devices.iter().any(|d| d.id == self.serial && d.status == "device"))
+                .unwrap_or(false);
+            if online {
+                return;
+            }
+
+            let deadline = Instant::now() + RECONNECT_POLL_INTERVAL;
+            while Instant::now() < deadline && !self.stopped() {
+                thread::sleep(READ_TIMEOUT);
+            }
+        }
+    }
+}
+
+/// The last timestamp a stream delivered and how many lines carried it
+#[derive(Debug, Clone)]
+struct ResumePoint {
+    timestamp: String,
+    seen: usize,
+}
+
+impl ResumePoint {
+    /// Whether a replayed line is one already delivered; consumes one of the `seen` lines.
+    /// Untimestamped lines before the first new entry are logcat's own `beginning of` banners.
+    fn skips(&mut self, timestamp: Option<&str>) -> bool {
+        match timestamp {
+            None => true,
+            Some(timestamp) if timestamp < self.timestamp.as_str() => true,
+            Some(timestamp) if timestamp == self.timestamp && self.seen > 0 => {
+                self.seen -= 1;
+                true
+            }
+            Some(_) => false,
+        }
+    }
+}
+
+/// Extract the `MM-DD HH:MM:SS.mmm` prefix of a `threadtime` line
+fn threadtime_timestamp(line: &str) -> Option<&str> {
+    let timestamp = line.get(..18)?;
+    let bytes = timestamp.as_bytes();
+    let looks_valid = bytes[2] == b'-' && bytes[5] == b' ' && bytes[8] == b':' && bytes[14] == b'.'
+        && bytes[..2].iter().all(u8::is_ascii_digit);
+    looks_valid.then_some(timestamp)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum LogPriority {
+    Verbose,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl LogPriority {
+    pub fn all() -> Vec<Self> {
+        vec![Self::Verbose, Self::Debug, Self::Info, Self::Warn, Self::Error, Self::Fatal]
+    }
+
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter {
+            'V' => Some(Self::Verbose),
+            'D' => Some(Self::Debug),
+            'I' => Some(Self::Info),
+            'W' => Some(Self::Warn),
+            'E' => Some(Self::Error),
+            'F' | 'A' => Some(Self::Fatal),
+            _ => None,
+        }
+    }
+
+    pub fn letter(&self) -> &'static str {
+        match self {
+            Self::Verbose => "V",
+            Self::Debug => "D",
+            Self::Info => "I",
+            Self::Warn => "W",
+            Self::Error => "E",
+            Self::Fatal => "F",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Verbose => "Verbose",
+            Self::Debug => "Debug",
+            Self::Info => "Info",
+            Self::Warn => "Warn",
+            Self::Error => "Error",
+            Self::Fatal => "Fatal",
+        }
+    }
+
+    pub fn color(&self) -> egui::Color32 {
+        match self {
+            Self::Verbose => egui::Color32::GRAY,
+            Self::Debug => egui::Color32::LIGHT_BLUE,
+            Self::Info => egui::Color32::LIGHT_GREEN,
+            Self::Warn => egui::Color32::YELLOW,
+            Self::Error => egui::Color32::from_rgb(255, 100, 100),
+            Self::Fatal => egui::Color32::from_rgb(255, 80, 255),
+        }
+    }
+}
+
+/// One logcat line; lines that don't parse keep their text in `message` and have no priority
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub pid: Option<u32>,
+    pub tid: Option<u32>,
+    pub priority: Option<LogPriority>,
+    pub tag: String,
+    pub message: String,
+}
+
+impl LogEntry {
+    fn unparsed(line: &str) -> Self {
+        Self {
+            timestamp: String::new(),
+            pid: None,
+            tid: None,
+            priority: None,
+            tag: String::new(),
+            message: line.to_string(),
+        }
+    }
+}
+
+/// Parse a `threadtime` line: `MM-DD HH:MM:SS.mmm  PID  TID P TAG: message`
+pub fn parse_threadtime_line(line: &str) -> LogEntry {
+    parse_threadtime_fields(line).unwrap_or_else(|| LogEntry::unparsed(line))
+}
+
+fn parse_threadtime_fields(line: &str) -> Option<LogEntry> {
+    let timestamp = threadtime_timestamp(line)?;
+    let mut rest = line[timestamp.len()..].trim_start();
+
+    let next_field = |rest: &mut &str| -> Option<String> {
+        let end = rest.find(char::is_whitespace)?;
+        let field = rest[..end].to_string();
+        *rest = rest[end..].trim_start();
+        Some(field)
+    };
+
+    let pid = next_field(&mut rest)?.parse().ok()?;
+    let tid = next_field(&mut rest)?.parse().ok()?;
+    let priority_field = next_field(&mut rest)?;
+    let mut letters = priority_field.chars();
+    let priority = LogPriority::from_letter(letters.next()?)?;
+    if letters.next().is_some() {
+        return None;
+    }
+
+    // Tags may contain spaces, so split on the first ": " instead of whitespace
+    let (tag, message) = match rest.find(": ") {
+        Some(pos) => (&rest[..pos], &rest[pos + 2..]),
+        None => (rest.trim_end_matches(':'), ""),
+    };
+
+    Some(LogEntry {
+        timestamp: timestamp.to_string(),
+        pid: Some(pid),
+        tid: Some(tid),
+        priority: Some(priority),
+        tag: tag.trim_end().to_string(),
+        message: message.to_string(),
+    })
+}
+
+// Timestamps as printed by the `time`, `threadtime` and `long` formats, with the `year`,
+// `zone`, `epoch` and `monotonic` modifiers
+const TIMESTAMP_PATTERN: &str = r"(?:\d{4}-)?\d{2}-\d{2} \d{2}:\d{2}:\d{2}(?:\.\d+)?(?: [+-]\d{4})?|\d+\.\d+";
+
+/// Patterns for the single-line `-v` formats, most specific first
+fn line_patterns() -> &'static [Regex] {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let ts = TIMESTAMP_PATTERN;
+        [
+            // threadtime: `TS [UID] PID TID P TAG: message`
+            format!(r"^(?P<ts>{ts})\s+(?:\S+\s+)?(?P<pid>\d+)\s+(?P<tid>\d+)\s+(?P<p>[VDIWEFA])\s+(?P<tag>.*?)\s*:(?: (?P<msg>.*))?$"),
+            // time / brief: `[TS] P/TAG( PID): message`
+            format!(r"^(?:(?P<ts>{ts})\s+)?(?P<p>[VDIWEFA])/(?P<tag>.*?)\(\s*(?:\S+:\s*)?(?P<pid>\d+)\):(?: (?P<msg>.*))?$"),
+            // thread: `[TS] P( PID: TID) message`
+            format!(r"^(?:(?P<ts>{ts})\s+)?(?P<p>[VDIWEFA])\(\s*(?P<pid>\d+):\s*(?P<tid>\d+)\) ?(?P<msg>.*)$"),
+            // process: `[TS] P( PID) message  (TAG)`
+            format!(r"^(?:(?P<ts>{ts})\s+)?(?P<p>[VDIWEFA])\(\s*(?P<pid>\d+)\) ?(?P<msg>.*?)\s+\((?P<tag>[^()]*)\)$"),
+            // tag: `[TS] P/TAG: message`
+            format!(r"^(?:(?P<ts>{ts})\s+)?(?P<p>[VDIWEFA])/(?P<tag>[^:]*?)\s*:(?: (?P<msg>.*))?$"),
+        ]
+        .iter()
+        .map(|pattern| Regex::new(pattern).expect("valid logcat pattern"))
+        .collect()
+    })
+}
+
+/// Header line of the `long` format: `[ TS [UID] PID: TID P/TAG ]`
+fn long_header_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        let pattern = format!(
+            r"^\[ (?P<ts>{})\s+(?:\S+\s+)?(?P<pid>\d+):\s*(?P<tid>\d+) (?P<p>[VDIWEFA])/(?P<tag>.*?)\s*\]$",
+            TIMESTAMP_PATTERN
+        );
+        Regex::new(&pattern).expect("valid logcat pattern")
+    })
+}
+
+fn entry_from_captures(captures: &regex::Captures) -> LogEntry {
+    let text = |name: &str| captures.name(name).map(|m| m.as_str()).unwrap_or_default();
+    LogEntry {
+        timestamp: text("ts").to_string(),
+        pid: text("pid").parse().ok(),
+        tid: text("tid").parse().ok(),
+        priority: text("p").chars().next().and_then(LogPriority::from_letter),
+        tag: text("tag").trim().to_string(),
+        message: text("msg").to_string(),
+    }
+}
+
+/// Parse a line in any of the single-line `-v` formats; `raw` lines keep only the message
+pub fn parse_logcat_line(line: &str) -> LogEntry {
+    if let Some(entry) = parse_threadtime_fields(line) {
+        return entry;
+    }
+    line_patterns()
+        .iter()
+        .find_map(|pattern| pattern.captures(line))
+        .map(|captures| entry_from_captures(&captures))
+        .unwrap_or_else(|| LogEntry::unparsed(line))
+}
+
+/// Parse a saved logcat file, including the multi-line `long` format
+pub fn parse_logcat_text(text: &str) -> Vec<LogEntry> {
+    let mut entries = Vec::new();
+    // Header of the `long` entry whose message lines are being read
+    let mut long_header: Option<LogEntry> = None;
+
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+
+        if let Some(captures) = long_header_pattern().captures(line) {
+            long_header = Some(entry_from_captures(&captures));
+            continue;
+        }
+        if let Some(header) = &long_header {
+            if line.is_empty() {
+                long_header = None;
+            } else {
+                entries.push(LogEntry {
+                    message: line.to_string(),
+                    ..header.clone()
+                });
+            }
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+        entries.push(parse_logcat_line(line));
+    }
+
+    entries
+}
+
+/// Format an entry back into `threadtime` layout for export
+pub fn format_threadtime(entry: &LogEntry) -> String {
+    let Some(priority) = entry.priority else {
+        return entry.message.clone();
+    };
+    let pid = entry.pid.map(|p| p.to_string()).unwrap_or_default();
+    let tid = entry.tid.or(entry.pid).map(|t| t.to_string()).unwrap_or_default();
+    format!(
+        "{} {:>5} {:>5} {} {}: {}",
+        entry.timestamp,
+        pid,
+        tid,
+        priority.letter(),
+        entry.tag,
+        entry.message
+    )
+}
+
+/// Filter settings as edited in the UI and stored in the config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogcatFilter {
+    pub min_priority: LogPriority,
+    /// Comma separated tags; when non-empty only these tags are shown
+    pub tag_include: String,
+    /// Comma separated tags that are always hidden
+    pub tag_exclude: String,
+    pub pid: String,
+    pub package: String,
+    pub message_regex: String,
+    pub case_sensitive: bool,
+}
+
+impl Default for LogcatFilter {
+    fn default() -> Self {
+        Self {
+            min_priority: LogPriority::Verbose,
+            tag_include: String::new(),
+            tag_exclude: String::new(),
+            pid: String::new(),
+            package: String::new(),
+            message_regex: String::new(),
+            case_sensitive: false,
+        }
+    }
+}
+
+impl LogcatFilter {
+    /// Build the matcher, resolving `package` through the given pids
+    pub fn compile(&self, package_pids: &HashSet<u32>) -> Result<CompiledLogcatFilter, String> {
+        let split_tags = |tags: &str| -> Vec<String> {
+            tags.split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect()
+        };
+
+        let pid = match self.pid.trim() {
+            "" => None,
+            pid => Some(pid.parse::<u32>().map_err(|_| format!("Invalid PID: {}", pid))?),
+        };
+
+        let message = if self.message_regex.is_empty() {
+            None
+        } else {
+            let pattern = if self.case_sensitive {
+                self.message_regex.clone()
+            } else {
+                format!("(?i){}", self.message_regex)
+            };
+            Some(Regex::new(&pattern).map_err(|e| format!("Invalid regex: {}", e))?)
+        };
+
+        Ok(CompiledLogcatFilter {
+            min_priority: Some(self.min_priority),
+            tag_include: split_tags(&self.tag_include),
+            tag_exclude: split_tags(&self.tag_exclude),
+            pid,
+            package_pids: (!self.package.trim().is_empty()).then(|| package_pids.clone()),
+            message,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CompiledLogcatFilter {
+    min_priority: Option<LogPriority>,
+    tag_include: Vec<String>,
+    tag_exclude: Vec<String>,
+    pid: Option<u32>,
+    package_pids: Option<HashSet<u32>>,
+    message: Option<Regex>,
+}
+
+impl CompiledLogcatFilter {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        if let (Some(min), Some(priority)) = (self.min_priority, entry.priority) {
+            if priority < min {
+                return false;
+            }
+        }
+        if !self.tag_include.is_empty() && !self.tag_include.contains(&entry.tag) {
+            return false;
+        }
+        if self.tag_exclude.contains(&entry.tag) {
+            return false;
+        }
+        if let Some(pid) = self.pid {
+            if entry.pid != Some(pid) {
+                return false;
+            }
+        }
+        if let Some(pids) = &self.package_pids {
+            if !entry.pid.is_some_and(|pid| pids.contains(&pid)) {
+                return false;
+            }
+        }
+        if let Some(regex) = &self.message {
+            if !regex.is_match(&entry.message) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Pick up `Start proc 1234:com.example/u0a12` announcements so a package filter follows restarts
+fn started_process(entry: &LogEntry) -> Option<(u32, &str)> {
+    if entry.tag != "ActivityManager" {
+        return None;
+    }
+    let rest = entry.message.split("Start proc ").nth(1)?;
+    let (pid, rest) = rest.split_once(':')?;
+    let package = rest.split(['/', ' ']).next()?;
+    Some((pid.parse().ok()?, package))
+}
+
+/// Live logcat for one device: a reader thread feeding a bounded ring buffer
+pub struct LogcatSession {
+    pub serial: String,
+    pub status: LogcatStatus,
+    pub paused: bool,
+    pub auto_scroll: bool,
+    pub total_lines: u64,
+    pub dropped_lines: u64,
+    /// PIDs currently belonging to the filtered package
+    pub package_pids: HashSet<u32>,
+    entries: VecDeque<LogEntry>,
+    /// Sequence number of `entries[0]`, so `visible` survives the front being trimmed
+    first_seq: u64,
+    visible: VecDeque<u64>,
+    filter: LogcatFilter,
+    compiled: CompiledLogcatFilter,
+    capacity: usize,
+    pending: VecDeque<LogEntry>,
+    reader: Option<LogcatReader>,
+}
+
+impl std::fmt::Debug for LogcatSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogcatSession")
+            .field("serial", &self.serial)
+            .field("status", &self.status)
+            .field("entries", &self.entries.len())
+            .field("visible", &self.visible.len())
+            .field("paused", &self.paused)
+            .finish()
+    }
+}
+
+impl LogcatSession {
+    pub fn new(serial: &str, capacity: usize) -> Self {
+        Self {
+            serial: serial.to_string(),
+            status: LogcatStatus::Stopped,
+            paused: false,
+            auto_scroll: true,
+            total_lines: 0,
+            dropped_lines: 0,
+            package_pids: HashSet::new(),
+            entries: VecDeque::new(),
+            first_seq: 0,
+            visible: VecDeque::new(),
+            filter: LogcatFilter::default(),
+            compiled: CompiledLogcatFilter::default(),
+            capacity: capacity.max(1),
+            pending: VecDeque::new(),
+            reader: None,
+        }
+    }
+
+    /// A session over already captured entries, e.g. a log file opened without a device
+    pub fn from_entries(name: &str, entries: Vec<LogEntry>) -> Self {
+        let mut session = Self::new(name, entries.len());
+        session.total_lines = entries.len() as u64;
+        for entry in entries {
+            session.push_entry(entry);
+        }
+        session
+    }
+
+    pub fn start(&mut self, client: &AdbClient, args: Vec<String>, repaint: Option<egui::Context>) {
+        self.stop();
+        self.reader = Some(LogcatReader::spawn(client.clone(), self.serial.clone(), args, repaint));
+        self.status = LogcatStatus::Connecting;
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(mut reader) = self.reader.take() {
+            reader.stop();
+        }
+        self.flush_pending();
+        self.status = LogcatStatus::Stopped;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.reader.is_some()
+    }
+
+    /// Drain lines received from the reader thread into the ring buffer
+    pub fn poll(&mut self) {
+        let Some(reader) = &self.reader else {
+            return;
+        };
+
+        let mut received = Vec::new();
+        let mut status = None;
+        while let Ok(event) = reader.receiver.try_recv() {
+            match event {
+                LogcatEvent::Lines(lines) => received.extend(lines),
+                LogcatEvent::Status(new_status) => status = Some(new_status),
+            }
+        }
+
+        if let Some(status) = status {
+            self.status = status;
+        }
+        for line in received {
+            self.total_lines += 1;
+            let entry = parse_threadtime_line(&line);
+            if self.paused {
+                self.pending.push_back(entry);
+                if self.pending.len() > self.capacity {
+                    self.pending.pop_front();
+                    self.dropped_lines += 1;
+                }
+            } else {
+                self.push_entry(entry);
+            }
+        }
+    }
+
+    fn push_entry(&mut self, entry: LogEntry) {
+        if !self.filter.package.is_empty() {
+            if let Some((pid, package)) = started_process(&entry) {
+                if package == self.filter.package.trim() && self.package_pids.insert(pid) {
+                    self.recompile();
+                }
+            }
+        }
+
+        let seq = self.first_seq + self.entries.len() as u64;
+        if self.compiled.matches(&entry) {
+            self.visible.push_back(seq);
+        }
+        self.entries.push_back(entry);
+        self.trim_to_capacity();
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        if !paused {
+            self.flush_pending();
+        }
+    }
+
+    fn flush_pending(&mut self) {
+        while let Some(entry) = self.pending.pop_front() {
+            self.push_entry(entry);
+        }
+    }
+
+    pub fn pending_lines(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        self.trim_to_capacity();
+        while self.pending.len() > self.capacity {
+            self.pending.pop_front();
+            self.dropped_lines += 1;
+        }
+    }
+
+    fn trim_to_capacity(&mut self) {
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+            self.first_seq += 1;
+            self.dropped_lines += 1;
+        }
+        while self.visible.front().is_some_and(|&seq| seq < self.first_seq) {
+            self.visible.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.first_seq += self.entries.len() as u64;
+        self.entries.clear();
+        self.visible.clear();
+        self.pending.clear();
+        self.total_lines = 0;
+        self.dropped_lines = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Number of entries passing the current filter
+    pub fn visible_len(&self) -> usize {
+        self.visible.len()
+    }
+
+    /// The `index`-th entry passing the current filter
+    pub fn visible_entry(&self, index: usize) -> Option<&LogEntry> {
+        let seq = *self.visible.get(index)?;
+        self.entries.get((seq - self.first_seq) as usize)
+    }
+
+    /// The buffer as `threadtime` text, optionally limited to entries passing the filter
+    pub fn export_text(&self, filtered_only: bool) -> String {
+        let mut text = String::new();
+        let mut append = |entry: &LogEntry| {
+            text.push_str(&format_threadtime(entry));
+            text.push('\n');
+        };
+        if filtered_only {
+            (0..self.visible_len()).filter_map(|i| self.visible_entry(i)).for_each(&mut append);
+        } else {
+            self.entries.iter().for_each(&mut append);
+        }
+        text
+    }
+
+    /// Apply new filter settings, re-filtering the buffer when they changed
+    pub fn set_filter(&mut self, filter: &LogcatFilter) -> Result<(), String> {
+        if *filter == self.filter {
+            return Ok(());
+        }
+        if filter.package != self.filter.package {
+            self.package_pids.clear();
+        }
+        let compiled = filter.compile(&self.package_pids)?;
+        self.filter = filter.clone();
+        self.compiled = compiled;
+        self.refilter();
+        Ok(())
+    }
+
+    /// Replace the PIDs resolved for the package filter
+    pub fn set_package_pids(&mut self, package: &str, pids: HashSet<u32>) {
+        if package != self.filter.package.trim() || pids == self.package_pids {
+            return;
+        }
+        self.package_pids = pids;
+        self.recompile();
+    }
+
+    fn recompile(&mut self) {
+        if let Ok(compiled) = self.filter.compile(&self.package_pids) {
+            self.compiled = compiled;
+            self.refilter();
+        }
+    }
+
+    fn refilter(&mut self) {
+        let first_seq = self.first_seq;
+        self.visible = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| self.compiled.matches(entry))
+            .map(|(i, _)| first_seq + i as u64)
+            .collect();
+    }
+}
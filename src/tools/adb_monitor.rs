@@ -0,0 +1,794 @@
+use std::collections::BTreeMap;
+
+use crate::tools::adb_client::{shell_quote, AdbClient};
+
+/// Gap between the two `/proc/stat` reads, taken on the device in one shell call
+const CPU_SAMPLE_WINDOW: &str = "0.5";
+
+/// Cumulative jiffies from one `cpu` line of `/proc/stat`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub iowait: u64,
+    pub irq: u64,
+    pub softirq: u64,
+    pub steal: u64,
+}
+
+impl CpuTimes {
+    /// Guest time is already included in user and nice, so it is not added again
+    pub fn total(&self) -> u64 {
+        self.user + self.nice + self.system + self.idle + self.iowait + self.irq + self.softirq + self.steal
+    }
+}
+
+/// One read of `/proc/stat`: the aggregate line and every online core
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpuStat {
+    pub total: CpuTimes,
+    /// `(core index, times)`; offline cores are missing from the file
+    pub cores: Vec<(usize, CpuTimes)>,
+}
+
+pub fn parse_proc_stat(text: &str) -> CpuStat {
+    let mut stat = CpuStat::default();
+    for line in text.lines() {
+        let mut fields = line.split_whitespace();
+        let Some(label) = fields.next() else {
+            continue;
+        };
+        let Some(core) = label.strip_prefix("cpu") else {
+            continue;
+        };
+        let values: Vec<u64> = fields.map(|v| v.parse().unwrap_or(0)).collect();
+        let value = |index: usize| values.get(index).copied().unwrap_or(0);
+        let times = CpuTimes {
+            user: value(0),
+            nice: value(1),
+            system: value(2),
+            idle: value(3),
+            iowait: value(4),
+            irq: value(5),
+            softirq: value(6),
+            steal: value(7),
+        };
+        if core.is_empty() {
+            stat.total = times;
+        } else if let Ok(index) = core.parse() {
+            stat.cores.push((index, times));
+        }
+    }
+    stat
+}
+
+/// Share of time spent in each state between two samples, in percent
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CpuLoad {
+    /// Everything but idle and iowait
+    pub busy: f64,
+    /// User and nice
+    pub user: f64,
+    pub system: f64,
+    pub iowait: f64,
+    /// Hard and soft interrupts
+    pub irq: f64,
+}
+
+impl CpuLoad {
+    pub fn between(before: &CpuTimes, after: &CpuTimes) -> Self {
+        let elapsed = after.total().saturating_sub(before.total());
+        if elapsed == 0 {
+            return Self::default();
+        }
+        let percent = |value: u64| value as f64 * 100.0 / elapsed as f64;
+        let delta = |pick: fn(&CpuTimes) -> u64| pick(after).saturating_sub(pick(before));
+        let idle = delta(|t| t.idle) + delta(|t| t.iowait);
+        Self {
+            busy: percent(elapsed.saturating_sub(idle)),
+            user: percent(delta(|t| t.user) + delta(|t| t.nice)),
+            system: percent(delta(|t| t.system)),
+            iowait: percent(delta(|t| t.iowait)),
+            irq: percent(delta(|t| t.irq) + delta(|t| t.softirq)),
+        }
+    }
+}
+
+/// Utilisation of the whole CPU and of each core over one sample window
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CpuUtilisation {
+    pub total: CpuLoad,
+    /// Cores online in both samples, by index
+    pub cores: Vec<(usize, CpuLoad)>,
+}
+
+impl CpuUtilisation {
+    pub fn between(before: &CpuStat, after: &CpuStat) -> Self {
+        let cores = after
+            .cores
+            .iter()
+            .filter_map(|(index, times)| {
+                let (_, previous) = before.cores.iter().find(|(i, _)| i == index)?;
+                Some((*index, CpuLoad::between(previous, times)))
+            })
+            .collect();
+        Self {
+            total: CpuLoad::between(&before.total, &after.total),
+            cores,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{:.1}% (user {:.1}%, system {:.1}%, iowait {:.1}%, irq {:.1}%) | {} cores",
+            self.total.busy,
+            self.total.user,
+            self.total.system,
+            self.total.iowait,
+            self.total.irq,
+            self.cores.len()
+        )
+    }
+}
+
+/// Read `/proc/stat` twice on the device and work out the utilisation in between
+pub fn sample_cpu(client: &AdbClient, serial: &str) -> Result<CpuUtilisation, String> {
+    let command = format!("cat /proc/stat; echo ---; sleep {}; cat /proc/stat", CPU_SAMPLE_WINDOW);
+    let output = client.shell(serial, &command).map_err(|e| format!("Failed to read /proc/stat: {}", e))?;
+    let (before, after) = output
+        .split_once("---")
+        .ok_or_else(|| "Unexpected /proc/stat output".to_string())?;
+    Ok(CpuUtilisation::between(&parse_proc_stat(before), &parse_proc_stat(after)))
+}
+
+/// CPU counters kept between app samples to work out the next CPU%
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AppCpuTicks {
+    pub pid: u32,
+    /// utime + stime of the process
+    pub process: u64,
+    /// Aggregate jiffies of the whole CPU
+    pub total: u64,
+}
+
+/// One reading of a tracked app or process
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AppSample {
+    /// `None` while the process is not running
+    pub pid: Option<u32>,
+    pub pss_kb: Option<u64>,
+    pub rss_kb: Option<u64>,
+    pub java_heap_kb: Option<u64>,
+    pub native_heap_kb: Option<u64>,
+    /// Like `top`: 100% is one core fully busy
+    pub cpu_percent: Option<f64>,
+    pub threads: Option<u64>,
+    /// Unreadable without root for other apps' processes
+    pub fds: Option<u64>,
+    pub ticks: Option<AppCpuTicks>,
+}
+
+/// An app or process followed by the Device Monitor
+#[derive(Debug, Clone, Default)]
+pub struct AppTracking {
+    /// Package or process name, or a PID
+    pub target: String,
+    pub last: Option<AppSample>,
+    /// Last PID seen running, kept while the process is down
+    pub last_pid: Option<u32>,
+    pub restarts: usize,
+    /// The latest sample found a new PID
+    pub restarted: bool,
+    pub error: Option<String>,
+}
+
+impl AppTracking {
+    pub fn new(target: &str) -> Self {
+        Self {
+            target: target.trim().to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Counters for the next CPU% delta
+    pub fn ticks(&self) -> Option<AppCpuTicks> {
+        self.last.as_ref().and_then(|s| s.ticks)
+    }
+
+    pub fn record(&mut self, sample: AppSample) {
+        self.restarted = sample.pid.is_some() && self.last_pid.is_some() && sample.pid != self.last_pid;
+        if self.restarted {
+            self.restarts += 1;
+        }
+        self.last_pid = sample.pid.or(self.last_pid);
+        self.last = Some(sample);
+        self.error = None;
+    }
+
+    pub fn status(&self) -> String {
+        if let Some(error) = &self.error {
+            return format!("❌ {}", error);
+        }
+        match (self.last.as_ref().and_then(|s| s.pid), self.last_pid) {
+            (Some(pid), _) => format!("🟢 PID {} ({} restarts)", pid, self.restarts),
+            (None, Some(pid)) => format!("⏸️ Not running (was PID {}), waiting for it to start", pid),
+            (None, None) if self.last.is_some() => "⏸️ Not running, waiting for it to start".to_string(),
+            (None, None) => "⏳ Waiting for the first sample".to_string(),
+        }
+    }
+}
+
+/// Memory figures from `dumpsys meminfo <pid>`, in KB
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AppMemory {
+    pub pss_kb: Option<u64>,
+    pub rss_kb: Option<u64>,
+    pub java_heap_kb: Option<u64>,
+    pub native_heap_kb: Option<u64>,
+}
+
+/// Read the App Summary of `dumpsys meminfo`; releases before Android 11 have no RSS total
+pub fn parse_app_meminfo(output: &str) -> AppMemory {
+    let first_number = |text: &str| text.split_whitespace().next().and_then(|v| v.parse().ok());
+    let after = |line: &str, label: &str| line.find(label).and_then(|i| first_number(&line[i + label.len()..]));
+    let mut memory = AppMemory::default();
+    for line in output.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("Java Heap:") {
+            memory.java_heap_kb = after(trimmed, "Java Heap:");
+        } else if trimmed.starts_with("Native Heap:") {
+            memory.native_heap_kb = after(trimmed, "Native Heap:");
+        } else if trimmed.starts_with("TOTAL PSS:") {
+            memory.pss_kb = after(trimmed, "TOTAL PSS:");
+            memory.rss_kb = after(trimmed, "TOTAL RSS:");
+        } else if trimmed.starts_with("TOTAL:") && memory.pss_kb.is_none() {
+            memory.pss_kb = after(trimmed, "TOTAL:");
+        }
+    }
+    memory
+}
+
+/// Fields of `/proc/<pid>/stat` after the command name, which may itself contain spaces
+fn parse_pid_stat(text: &str) -> Option<(u64, u64)> {
+    let fields: Vec<&str> = text[text.rfind(')')? + 1..].split_whitespace().collect();
+    let field = |index: usize| fields.get(index).and_then(|v| v.parse::<u64>().ok());
+    // utime and stime are fields 14 and 15, num_threads is field 20
+    Some((field(11)? + field(12)?, field(17)?))
+}
+
+/// Sample a package (followed across restarts through `pidof`) or a fixed PID.
+/// `previous` is the last sample's counters; CPU% needs two samples of the same PID.
+pub fn sample_app(
+    client: &AdbClient,
+    serial: &str,
+    target: &str,
+    previous: Option<AppCpuTicks>,
+) -> Result<AppSample, String> {
+    let target = target.trim();
+    let resolve = match target.parse::<u32>() {
+        Ok(pid) => format!("pid={}; [ -d /proc/$pid ] || pid=", pid),
+        Err(_) => format!("pid=$(pidof -s {})", shell_quote(target)),
+    };
+    let script = format!(
+        "{}; echo \"pid $pid\"; [ -n \"$pid\" ] || exit 0; \
+         head -1 /proc/stat; echo \"cores $(grep -c '^cpu[0-9]' /proc/stat)\"; \
+         echo \"stat $(cat /proc/$pid/stat)\"; grep '^VmRSS:' /proc/$pid/status; \
+         ls /proc/$pid/fd >/dev/null 2>&1 && echo \"fds $(ls /proc/$pid/fd | wc -l)\"; true",
+        resolve
+    );
+    let output = client.shell(serial, &script).map_err(|e| format!("Failed to sample {}: {}", target, e))?;
+
+    let mut sample = AppSample::default();
+    let mut total_jiffies = None;
+    let mut cores = 1u64;
+    let mut process_ticks = None;
+    for line in output.lines() {
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        let value = value.trim();
+        match key {
+            "pid" => sample.pid = value.parse().ok(),
+            "cpu" => total_jiffies = Some(parse_proc_stat(line).total.total()),
+            "cores" => cores = value.parse::<u64>().unwrap_or(1).max(1),
+            "stat" => {
+                if let Some((ticks, threads)) = parse_pid_stat(value) {
+                    process_ticks = Some(ticks);
+                    sample.threads = Some(threads);
+                }
+            }
+            "VmRSS:" => sample.rss_kb = value.split_whitespace().next().and_then(|v| v.parse().ok()),
+            "fds" => sample.fds = value.parse().ok(),
+            _ => {}
+        }
+    }
+    let Some(pid) = sample.pid else {
+        return Ok(sample);
+    };
+
+    if let (Some(process), Some(total)) = (process_ticks, total_jiffies) {
+        let ticks = AppCpuTicks { pid, process, total };
+        if let Some(previous) = previous.filter(|p| p.pid == pid && total > p.total) {
+            let share = process.saturating_sub(previous.process) as f64 / (total - previous.total) as f64;
+            sample.cpu_percent = Some(share * cores as f64 * 100.0);
+        }
+        sample.ticks = Some(ticks);
+    }
+
+    if let Ok(meminfo) = client.shell(serial, &format!("dumpsys meminfo {}", pid)) {
+        let memory = parse_app_meminfo(&meminfo);
+        sample.pss_kb = memory.pss_kb;
+        sample.java_heap_kb = memory.java_heap_kb;
+        sample.native_heap_kb = memory.native_heap_kb;
+        // Prefer dumpsys' RSS, which matches its PSS; VmRSS is the fallback on older releases
+        sample.rss_kb = memory.rss_kb.or(sample.rss_kb);
+    }
+    Ok(sample)
+}
+
+/// `thermal_zone*` and `cooling_device*` under `/sys/class/thermal`, one tab-separated line each
+const THERMAL_SYSFS_SCRIPT: &str = "cd /sys/class/thermal 2>/dev/null || exit 0; \
+    for z in thermal_zone*; do [ -d \"$z\" ] && printf 'zone\\t%s\\t%s\\t%s\\n' \"${z#thermal_zone}\" \"$(cat $z/type 2>/dev/null)\" \"$(cat $z/temp 2>/dev/null)\"; done; \
+    for c in cooling_device*; do [ -d \"$c\" ] && printf 'cooling\\t%s\\t%s\\t%s\\t%s\\n' \"${c#cooling_device}\" \"$(cat $c/type 2>/dev/null)\" \"$(cat $c/cur_state 2>/dev/null)\" \"$(cat $c/max_state 2>/dev/null)\"; done; true";
+
+/// A `/sys/class/thermal/thermal_zone<index>` sensor
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThermalZone {
+    pub index: usize,
+    /// The zone's `type`, e.g. `cpu-1-0-usr` or `battery`
+    pub name: String,
+    /// `None` when the sensor can't be read, which some zones refuse while powered down
+    pub celsius: Option<f64>,
+}
+
+/// A `/sys/class/thermal/cooling_device<index>` throttling knob
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoolingDevice {
+    pub index: usize,
+    pub name: String,
+    pub current: Option<u64>,
+    pub max: Option<u64>,
+}
+
+impl CoolingDevice {
+    /// A cooling device above state 0 is throttling something
+    pub fn is_active(&self) -> bool {
+        self.current.is_some_and(|state| state > 0)
+    }
+}
+
+/// A temperature reported by the thermal HAL through `dumpsys thermalservice`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HalTemperature {
+    pub name: String,
+    pub kind: String,
+    pub celsius: f64,
+    pub status: i32,
+}
+
+/// What `dumpsys thermalservice` says about the device as a whole
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThermalServiceStatus {
+    /// `PowerManager.THERMAL_STATUS_*`
+    pub status: Option<i32>,
+    pub temperatures: Vec<HalTemperature>,
+}
+
+/// Every thermal zone and cooling device, plus the framework's view when available
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThermalInfo {
+    pub zones: Vec<ThermalZone>,
+    pub cooling: Vec<CoolingDevice>,
+    /// `None` before Android 10 or when the service can't be dumped
+    pub service: Option<ThermalServiceStatus>,
+}
+
+impl ThermalInfo {
+    pub fn hottest(&self) -> Option<&ThermalZone> {
+        self.zones
+            .iter()
+            .filter(|z| z.celsius.is_some())
+            .max_by(|a, b| a.celsius.partial_cmp(&b.celsius).unwrap_or(std::cmp::Ordering::Equal))
+    }
+
+    /// Zones at or above their threshold, keyed by zone type
+    pub fn alerts<'a>(&'a self, thresholds: &BTreeMap<String, f64>) -> Vec<(&'a ThermalZone, f64)> {
+        self.zones
+            .iter()
+            .filter_map(|zone| {
+                let threshold = *thresholds.get(&zone.name)?;
+                (zone.celsius? >= threshold).then_some((zone, threshold))
+            })
+            .collect()
+    }
+
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(status) = self.service.as_ref().and_then(|s| s.status) {
+            parts.push(format!("status {}", thermal_status_name(status)));
+        }
+        if let Some(zone) = self.hottest() {
+            parts.push(format!("hottest {} {:.1}°C", zone.name, zone.celsius.unwrap_or_default()));
+        }
+        parts.push(format!("{} zones", self.zones.len()));
+        let active = self.cooling.iter().filter(|c| c.is_active()).count();
+        parts.push(format!("{} of {} cooling devices active", active, self.cooling.len()));
+        parts.join(" | ")
+    }
+}
+
+pub fn thermal_status_name(status: i32) -> &'static str {
+    match status {
+        0 => "NONE",
+        1 => "LIGHT",
+        2 => "MODERATE",
+        3 => "SEVERE",
+        4 => "CRITICAL",
+        5 => "EMERGENCY",
+        6 => "SHUTDOWN",
+        _ => "UNKNOWN",
+    }
+}
+
+/// `Temperature.TYPE_*` as used by the thermal HAL
+fn temperature_type_name(kind: i32) -> String {
+    let name = match kind {
+        0 => "CPU",
+        1 => "GPU",
+        2 => "BATTERY",
+        3 => "SKIN",
+        4 => "USB_PORT",
+        5 => "POWER_AMPLIFIER",
+        6 => "BCL_VOLTAGE",
+        7 => "BCL_CURRENT",
+        8 => "BCL_PERCENTAGE",
+        9 => "NPU",
+        _ => return kind.to_string(),
+    };
+    name.to_string()
+}
+
+/// Zones report millidegrees, except for a few drivers that report whole degrees
+fn zone_celsius(raw: &str) -> Option<f64> {
+    let value = raw.trim().parse::<i64>().ok()?;
+    let celsius = if value.abs() >= 1000 { value as f64 / 1000.0 } else { value as f64 };
+    // Disabled sensors read as absolute zero or similar
+    (celsius > -100.0).then_some(celsius)
+}
+
+pub fn parse_thermal_sysfs(text: &str) -> (Vec<ThermalZone>, Vec<CoolingDevice>) {
+    let mut zones = Vec::new();
+    let mut cooling = Vec::new();
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let field = |index: usize| fields.get(index).copied().unwrap_or("").trim();
+        let Ok(index) = field(1).parse::<usize>() else {
+            continue;
+        };
+        match field(0) {
+            "zone" => zones.push(ThermalZone {
+                index,
+                name: field(2).to_string(),
+                celsius: zone_celsius(field(3)),
+            }),
+            "cooling" => cooling.push(CoolingDevice {
+                index,
+                name: field(2).to_string(),
+                current: field(3).parse().ok(),
+                max: field(4).parse().ok(),
+            }),
+            _ => {}
+        }
+    }
+    zones.sort_by_key(|z| z.index);
+    cooling.sort_by_key(|c| c.index);
+    (zones, cooling)
+}
+
+/// `Temperature{mValue=35.5, mType=3, mName=skin, mStatus=0}`
+fn parse_hal_temperature(line: &str) -> Option<HalTemperature> {
+    let body = line.trim().strip_prefix("Temperature{")?.strip_suffix('}')?;
+    let mut temperature = HalTemperature::default();
+    for pair in body.split(", ") {
+        let (key, value) = pair.split_once('=')?;
+        match key {
+            "mValue" => temperature.celsius = value.parse().ok()?,
+            "mType" => temperature.kind = temperature_type_name(value.parse().unwrap_or(-1)),
+            "mName" => temperature.name = value.to_string(),
+            "mStatus" => temperature.status = value.parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+    Some(temperature)
+}
+
+/// Reads the overall status and the HAL's current temperatures, falling back to the cached ones
+pub fn parse_thermalservice(text: &str) -> ThermalServiceStatus {
+    let mut service = ThermalServiceStatus::default();
+    let mut cached = Vec::new();
+    let mut section = "";
+    for line in text.lines() {
+        if !line.starts_with([' ', '\t']) {
+            section = line.trim();
+            if let Some(status) = section.strip_prefix("Thermal Status:") {
+                service.status = status.trim().parse().ok();
+            }
+            continue;
+        }
+        let Some(temperature) = parse_hal_temperature(line) else {
+            continue;
+        };
+        match section {
+            "Current temperatures from HAL:" => service.temperatures.push(temperature),
+            "Cached temperatures:" => cached.push(temperature),
+            _ => {}
+        }
+    }
+    if service.temperatures.is_empty() {
+        service.temperatures = cached;
+    }
+    service
+}
+
+pub fn sample_thermal(client: &AdbClient, serial: &str) -> Result<ThermalInfo, String> {
+    let output = client
+        .shell(serial, THERMAL_SYSFS_SCRIPT)
+        .map_err(|e| format!("Failed to read /sys/class/thermal: {}", e))?;
+    let (zones, cooling) = parse_thermal_sysfs(&output);
+    let service = client
+        .shell(serial, "dumpsys thermalservice")
+        .ok()
+        .map(|output| parse_thermalservice(&output))
+        .filter(|s| s.status.is_some() || !s.temperatures.is_empty());
+    Ok(ThermalInfo { zones, cooling, service })
+}
+
+/// Cumulative counters of one interface in `/proc/net/dev`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InterfaceCounters {
+    pub rx_bytes: u64,
+    pub rx_packets: u64,
+    pub tx_bytes: u64,
+    pub tx_packets: u64,
+}
+
+/// One read of `/proc/net/dev`, stamped with the device's uptime so rates don't depend on adb latency
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetworkCounters {
+    pub uptime: f64,
+    pub interfaces: Vec<(String, InterfaceCounters)>,
+}
+
+/// Throughput of one interface between two samples
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InterfaceRates {
+    pub name: String,
+    pub rx_bytes_per_sec: f64,
+    pub tx_bytes_per_sec: f64,
+    pub rx_packets_per_sec: f64,
+    pub tx_packets_per_sec: f64,
+    /// Counters at the later sample
+    pub totals: InterfaceCounters,
+}
+
+impl InterfaceRates {
+    /// Nothing was sent or received since the previous sample
+    pub fn is_idle(&self) -> bool {
+        self.rx_bytes_per_sec == 0.0 && self.tx_bytes_per_sec == 0.0
+    }
+}
+
+impl NetworkCounters {
+    /// Rates for every interface in this sample; new interfaces, and counters that went
+    /// backwards because an interface was recreated, report zero until the next sample
+    pub fn rates(&self, previous: Option<&NetworkCounters>) -> Vec<InterfaceRates> {
+        let elapsed = previous.map(|p| self.uptime - p.uptime).filter(|e| *e > 0.0);
+        self.interfaces
+            .iter()
+            .map(|(name, now)| {
+                let before = previous
+                    .and_then(|p| p.interfaces.iter().find(|(n, _)| n == name))
+                    .map(|(_, c)| *c);
+                let rate = |pick: fn(&InterfaceCounters) -> u64| match (before, elapsed) {
+                    (Some(before), Some(elapsed)) if pick(now) >= pick(&before) => {
+                        (pick(now) - pick(&before)) as f64 / elapsed
+                    }
+                    _ => 0.0,
+                };
+                InterfaceRates {
+                    name: name.clone(),
+                    rx_bytes_per_sec: rate(|c| c.rx_bytes),
+                    tx_bytes_per_sec: rate(|c| c.tx_bytes),
+                    rx_packets_per_sec: rate(|c| c.rx_packets),
+                    tx_packets_per_sec: rate(|c| c.tx_packets),
+                    totals: *now,
+                }
+            })
+            .collect()
+    }
+}
+
+/// `  wlan0: rx_bytes rx_packets errs drop fifo frame compressed multicast tx_bytes tx_packets …`
+pub fn parse_proc_net_dev(text: &str) -> Vec<(String, InterfaceCounters)> {
+    let mut interfaces: Vec<(String, InterfaceCounters)> = text
+        .lines()
+        .filter_map(|line| {
+            // Large counters can run into the colon, so split on it rather than on whitespace
+            let (name, counters) = line.split_once(':')?;
+            let values: Vec<u64> = counters.split_whitespace().map(|v| v.parse().unwrap_or(0)).collect();
+            if values.len() < 10 {
+                return None;
+            }
+            Some((
+                name.trim().to_string(),
+                InterfaceCounters {
+                    rx_bytes: values[0],
+                    rx_packets: values[1],
+                    tx_bytes: values[8],
+                    tx_packets: values[9],
+                },
+            ))
+        })
+        .collect();
+    interfaces.sort_by(|a, b| a.0.cmp(&b.0));
+    interfaces
+}
+
+pub fn sample_network(client: &AdbClient, serial: &str) -> Result<NetworkCounters, String> {
+    let output = client
+        .shell(serial, "cat /proc/uptime; cat /proc/net/dev")
+        .map_err(|e| format!("Failed to read /proc/net/dev: {}", e))?;
+    let uptime = output
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().next())
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| "Unexpected /proc/uptime output".to_string())?;
+    Ok(NetworkCounters {
+        uptime,
+        interfaces: parse_proc_net_dev(&output),
+    })
+}
+
+/// Traffic attributed to one UID since boot (xt_qtaguid) or over the stored history (netstats)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UidTraffic {
+    pub uid: u32,
+    /// Packages sharing the UID, or a well-known system name
+    pub name: String,
+    pub counters: InterfaceCounters,
+}
+
+impl UidTraffic {
+    pub fn total_bytes(&self) -> u64 {
+        self.counters.rx_bytes + self.counters.tx_bytes
+    }
+}
+
+fn add_uid_counters(traffic: &mut BTreeMap<u32, InterfaceCounters>, uid: u32, counters: InterfaceCounters) {
+    let entry = traffic.entry(uid).or_default();
+    entry.rx_bytes += counters.rx_bytes;
+    entry.rx_packets += counters.rx_packets;
+    entry.tx_bytes += counters.tx_bytes;
+    entry.tx_packets += counters.tx_packets;
+}
+
+/// `/proc/net/xt_qtaguid/stats`, summing the untagged rows of every interface and counter set
+pub fn parse_qtaguid_stats(text: &str) -> BTreeMap<u32, InterfaceCounters> {
+    let mut traffic = BTreeMap::new();
+    for line in text.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 9 || fields[2] != "0x0" {
+            continue;
+        }
+        let value = |index: usize| fields[index].parse::<u64>().unwrap_or(0);
+        let Ok(uid) = fields[3].parse::<u32>() else {
+            continue;
+        };
+        let counters = InterfaceCounters {
+            rx_bytes: value(5),
+            rx_packets: value(6),
+            tx_bytes: value(7),
+            tx_packets: value(8),
+        };
+        add_uid_counters(&mut traffic, uid, counters);
+    }
+    traffic
+}
+
+/// The `UID stats:` section of `dumpsys netstats --uid`, summing the untagged history buckets
+pub fn parse_netstats_uid(text: &str) -> BTreeMap<u32, InterfaceCounters> {
+    let mut traffic = BTreeMap::new();
+    let mut in_uid_stats = false;
+    let mut current: Option<u32> = None;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.ends_with("stats:") {
+            in_uid_stats = trimmed == "UID stats:";
+            current = None;
+            continue;
+        }
+        if !in_uid_stats {
+            continue;
+        }
+        let pairs: Vec<(&str, &str)> = trimmed.split_whitespace().filter_map(|field| field.split_once('=')).collect();
+        let value = |key: &str| pairs.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+        if let Some(uid) = value("uid") {
+            current = uid.parse().ok().filter(|_| value("tag") == Some("0x0"));
+        } else if let (Some(uid), Some(_)) = (current, value("st")) {
+            let count = |key: &str| value(key).and_then(|v| v.parse().ok()).unwrap_or(0);
+            let counters = InterfaceCounters {
+                rx_bytes: count("rb"),
+                rx_packets: count("rp"),
+                tx_bytes: count("tb"),
+                tx_packets: count("tp"),
+            };
+            add_uid_counters(&mut traffic, uid, counters);
+        }
+    }
+    traffic
+}
+
+/// `package:com.example.app uid:10123`, as printed by `pm list packages -U`
+fn parse_package_uids(text: &str) -> BTreeMap<u32, Vec<String>> {
+    let mut packages: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+    for line in text.lines() {
+        let Some(rest) = line.trim().strip_prefix("package:") else {
+            continue;
+        };
+        let Some((package, uid)) = rest.split_once(" uid:") else {
+            continue;
+        };
+        // Shared UIDs are listed as `uid:1000,1001` on some releases
+        if let Some(uid) = uid.split(',').next().and_then(|u| u.trim().parse().ok()) {
+            packages.entry(uid).or_default().push(package.to_string());
+        }
+    }
+    packages
+}
+
+fn uid_name(uid: u32, packages: &BTreeMap<u32, Vec<String>>) -> String {
+    match (uid, packages.get(&uid)) {
+        (0, _) => "root".to_string(),
+        (1000, _) => "system".to_string(),
+        (2000, _) => "shell".to_string(),
+        (_, Some(names)) if names.len() > 1 => format!("{} (+{} shared)", names[0], names.len() - 1),
+        (_, Some(names)) => names[0].clone(),
+        (_, None) => String::new(),
+    }
+}
+
+/// Per-UID traffic from xt_qtaguid where the kernel still has it, otherwise from netstats.
+/// Returns the source used along with the UIDs, busiest first.
+pub fn sample_uid_traffic(client: &AdbClient, serial: &str) -> Result<(String, Vec<UidTraffic>), String> {
+    let qtaguid = client
+        .shell(serial, "cat /proc/net/xt_qtaguid/stats")
+        .map(|output| parse_qtaguid_stats(&output))
+        .unwrap_or_default();
+    let (source, traffic) = if qtaguid.is_empty() {
+        let output = client
+            .shell(serial, "dumpsys netstats --uid")
+            .map_err(|e| format!("Failed to read netstats: {}", e))?;
+        ("dumpsys netstats", parse_netstats_uid(&output))
+    } else {
+        ("/proc/net/xt_qtaguid/stats", qtaguid)
+    };
+    if traffic.is_empty() {
+        return Err("No per-UID traffic reported by xt_qtaguid or netstats".to_string());
+    }
+
+    let packages = client
+        .shell(serial, "pm list packages -U")
+        .map(|output| parse_package_uids(&output))
+        .unwrap_or_default();
+    let mut entries: Vec<UidTraffic> = traffic
+        .into_iter()
+        .map(|(uid, counters)| UidTraffic {
+            uid,
+            name: uid_name(uid, &packages),
+            counters,
+        })
+        .collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.total_bytes()));
+    Ok((source.to_string(), entries))
+}
@@ -0,0 +1,444 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::tools::adb_client::{shell_quote, AdbClient};
+
+/// Modes accepted by `appops set`
+pub const APP_OP_MODES: [&str; 5] = ["allow", "ignore", "deny", "default", "foreground"];
+
+/// Resolver tables at the top of `dumpsys package`, and the component kind each one lists
+const RESOLVER_TABLES: [(&str, ComponentKind); 4] = [
+    ("Activity Resolver Table:", ComponentKind::Activity),
+    ("Receiver Resolver Table:", ComponentKind::Receiver),
+    ("Service Resolver Table:", ComponentKind::Service),
+    ("Provider Resolver Table:", ComponentKind::Provider),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ComponentKind {
+    Activity,
+    Receiver,
+    Service,
+    Provider,
+    Unknown,
+}
+
+impl ComponentKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Activity => "Activity",
+            Self::Receiver => "Receiver",
+            Self::Service => "Service",
+            Self::Provider => "Provider",
+            Self::Unknown => "Component",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentState {
+    /// Whatever the manifest declares
+    Default,
+    Enabled,
+    Disabled,
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageComponent {
+    /// Fully qualified class name
+    pub name: String,
+    pub kind: ComponentKind,
+    pub state: ComponentState,
+}
+
+#[derive(Debug, Clone)]
+pub struct PermissionState {
+    pub name: String,
+    pub granted: bool,
+    pub flags: Vec<String>,
+}
+
+/// The parts of `dumpsys package <pkg>` shown in the package detail pane
+#[derive(Debug, Clone, Default)]
+pub struct PackageDetails {
+    pub package: String,
+    pub version_name: String,
+    pub version_code: String,
+    pub min_sdk: String,
+    pub target_sdk: String,
+    pub uid: String,
+    pub primary_abi: String,
+    pub installer: String,
+    pub first_install_time: String,
+    pub last_update_time: String,
+    pub code_path: String,
+    pub data_dir: String,
+    pub apk_paths: Vec<String>,
+    /// User whose runtime permissions and component states were read
+    pub user_id: Option<u32>,
+    pub requested_permissions: Vec<String>,
+    pub install_permissions: Vec<PermissionState>,
+    pub runtime_permissions: Vec<PermissionState>,
+    pub components: Vec<PackageComponent>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AppOp {
+    pub name: String,
+    pub mode: String,
+    /// Set for ops applied to the whole UID rather than the package
+    pub uid_mode: bool,
+    /// Trailing `time=`/`rejectTime=` details, if any
+    pub details: String,
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Expand `pkg/.Class` and `.Class` into `pkg.Class`
+fn qualify_component(package: &str, name: &str) -> String {
+    let class = name.split_once('/').map(|(_, class)| class).unwrap_or(name);
+    if class.starts_with('.') {
+        format!("{}{}", package, class)
+    } else {
+        class.to_string()
+    }
+}
+
+/// `android.permission.CAMERA: granted=false, flags=[ USER_SET|USER_FIXED ]`
+fn parse_permission_line(line: &str) -> PermissionState {
+    let (name, rest) = line.split_once(':').unwrap_or((line, ""));
+    let flags = rest
+        .split_once('[')
+        .and_then(|(_, flags)| flags.split_once(']'))
+        .map(|(flags, _)| {
+            flags
+                .split(|c: char| c == '|' || c.is_whitespace())
+                .filter(|f| !f.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    PermissionState {
+        name: name.trim().to_string(),
+        granted: rest.contains("granted=true"),
+        flags,
+    }
+}
+
+/// Parse `dumpsys package <pkg>`; `None` when the package is not installed
+pub fn parse_dumpsys_package(output: &str, package: &str) -> Option<PackageDetails> {
+    let lines: Vec<&str> = output.lines().collect();
+    let mut details = PackageDetails {
+        package: package.to_string(),
+        ..Default::default()
+    };
+
+    // Components with intent filters, from the resolver tables
+    let mut components: BTreeMap<String, ComponentKind> = BTreeMap::new();
+    let component_prefix = format!("{}/", package);
+    let mut table_kind = None;
+    for line in &lines {
+        if indent_of(line) == 0 {
+            table_kind = RESOLVER_TABLES.iter().find(|(title, _)| line.starts_with(title)).map(|(_, kind)| *kind);
+            continue;
+        }
+        if let Some(kind) = table_kind {
+            let component = line.split_whitespace().find(|token| token.starts_with(&component_prefix));
+            if let Some(component) = component {
+                components.entry(qualify_component(package, component)).or_insert(kind);
+            }
+        }
+    }
+
+    // The package's own block; a second one under "Hidden system packages" is ignored
+    let header = format!("Package [{}]", package);
+    let start = lines.iter().position(|line| line.trim_start().starts_with(&header))?;
+    let block_indent = indent_of(lines[start]);
+
+    let mut values: BTreeMap<String, String> = BTreeMap::new();
+    let mut section: Option<(String, usize)> = None;
+    let mut in_first_user = false;
+    let mut enabled = Vec::new();
+    let mut disabled = Vec::new();
+
+    for line in &lines[start + 1..] {
+        let indent = indent_of(line);
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if indent <= block_indent {
+            break;
+        }
+
+        if let Some((name, section_indent)) = &section {
+            if indent > *section_indent {
+                match name.as_str() {
+                    "requested permissions:" => {
+                        details.requested_permissions.push(trimmed.split(':').next().unwrap_or(trimmed).to_string());
+                    }
+                    "install permissions:" => details.install_permissions.push(parse_permission_line(trimmed)),
+                    "runtime permissions:" if in_first_user => details.runtime_permissions.push(parse_permission_line(trimmed)),
+                    "enabledComponents:" if in_first_user => enabled.push(trimmed.to_string()),
+                    "disabledComponents:" if in_first_user => disabled.push(trimmed.to_string()),
+                    _ => {}
+                }
+                continue;
+            }
+            section = None;
+        }
+
+        if let Some(user) = trimmed.strip_prefix("User ") {
+            // Only the first user block is shown; later users keep their own grants
+            let user_id = user.split(':').next().and_then(|id| id.trim().parse().ok());
+            in_first_user = details.user_id.is_none();
+            if in_first_user {
+                details.user_id = user_id;
+            }
+        } else if trimmed.ends_with(':') && !trimmed.contains('=') {
+            section = Some((trimmed.to_string(), indent));
+        } else if trimmed.matches('=').count() == 1 {
+            // A single key may carry a value with spaces, such as a timestamp
+            if let Some((key, value)) = trimmed.split_once('=') {
+                values.entry(key.to_string()).or_insert_with(|| value.to_string());
+            }
+        } else {
+            for token in trimmed.split_whitespace() {
+                if let Some((key, value)) = token.split_once('=') {
+                    values.entry(key.to_string()).or_insert_with(|| value.to_string());
+                }
+            }
+        }
+    }
+
+    let value = |key: &str| values.get(key).cloned().unwrap_or_default();
+    details.version_name = value("versionName");
+    details.version_code = value("versionCode");
+    details.min_sdk = value("minSdk");
+    details.target_sdk = value("targetSdk");
+    details.uid = value("userId");
+    details.primary_abi = value("primaryCpuAbi");
+    details.installer = value("installerPackageName");
+    details.first_install_time = value("firstInstallTime");
+    details.last_update_time = value("lastUpdateTime");
+    details.code_path = value("codePath");
+    details.data_dir = value("dataDir");
+
+    for name in enabled.iter().chain(&disabled) {
+        components.entry(name.clone()).or_insert(ComponentKind::Unknown);
+    }
+    details.components = components
+        .into_iter()
+        .map(|(name, kind)| {
+            let state = if disabled.contains(&name) {
+                ComponentState::Disabled
+            } else if enabled.contains(&name) {
+                ComponentState::Enabled
+            } else {
+                ComponentState::Default
+            };
+            PackageComponent { name, kind, state }
+        })
+        .collect();
+    details.components.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.name.cmp(&b.name)));
+    Some(details)
+}
+
+/// Parse `appops get <pkg>`: `CAMERA: allow; time=+1h2m ago` and `Uid mode: COARSE_LOCATION: foreground`
+pub fn parse_appops(output: &str) -> Vec<AppOp> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (uid_mode, line) = match line.strip_prefix("Uid mode:") {
+                Some(rest) => (true, rest.trim()),
+                None => (false, line),
+            };
+            let (name, rest) = line.split_once(':')?;
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_') {
+                return None;
+            }
+            let (mode, details) = rest.split_once(';').unwrap_or((rest, ""));
+            Some(AppOp {
+                name: name.to_string(),
+                mode: mode.trim().to_string(),
+                uid_mode,
+                details: details.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Read everything the detail pane shows for one package
+pub fn fetch_package_details(client: &AdbClient, serial: &str, package: &str) -> Result<(PackageDetails, Vec<AppOp>), String> {
+    let quoted = shell_quote(package);
+    let dumpsys = client
+        .shell(serial, &format!("dumpsys package {}", quoted))
+        .map_err(|e| format!("dumpsys package failed: {}", e))?;
+    let mut details =
+        parse_dumpsys_package(&dumpsys, package).ok_or_else(|| format!("Package {} is not installed", package))?;
+
+    if let Ok(paths) = client.shell(serial, &format!("pm path {}", quoted)) {
+        details.apk_paths = paths
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("package:"))
+            .map(str::to_string)
+            .collect();
+    }
+
+    // App ops are unavailable on old releases; the pane just shows none
+    let app_ops = client
+        .shell(serial, &format!("appops get {}", quoted))
+        .map(|output| parse_appops(&output))
+        .unwrap_or_default();
+    Ok((details, app_ops))
+}
+
+/// Which packages `pm list packages` returns
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PackageListFilter {
+    #[default]
+    All,
+    ThirdParty,
+    System,
+    Enabled,
+    Disabled,
+}
+
+impl PackageListFilter {
+    pub fn all() -> [Self; 5] {
+        [Self::All, Self::ThirdParty, Self::System, Self::Enabled, Self::Disabled]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::All => "All",
+            Self::ThirdParty => "Third-party",
+            Self::System => "System",
+            Self::Enabled => "Enabled",
+            Self::Disabled => "Disabled",
+        }
+    }
+
+    pub fn flag(&self) -> Option<&'static str> {
+        match self {
+            Self::All => None,
+            Self::ThirdParty => Some("-3"),
+            Self::System => Some("-s"),
+            Self::Enabled => Some("-e"),
+            Self::Disabled => Some("-d"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageAction {
+    ForceStop,
+    ClearData,
+    Disable,
+    Enable,
+    Uninstall,
+    UninstallKeepData,
+    PullApk,
+}
+
+impl PackageAction {
+    pub fn all() -> [Self; 7] {
+        [
+            Self::ForceStop,
+            Self::ClearData,
+            Self::Disable,
+            Self::Enable,
+            Self::Uninstall,
+            Self::UninstallKeepData,
+            Self::PullApk,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ForceStop => "⏹ Force stop",
+            Self::ClearData => "🧹 Clear data",
+            Self::Disable => "🚫 Disable",
+            Self::Enable => "✅ Enable",
+            Self::Uninstall => "🗑️ Uninstall",
+            Self::UninstallKeepData => "🗑️ Uninstall (-k)",
+            Self::PullApk => "📥 Pull APK",
+        }
+    }
+
+    /// Actions that lose data and ask for confirmation first
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, Self::ClearData | Self::Uninstall | Self::UninstallKeepData)
+    }
+
+    /// Whether the package list should be reloaded afterwards
+    pub fn changes_package_list(&self) -> bool {
+        matches!(self, Self::Disable | Self::Enable | Self::Uninstall | Self::UninstallKeepData)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageActionResult {
+    pub package: String,
+    pub action: PackageAction,
+    pub success: bool,
+    pub message: String,
+}
+
+/// Apply one action to one package; `apk_dir` is where `PullApk` saves `<package>.apk`
+pub fn run_package_action(
+    client: &AdbClient,
+    serial: &str,
+    package: &str,
+    action: PackageAction,
+    apk_dir: &Path,
+) -> Result<String, String> {
+    let quoted = shell_quote(package);
+    let command = match action {
+        PackageAction::ForceStop => format!("am force-stop {}", quoted),
+        PackageAction::ClearData => format!("pm clear {}", quoted),
+        PackageAction::Disable => format!("pm disable-user {}", quoted),
+        PackageAction::Enable => format!("pm enable {}", quoted),
+        PackageAction::Uninstall => format!("pm uninstall {}", quoted),
+        PackageAction::UninstallKeepData => format!("pm uninstall -k {}", quoted),
+        PackageAction::PullApk => return pull_base_apk(client, serial, package, apk_dir),
+    };
+
+    // pm reports most failures on stdout, sometimes with a zero exit code
+    let output = client.shell(serial, &command).map_err(|e| e.to_string())?;
+    let output = output.trim();
+    if output.contains("Failure") || output.starts_with("Failed") || output.starts_with("Error") {
+        Err(output.to_string())
+    } else if output.is_empty() {
+        Ok("Done".to_string())
+    } else {
+        Ok(output.to_string())
+    }
+}
+
+fn pull_base_apk(client: &AdbClient, serial: &str, package: &str, apk_dir: &Path) -> Result<String, String> {
+    let paths = client
+        .shell(serial, &format!("pm path {}", shell_quote(package)))
+        .map_err(|e| format!("pm path failed: {}", e))?;
+    let paths: Vec<&str> = paths.lines().filter_map(|line| line.trim().strip_prefix("package:")).collect();
+    let base = paths
+        .iter()
+        .find(|path| path.ends_with("/base.apk"))
+        .or(paths.first())
+        .ok_or_else(|| format!("No APK path for {}", package))?;
+
+    std::fs::create_dir_all(apk_dir).map_err(|e| format!("Failed to create {}: {}", apk_dir.display(), e))?;
+    let local = apk_dir.join(format!("{}.apk", package));
+    client.pull(serial, base, &local).map_err(|e| format!("Pull failed: {}", e))?;
+    let splits = paths.len() - 1;
+    Ok(if splits > 0 {
+        format!("Saved {} ({} split APK(s) not pulled)", local.display(), splits)
+    } else {
+        format!("Saved {}", local.display())
+    })
+}
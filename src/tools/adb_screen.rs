@@ -0,0 +1,686 @@
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::tools::adb_client::{shell_quote, AdbClient};
+use crate::tools::adb_jobs::JobContext;
+
+/// Longest recording `screenrecord` will make in one run
+pub const MAX_SEGMENT_SECS: u32 = 180;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+const REMOTE_RECORD_DIR: &str = "/sdcard";
+const GEOMETRY_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScreenRecordOptions {
+    pub bit_rate_mbps: f32,
+    /// `WIDTHxHEIGHT`, empty for the display's native size
+    pub size: String,
+    /// Total recording length in seconds, 0 records until stopped
+    pub time_limit_secs: u32,
+    pub bugreport: bool,
+}
+
+impl Default for ScreenRecordOptions {
+    fn default() -> Self {
+        Self {
+            bit_rate_mbps: 8.0,
+            size: String::new(),
+            time_limit_secs: MAX_SEGMENT_SECS,
+            bugreport: false,
+        }
+    }
+}
+
+impl ScreenRecordOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        let size = self.size.trim();
+        if !size.is_empty() {
+            let valid = size
+                .split_once('x')
+                .is_some_and(|(w, h)| w.parse::<u32>().is_ok() && h.parse::<u32>().is_ok());
+            if !valid {
+                return Err(format!("Invalid size '{}', expected WIDTHxHEIGHT", size));
+            }
+        }
+        if self.bit_rate_mbps <= 0.0 {
+            return Err("Bit rate must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+
+    fn command(&self, segment_secs: u32, remote_path: &str) -> String {
+        let mut command = format!(
+            "screenrecord --bit-rate {} --time-limit {}",
+            (self.bit_rate_mbps * 1_000_000.0).round() as u64,
+            segment_secs
+        );
+        if !self.size.trim().is_empty() {
+            command.push_str(&format!(" --size {}", self.size.trim()));
+        }
+        if self.bugreport {
+            command.push_str(" --bugreport");
+        }
+        command.push(' ');
+        command.push_str(&shell_quote(remote_path));
+        command
+    }
+}
+
+/// Local name of segment `index` (1-based): `record.mp4` -> `record_001.mp4`
+pub fn segment_path(base: &Path, index: usize) -> PathBuf {
+    let stem = base.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let name = match base.extension() {
+        Some(ext) => format!("{}_{:03}.{}", stem, index, ext.to_string_lossy()),
+        None => format!("{}_{:03}", stem, index),
+    };
+    base.with_file_name(name)
+}
+
+/// Record the screen in segments of at most three minutes until the time limit is reached or
+/// `stop` is raised, then pull the segments to `local_path`.
+///
+/// Segments are recorded back to back under their own device paths and only pulled once recording
+/// is over, so transfers neither leave gaps between segments nor eat into the time limit.
+/// A single segment is saved as `local_path` itself; several are numbered (`name_001.mp4`,
+/// `name_002.mp4`, ...).
+pub fn record_screen<T>(
+    job: &JobContext<T>,
+    client: &AdbClient,
+    serial: &str,
+    options: &ScreenRecordOptions,
+    local_path: &Path,
+    stop: &AtomicBool,
+) -> Result<Vec<PathBuf>, String> {
+    let started = Instant::now();
+    let mut recorded: Vec<String> = Vec::new();
+    let mut failure = None;
+
+    for index in 1.. {
+        let elapsed = started.elapsed().as_secs() as u32;
+        let remaining = if options.time_limit_secs == 0 {
+            MAX_SEGMENT_SECS
+        } else {
+            options.time_limit_secs.saturating_sub(elapsed).min(MAX_SEGMENT_SECS)
+        };
+        if remaining == 0 || stop.load(Ordering::Relaxed) || job.is_cancelled() {
+            break;
+        }
+
+        job.log(format!("Recording segment {} ({}s max)", index, remaining));
+        let remote_path = format!("{}/ohmytoolboxs_record_{:03}.mp4", REMOTE_RECORD_DIR, index);
+        let command = options.command(remaining, &remote_path);
+        match run_segment(job, client, serial, &command, started, options, stop) {
+            Ok(finished) => {
+                recorded.push(remote_path);
+                if finished {
+                    break;
+                }
+            }
+            Err(e) => {
+                // screenrecord may have left a partial file behind
+                let _ = client.shell_v2(serial, &format!("rm -f {}", shell_quote(&remote_path)));
+                failure = Some(e);
+                break;
+            }
+        }
+    }
+
+    let pulled = if job.is_cancelled() {
+        Ok(Vec::new())
+    } else {
+        pull_segments(job, client, serial, &recorded, local_path)
+    };
+    if !recorded.is_empty() {
+        let paths: Vec<String> = recorded.iter().map(|path| shell_quote(path)).collect();
+        let _ = client.shell_v2(serial, &format!("rm -f {}", paths.join(" ")));
+    }
+    let saved = pulled?;
+
+    match failure {
+        Some(e) if saved.is_empty() => Err(e),
+        Some(e) => Err(format!("{} ({} earlier segment(s) saved next to {})", e, saved.len(), local_path.display())),
+        None => Ok(saved),
+    }
+}
+
+fn pull_segments<T>(
+    job: &JobContext<T>,
+    client: &AdbClient,
+    serial: &str,
+    recorded: &[String],
+    local_path: &Path,
+) -> Result<Vec<PathBuf>, String> {
+    let client = job.client(client);
+    let mut saved = Vec::with_capacity(recorded.len());
+    for (index, remote_path) in recorded.iter().enumerate() {
+        let target = if recorded.len() == 1 {
+            local_path.to_path_buf()
+        } else {
+            segment_path(local_path, index + 1)
+        };
+        job.log(format!("Pulling segment {} to {}", index + 1, target.display()));
+        job.set_progress(index as f32 / recorded.len() as f32);
+        client
+            .pull(serial, remote_path, &target)
+            .map_err(|e| format!("Failed to pull recording: {}", e))?;
+        saved.push(target);
+    }
+    Ok(saved)
+}
+
+/// Run one `screenrecord` invocation; returns true when the user stopped the recording
+fn run_segment<T>(
+    job: &JobContext<T>,
+    client: &AdbClient,
+    serial: &str,
+    command: &str,
+    started: Instant,
+    options: &ScreenRecordOptions,
+    stop: &AtomicBool,
+) -> Result<bool, String> {
+    let mut stream = client
+        .open_service(serial, &format!("shell:{}", command))
+        .map_err(|e| format!("Failed to start screenrecord: {}", e))?;
+    stream
+        .set_read_timeout(Some(POLL_INTERVAL))
+        .map_err(|e| e.to_string())?;
+
+    let mut output = Vec::new();
+    let mut buffer = [0u8; 1024];
+    let mut interrupted = false;
+
+    loop {
+        if !interrupted && (stop.load(Ordering::Relaxed) || job.is_cancelled()) {
+            // SIGINT lets screenrecord finish writing the mp4 before exiting
+            let _ = client.shell_v2(serial, "pkill -INT screenrecord || kill -INT $(pidof screenrecord)");
+            interrupted = true;
+        }
+
+        match stream.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => output.extend_from_slice(&buffer[..n]),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(format!("Lost connection to screenrecord: {}", e)),
+        }
+
+        if options.time_limit_secs > 0 {
+            job.set_progress(started.elapsed().as_secs_f32() / options.time_limit_secs as f32);
+        }
+    }
+
+    let output = String::from_utf8_lossy(&output);
+    if output.contains("ERROR") || output.contains("Unknown option") {
+        return Err(format!("screenrecord failed: {}", output.trim()));
+    }
+    if job.is_cancelled() {
+        return Err("Cancelled".to_string());
+    }
+    Ok(interrupted)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScreenMirrorOptions {
+    pub fps: f32,
+    /// Fraction of the device resolution frames are scaled to before display
+    pub scale: f32,
+}
+
+impl Default for ScreenMirrorOptions {
+    fn default() -> Self {
+        Self { fps: 2.0, scale: 0.5 }
+    }
+}
+
+/// One grabbed screen, decoded and scaled for display
+struct MirrorFrame {
+    image: egui::ColorImage,
+    /// Full resolution PNG as returned by `screencap -p`, kept for saving
+    png: Vec<u8>,
+    device_size: [usize; 2],
+}
+
+#[derive(Default)]
+struct MirrorShared {
+    options: ScreenMirrorOptions,
+    paused: bool,
+    frame: Option<MirrorFrame>,
+    error: Option<String>,
+    geometry: Option<DisplayGeometry>,
+}
+
+/// Repeatedly grabs `screencap -p` frames on a background thread and shows the latest one
+pub struct ScreenMirror {
+    pub serial: String,
+    shared: Arc<Mutex<MirrorShared>>,
+    stop: Arc<AtomicBool>,
+    texture: Option<egui::TextureHandle>,
+    png: Vec<u8>,
+    device_size: [usize; 2],
+    geometry: Option<DisplayGeometry>,
+    frame_times: VecDeque<Instant>,
+    error: Option<String>,
+}
+
+impl std::fmt::Debug for ScreenMirror {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScreenMirror")
+            .field("serial", &self.serial)
+            .field("device_size", &self.device_size)
+            .finish()
+    }
+}
+
+impl ScreenMirror {
+    pub fn start(client: &AdbClient, serial: &str, options: &ScreenMirrorOptions, repaint: egui::Context) -> Self {
+        let shared = Arc::new(Mutex::new(MirrorShared {
+            options: options.clone(),
+            ..Default::default()
+        }));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        {
+            let client = client.clone();
+            let serial = serial.to_string();
+            let shared = shared.clone();
+            let stop = stop.clone();
+            thread::spawn(move || grab_frames(client, serial, shared, stop, repaint));
+        }
+
+        Self {
+            serial: serial.to_string(),
+            shared,
+            stop,
+            texture: None,
+            png: Vec::new(),
+            device_size: [0, 0],
+            geometry: None,
+            frame_times: VecDeque::new(),
+            error: None,
+        }
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn set_options(&self, options: &ScreenMirrorOptions) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.options = options.clone();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.shared.lock().map(|s| s.paused).unwrap_or(false)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.paused = paused;
+        }
+    }
+
+    /// Upload the newest frame from the grabber thread, if any
+    pub fn poll(&mut self, ctx: &egui::Context) {
+        let (frame, error) = match self.shared.lock() {
+            Ok(mut shared) => {
+                if let Some(geometry) = shared.geometry.take() {
+                    self.geometry = Some(geometry);
+                }
+                (shared.frame.take(), shared.error.take())
+            }
+            Err(_) => return,
+        };
+
+        if let Some(error) = error {
+            self.error = Some(error);
+        }
+        let Some(frame) = frame else {
+            return;
+        };
+
+        match &mut self.texture {
+            Some(texture) => texture.set(frame.image, egui::TextureOptions::LINEAR),
+            None => {
+                self.texture = Some(ctx.load_texture("adb_screen_mirror", frame.image, egui::TextureOptions::LINEAR));
+            }
+        }
+        self.png = frame.png;
+        self.device_size = frame.device_size;
+        self.error = None;
+
+        let now = Instant::now();
+        self.frame_times.push_back(now);
+        while self.frame_times.front().is_some_and(|t| now.duration_since(*t) > Duration::from_secs(2)) {
+            self.frame_times.pop_front();
+        }
+    }
+
+    pub fn texture(&self) -> Option<&egui::TextureHandle> {
+        self.texture.as_ref()
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Size of the last frame in device pixels
+    pub fn device_size(&self) -> [usize; 2] {
+        self.device_size
+    }
+
+    /// Display size and rotation as last reported by the device
+    pub fn geometry(&self) -> Option<&DisplayGeometry> {
+        self.geometry.as_ref()
+    }
+
+    /// Map a point on the shown frame, as fractions of its width and height, to `input` coordinates
+    pub fn to_device_point(&self, fraction: egui::Vec2) -> Option<(i32, i32)> {
+        let geometry = self.geometry.as_ref()?;
+        let [width, height] = self.device_size;
+        // Some devices return frames in the natural orientation regardless of rotation
+        let frame_landscape = width > height;
+        Some(geometry.map_fraction(fraction.x, fraction.y, frame_landscape))
+    }
+
+    /// Frames per second actually received over the last two seconds
+    pub fn measured_fps(&self) -> f32 {
+        match (self.frame_times.front(), self.frame_times.back()) {
+            (Some(first), Some(last)) if self.frame_times.len() > 1 => {
+                let span = last.duration_since(*first).as_secs_f32();
+                if span > 0.0 { (self.frame_times.len() - 1) as f32 / span } else { 0.0 }
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Write the current frame at full device resolution
+    pub fn save_frame(&self, path: &Path) -> Result<(), String> {
+        if self.png.is_empty() {
+            return Err("No frame captured yet".to_string());
+        }
+        std::fs::write(path, &self.png).map_err(|e| format!("Failed to save frame: {}", e))
+    }
+}
+
+impl Drop for ScreenMirror {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn grab_frames(
+    client: AdbClient,
+    serial: String,
+    shared: Arc<Mutex<MirrorShared>>,
+    stop: Arc<AtomicBool>,
+    repaint: egui::Context,
+) {
+    let mut last_geometry_check: Option<Instant> = None;
+
+    while !stop.load(Ordering::Relaxed) {
+        let started = Instant::now();
+
+        // Rotation can change at any time, so re-read it every couple of seconds
+        if last_geometry_check.is_none_or(|t| t.elapsed() >= GEOMETRY_REFRESH_INTERVAL) {
+            last_geometry_check = Some(Instant::now());
+            if let Ok(geometry) = DisplayGeometry::query(&client, &serial) {
+                if let Ok(mut shared) = shared.lock() {
+                    shared.geometry = Some(geometry);
+                }
+            }
+        }
+
+        let (options, paused) = match shared.lock() {
+            Ok(shared) => (shared.options.clone(), shared.paused),
+            Err(_) => break,
+        };
+
+        if !paused {
+            let result = client
+                .exec(&serial, "screencap -p")
+                .map_err(|e| format!("screencap failed: {}", e))
+                .and_then(|png| decode_frame(png, options.scale));
+            if let Ok(mut shared) = shared.lock() {
+                match result {
+                    Ok(frame) => shared.frame = Some(frame),
+                    Err(e) => shared.error = Some(e),
+                }
+            }
+            repaint.request_repaint();
+        }
+
+        let interval = Duration::from_secs_f32(1.0 / options.fps.clamp(0.1, 30.0));
+        while started.elapsed() < interval && !stop.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
+
+fn decode_frame(png: Vec<u8>, scale: f32) -> Result<MirrorFrame, String> {
+    let decoded = image::load_from_memory_with_format(&png, image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to decode frame: {}", e))?;
+    let device_size = [decoded.width() as usize, decoded.height() as usize];
+
+    let scale = scale.clamp(0.1, 1.0);
+    let scaled = if scale < 1.0 {
+        let width = ((decoded.width() as f32 * scale) as u32).max(1);
+        let height = ((decoded.height() as f32 * scale) as u32).max(1);
+        decoded.resize_exact(width, height, image::imageops::FilterType::Triangle)
+    } else {
+        decoded
+    };
+
+    let rgba = scaled.to_rgba8();
+    let size = [rgba.width() as usize, rgba.height() as usize];
+    Ok(MirrorFrame {
+        image: egui::ColorImage::from_rgba_unmultiplied(size, rgba.as_raw()),
+        png,
+        device_size,
+    })
+}
+
+/// Logical display size used by `input`, plus the current rotation (0-3, quarter turns)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayGeometry {
+    /// Size in the display's natural orientation, honouring `wm size` overrides
+    pub natural_width: u32,
+    pub natural_height: u32,
+    pub rotation: u8,
+}
+
+impl DisplayGeometry {
+    pub fn query(client: &AdbClient, serial: &str) -> Result<Self, String> {
+        let wm_size = client.shell(serial, "wm size").map_err(|e| format!("wm size failed: {}", e))?;
+        let (natural_width, natural_height) =
+            parse_wm_size(&wm_size).ok_or_else(|| format!("Unexpected wm size output: {}", wm_size.trim()))?;
+
+        let rotation = client
+            .shell(serial, "dumpsys input | grep -m 1 SurfaceOrientation")
+            .ok()
+            .and_then(|output| parse_rotation(&output))
+            .or_else(|| {
+                client
+                    .shell(serial, "dumpsys window displays | grep -m 1 mCurrentRotation")
+                    .ok()
+                    .and_then(|output| parse_rotation(&output))
+            })
+            .unwrap_or(0);
+
+        Ok(Self {
+            natural_width,
+            natural_height,
+            rotation,
+        })
+    }
+
+    pub fn is_rotated_sideways(&self) -> bool {
+        matches!(self.rotation, 1 | 3)
+    }
+
+    /// Size in the current orientation
+    pub fn logical_size(&self) -> (u32, u32) {
+        if self.is_rotated_sideways() {
+            (self.natural_height, self.natural_width)
+        } else {
+            (self.natural_width, self.natural_height)
+        }
+    }
+
+    /// Map a fraction of the frame to logical coordinates. `frame_landscape` tells whether the
+    /// frame was captured rotated; if it doesn't match the logical orientation it's still in the
+    /// natural orientation and the point is rotated first.
+    fn map_fraction(&self, fx: f32, fy: f32, frame_landscape: bool) -> (i32, i32) {
+        let (logical_width, logical_height) = self.logical_size();
+        let logical_landscape = logical_width > logical_height;
+
+        let (lx, ly) = if frame_landscape == logical_landscape || !self.is_rotated_sideways() {
+            (fx, fy)
+        } else {
+            match self.rotation {
+                1 => (fy, 1.0 - fx),
+                _ => (1.0 - fy, fx),
+            }
+        };
+
+        let x = (lx.clamp(0.0, 1.0) * (logical_width.saturating_sub(1)) as f32).round() as i32;
+        let y = (ly.clamp(0.0, 1.0) * (logical_height.saturating_sub(1)) as f32).round() as i32;
+        (x, y)
+    }
+}
+
+/// Parse `wm size`, preferring the override size over the physical one
+fn parse_wm_size(output: &str) -> Option<(u32, u32)> {
+    let parse_line = |prefix: &str| {
+        output.lines().find_map(|line| {
+            let size = line.trim().strip_prefix(prefix)?.trim();
+            let (w, h) = size.split_once('x')?;
+            Some((w.trim().parse().ok()?, h.trim().parse().ok()?))
+        })
+    };
+    parse_line("Override size:").or_else(|| parse_line("Physical size:"))
+}
+
+/// Parse `SurfaceOrientation: 1` or `mCurrentRotation=ROTATION_90` style output
+fn parse_rotation(output: &str) -> Option<u8> {
+    let value = output.trim().rsplit([':', '=']).next()?.trim();
+    let value = value.strip_prefix("ROTATION_").unwrap_or(value);
+    match value.parse::<u32>().ok()? {
+        0 => Some(0),
+        1 | 90 => Some(1),
+        2 | 180 => Some(2),
+        3 | 270 => Some(3),
+        _ => None,
+    }
+}
+
+pub const KEYPAD_KEYS: &[(&str, &str)] = &[
+    ("🏠 Home", "KEYCODE_HOME"),
+    ("⬅ Back", "KEYCODE_BACK"),
+    ("🗗 Recents", "KEYCODE_APP_SWITCH"),
+    ("⏻ Power", "KEYCODE_POWER"),
+    ("🔉 Vol -", "KEYCODE_VOLUME_DOWN"),
+    ("🔊 Vol +", "KEYCODE_VOLUME_UP"),
+    ("☰ Menu", "KEYCODE_MENU"),
+];
+
+/// An `input` action sent to the device
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputAction {
+    Tap(i32, i32),
+    Swipe { from: (i32, i32), to: (i32, i32), duration_ms: u32 },
+    Roll(i32, i32),
+    KeyEvent(String),
+    Text(String),
+}
+
+impl InputAction {
+    /// Shell commands performing this action
+    fn commands(&self) -> Vec<String> {
+        match self {
+            Self::Tap(x, y) => vec![format!("input tap {} {}", x, y)],
+            Self::Swipe { from, to, duration_ms } => vec![format!(
+                "input swipe {} {} {} {} {}",
+                from.0, from.1, to.0, to.1, duration_ms
+            )],
+            Self::Roll(dx, dy) => vec![format!("input roll {} {}", dx, dy)],
+            Self::KeyEvent(key) => vec![format!("input keyevent {}", shell_quote(key))],
+            Self::Text(text) => {
+                // `input text` reads %s as a space and stops at the first newline
+                let mut commands = Vec::new();
+                for (i, line) in text.split('\n').enumerate() {
+                    if i > 0 {
+                        commands.push("input keyevent KEYCODE_ENTER".to_string());
+                    }
+                    if !line.is_empty() {
+                        commands.push(format!("input text {}", shell_quote(&line.replace(' ', "%s"))));
+                    }
+                }
+                commands
+            }
+        }
+    }
+}
+
+/// Sends `input` commands to one device in order on a worker thread
+pub struct RemoteInput {
+    pub serial: String,
+    sender: Sender<InputAction>,
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+impl std::fmt::Debug for RemoteInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteInput").field("serial", &self.serial).finish()
+    }
+}
+
+impl RemoteInput {
+    pub fn new(client: &AdbClient, serial: &str) -> Self {
+        let (sender, receiver) = mpsc::channel::<InputAction>();
+        let last_error = Arc::new(Mutex::new(None));
+
+        {
+            let client = client.clone();
+            let serial = serial.to_string();
+            let last_error = last_error.clone();
+            // Ends when the sender is dropped
+            thread::spawn(move || {
+                for action in receiver {
+                    for command in action.commands() {
+                        let result = client.shell(&serial, &command);
+                        if let Ok(mut error) = last_error.lock() {
+                            *error = result.err().map(|e| format!("{}: {}", command, e));
+                        }
+                    }
+                }
+            });
+        }
+
+        Self {
+            serial: serial.to_string(),
+            sender,
+            last_error,
+        }
+    }
+
+    pub fn send(&self, action: InputAction) {
+        log::debug!("Input on {}: {:?}", self.serial, action);
+        let _ = self.sender.send(action);
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().ok().and_then(|e| e.clone())
+    }
+}
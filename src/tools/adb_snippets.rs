@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::tools::adb_client::AdbClient;
+use crate::tools::adb_jobs::JobContext;
+
+/// `{name}`, but not the shell's own `${name}`
+fn placeholder_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"(\$?)\{([A-Za-z_][A-Za-z0-9_-]*)\}").expect("valid placeholder regex"))
+}
+
+/// Placeholder names in order of first appearance
+pub fn placeholders(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for captures in placeholder_regex().captures_iter(text) {
+        let name = &captures[2];
+        if captures[1].is_empty() && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Substitute every placeholder; a missing value is an error rather than an empty string
+pub fn fill_placeholders(text: &str, values: &HashMap<String, String>) -> Result<String, String> {
+    let mut missing = Vec::new();
+    let filled = placeholder_regex().replace_all(text, |captures: &regex::Captures| {
+        if !captures[1].is_empty() {
+            return captures[0].to_string();
+        }
+        match values.get(&captures[2]).filter(|v| !v.is_empty()) {
+            Some(value) => value.clone(),
+            None => {
+                missing.push(captures[2].to_string());
+                captures[0].to_string()
+            }
+        }
+    });
+    if missing.is_empty() {
+        Ok(filled.into_owned())
+    } else {
+        Err(format!("No value for {}", missing.join(", ")))
+    }
+}
+
+/// A saved shell command
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Snippet {
+    pub name: String,
+    /// `/`-separated folder path; empty for the top level
+    pub folder: String,
+    pub description: String,
+    pub command: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StepKind {
+    /// Runs in `adb shell`
+    #[default]
+    Shell,
+    /// Runs the `adb` binary against the device, e.g. `install app.apk` or `reboot`
+    Adb,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MacroStep {
+    pub kind: StepKind,
+    pub command: String,
+}
+
+impl MacroStep {
+    /// The step as written in the editor: adb steps keep their `adb` prefix
+    pub fn line(&self) -> String {
+        match self.kind {
+            StepKind::Shell => self.command.clone(),
+            StepKind::Adb => format!("adb {}", self.command),
+        }
+    }
+}
+
+/// Parse one step per line; `adb …` lines are adb steps, `#` lines are comments
+pub fn parse_macro_steps(text: &str) -> Vec<MacroStep> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.strip_prefix("adb ") {
+            Some(args) => MacroStep { kind: StepKind::Adb, command: args.trim().to_string() },
+            None => MacroStep { kind: StepKind::Shell, command: line.to_string() },
+        })
+        .collect()
+}
+
+/// A named sequence of steps that stops at the first failure
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShellMacro {
+    pub name: String,
+    pub folder: String,
+    pub description: String,
+    pub steps: Vec<MacroStep>,
+}
+
+impl ShellMacro {
+    pub fn placeholders(&self) -> Vec<String> {
+        let text: Vec<String> = self.steps.iter().map(MacroStep::line).collect();
+        placeholders(&text.join("\n"))
+    }
+
+    pub fn fill(&self, values: &HashMap<String, String>) -> Result<Vec<MacroStep>, String> {
+        self.steps
+            .iter()
+            .map(|step| {
+                Ok(MacroStep {
+                    kind: step.kind,
+                    command: fill_placeholders(&step.command, values)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Snippets and macros, as kept in the config and in exported files
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnippetLibrary {
+    pub snippets: Vec<Snippet>,
+    pub macros: Vec<ShellMacro>,
+}
+
+impl SnippetLibrary {
+    /// Every folder in use, sorted, with the top level as ""
+    pub fn folders(&self) -> Vec<String> {
+        let mut folders: Vec<String> = self
+            .snippets
+            .iter()
+            .map(|s| s.folder.clone())
+            .chain(self.macros.iter().map(|m| m.folder.clone()))
+            .collect();
+        folders.sort();
+        folders.dedup();
+        folders
+    }
+
+    /// Add a snippet, replacing one with the same folder and name
+    pub fn save_snippet(&mut self, snippet: Snippet) {
+        match self.snippets.iter_mut().find(|s| s.folder == snippet.folder && s.name == snippet.name) {
+            Some(existing) => *existing = snippet,
+            None => self.snippets.push(snippet),
+        }
+    }
+
+    /// Add a macro, replacing one with the same folder and name
+    pub fn save_macro(&mut self, shell_macro: ShellMacro) {
+        match self.macros.iter_mut().find(|m| m.folder == shell_macro.folder && m.name == shell_macro.name) {
+            Some(existing) => *existing = shell_macro,
+            None => self.macros.push(shell_macro),
+        }
+    }
+
+    /// Merge an imported library; entries with the same folder and name are replaced
+    pub fn merge(&mut self, other: SnippetLibrary) -> usize {
+        let count = other.snippets.len() + other.macros.len();
+        other.snippets.into_iter().for_each(|s| self.save_snippet(s));
+        other.macros.into_iter().for_each(|m| self.save_macro(m));
+        count
+    }
+
+    pub fn import(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        toml::from_str(&content).map_err(|e| format!("Invalid snippet file {}: {}", path.display(), e))
+    }
+
+    pub fn export(&self, path: &Path) -> Result<(), String> {
+        let content = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+/// What the editor is working on: a snippet command, or macro steps one per line
+#[derive(Debug, Clone, Default)]
+pub struct SnippetEditor {
+    pub is_macro: bool,
+    pub name: String,
+    pub folder: String,
+    pub description: String,
+    pub body: String,
+}
+
+impl SnippetEditor {
+    pub fn from_snippet(snippet: &Snippet) -> Self {
+        Self {
+            is_macro: false,
+            name: snippet.name.clone(),
+            folder: snippet.folder.clone(),
+            description: snippet.description.clone(),
+            body: snippet.command.clone(),
+        }
+    }
+
+    pub fn from_macro(shell_macro: &ShellMacro) -> Self {
+        let lines: Vec<String> = shell_macro.steps.iter().map(MacroStep::line).collect();
+        Self {
+            is_macro: true,
+            name: shell_macro.name.clone(),
+            folder: shell_macro.folder.clone(),
+            description: shell_macro.description.clone(),
+            body: lines.join("\n"),
+        }
+    }
+
+    /// Store the entry in the library, replacing one with the same folder and name
+    pub fn save(&self, library: &mut SnippetLibrary) -> Result<String, String> {
+        let name = self.name.trim().to_string();
+        let folder = self.folder.trim().trim_matches('/').to_string();
+        if name.is_empty() {
+            return Err("Give the snippet a name".to_string());
+        }
+        if self.is_macro {
+            let steps = parse_macro_steps(&self.body);
+            if steps.is_empty() {
+                return Err("A macro needs at least one step".to_string());
+            }
+            let count = steps.len();
+            library.save_macro(ShellMacro { name: name.clone(), folder, description: self.description.trim().to_string(), steps });
+            Ok(format!("Saved macro '{}' ({} steps)", name, count))
+        } else {
+            if self.body.trim().is_empty() {
+                return Err("A snippet needs a command".to_string());
+            }
+            library.save_snippet(Snippet {
+                name: name.clone(),
+                folder,
+                description: self.description.trim().to_string(),
+                command: self.body.trim().to_string(),
+            });
+            Ok(format!("Saved snippet '{}'", name))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PromptTarget {
+    Snippet(Snippet),
+    Macro(ShellMacro),
+}
+
+/// Values being asked for before a snippet or macro runs
+#[derive(Debug, Clone)]
+pub struct SnippetPrompt {
+    pub target: PromptTarget,
+    pub values: Vec<(String, String)>,
+}
+
+impl SnippetPrompt {
+    pub fn name(&self) -> &str {
+        match &self.target {
+            PromptTarget::Snippet(snippet) => &snippet.name,
+            PromptTarget::Macro(shell_macro) => &shell_macro.name,
+        }
+    }
+
+    pub fn values(&self) -> HashMap<String, String> {
+        self.values.iter().cloned().collect()
+    }
+}
+
+/// Split an adb step into arguments, honouring quotes and backslash escapes
+pub fn split_args(command: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"') | None, '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                in_arg = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(format!("Unterminated quote in `{}`", command));
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+fn run_step<T>(job: &JobContext<T>, client: &AdbClient, serial: &str, step: &MacroStep) -> Result<String, String> {
+    match step.kind {
+        StepKind::Shell => {
+            let output = client.shell_v2(serial, &step.command).map_err(|e| e.to_string())?;
+            let text = format!("{}{}", output.stdout_string(), output.stderr_string());
+            // Legacy devices report no exit code; their steps count as successful
+            match output.exit_code {
+                Some(code) if code != 0 => Err(format!("{}exit code {}", text, code)),
+                _ => Ok(text),
+            }
+        }
+        StepKind::Adb => {
+            let args = split_args(&step.command)?;
+            let mut full_args = vec!["-s", serial];
+            full_args.extend(args.iter().map(String::as_str));
+            let output = job.adb_output(&full_args).map_err(|e| e.to_string())?;
+            let text = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+            if output.status.success() {
+                Ok(text)
+            } else {
+                Err(format!("{}{}", text, output.status))
+            }
+        }
+    }
+}
+
+/// Run the steps in order, returning the transcript; the first failure ends the run
+pub fn run_macro<T>(job: &JobContext<T>, client: &AdbClient, serial: &str, steps: &[MacroStep]) -> Result<String, String> {
+    let mut transcript = String::new();
+    for (index, step) in steps.iter().enumerate() {
+        if job.is_cancelled() {
+            return Err(format!("{}Cancelled before step {} of {}", transcript, index + 1, steps.len()));
+        }
+        job.log(format!("Step {}/{}: {}", index + 1, steps.len(), step.line()));
+        job.set_progress(index as f32 / steps.len() as f32);
+
+        transcript.push_str(&format!("$ {}\n", step.line()));
+        let result = run_step(job, client, serial, step);
+        let output = match &result {
+            Ok(output) | Err(output) => output,
+        };
+        transcript.push_str(output);
+        if !transcript.ends_with('\n') {
+            transcript.push('\n');
+        }
+        if result.is_err() {
+            return Err(format!("{}Stopped: step {} of {} failed", transcript, index + 1, steps.len()));
+        }
+    }
+    job.set_progress(1.0);
+    Ok(transcript)
+}
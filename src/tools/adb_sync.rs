@@ -0,0 +1,335 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+
+use crate::tools::adb_client::{remote_join, shell_quote, AdbClient, SyncConnection};
+use crate::tools::adb_jobs::JobContext;
+
+/// Modification times within this many seconds count as equal (FAT and sdcardfs round them)
+const MTIME_TOLERANCE_SECS: u64 = 2;
+/// Files hashed per `md5sum` invocation, keeping the command line short
+const MD5_BATCH: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SyncDirection {
+    /// Make the device folder match the local one
+    Push,
+    /// Make the local folder match the device one
+    Pull,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncOptions {
+    pub local_dir: String,
+    pub remote_dir: String,
+    pub direction: SyncDirection,
+    /// Compare same-sized files by MD5 instead of modification time
+    pub use_checksum: bool,
+    pub delete_extras: bool,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        Self {
+            local_dir: String::new(),
+            remote_dir: "/sdcard/".to_string(),
+            direction: SyncDirection::Push,
+            use_checksum: false,
+            delete_extras: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FileInfo {
+    size: u64,
+    mtime: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncReason {
+    New,
+    SizeChanged,
+    Newer,
+    ChecksumChanged,
+    Extra,
+}
+
+impl SyncReason {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::New => "new",
+            Self::SizeChanged => "size differs",
+            Self::Newer => "newer",
+            Self::ChecksumChanged => "content differs",
+            Self::Extra => "not in source",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SyncAction {
+    /// Path relative to both roots, `/` separated
+    pub path: String,
+    pub reason: SyncReason,
+    pub size: u64,
+    pub selected: bool,
+}
+
+impl SyncAction {
+    pub fn is_delete(&self) -> bool {
+        self.reason == SyncReason::Extra
+    }
+}
+
+/// What a sync would do, computed before anything is transferred
+#[derive(Debug, Clone)]
+pub struct SyncPlan {
+    pub options: SyncOptions,
+    pub actions: Vec<SyncAction>,
+    pub unchanged: usize,
+}
+
+impl SyncPlan {
+    pub fn transfer_bytes(&self) -> u64 {
+        self.actions.iter().filter(|a| a.selected && !a.is_delete()).map(|a| a.size).sum()
+    }
+
+    pub fn selected_count(&self) -> usize {
+        self.actions.iter().filter(|a| a.selected).count()
+    }
+}
+
+fn scan_local(root: &Path, prefix: &str, files: &mut BTreeMap<String, FileInfo>) -> Result<(), String> {
+    let entries = fs::read_dir(root).map_err(|e| format!("Failed to read {}: {}", root.display(), e))?;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let rel = if prefix.is_empty() { name.clone() } else { format!("{}/{}", prefix, name) };
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            scan_local(&entry.path(), &rel, files)?;
+        } else if metadata.is_file() {
+            let mtime = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            files.insert(rel, FileInfo { size: metadata.len(), mtime });
+        }
+    }
+    Ok(())
+}
+
+fn scan_remote(
+    sync: &mut SyncConnection,
+    root: &str,
+    prefix: &str,
+    files: &mut BTreeMap<String, FileInfo>,
+) -> Result<(), String> {
+    let dir = if prefix.is_empty() { root.to_string() } else { remote_join(root, prefix) };
+    let entries = sync.list(&dir).map_err(|e| format!("Failed to list {}: {}", dir, e))?;
+    for entry in entries {
+        let rel = if prefix.is_empty() { entry.name.clone() } else { format!("{}/{}", prefix, entry.name) };
+        if entry.stat.is_dir() {
+            scan_remote(sync, root, &rel, files)?;
+        } else if entry.stat.is_file() {
+            files.insert(
+                rel,
+                FileInfo {
+                    size: entry.stat.size as u64,
+                    mtime: entry.stat.mtime as u64,
+                },
+            );
+        }
+    }
+    Ok(())
+}
+
+fn local_md5(path: &Path) -> Result<String, String> {
+    let mut file = fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Md5::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Hash files on the device with `md5sum`, returning relative path -> digest
+fn remote_md5s(client: &AdbClient, serial: &str, root: &str, paths: &[&String]) -> Result<BTreeMap<String, String>, String> {
+    let mut digests = BTreeMap::new();
+    for batch in paths.chunks(MD5_BATCH) {
+        let args: Vec<String> = batch.iter().map(|p| shell_quote(p)).collect();
+        let command = format!("cd {} && md5sum {}", shell_quote(root), args.join(" "));
+        let output = client.shell_v2(serial, &command).map_err(|e| format!("md5sum failed: {}", e))?;
+        for line in output.stdout_string().lines() {
+            if let Some((digest, path)) = line.split_once(char::is_whitespace) {
+                digests.insert(path.trim_start().trim_start_matches("./").to_string(), digest.to_lowercase());
+            }
+        }
+    }
+    Ok(digests)
+}
+
+/// Compare the two folders and work out which files need copying or deleting
+pub fn plan_sync<T>(job: &JobContext<T>, client: &AdbClient, serial: &str, options: &SyncOptions) -> Result<SyncPlan, String> {
+    let local_root = PathBuf::from(&options.local_dir);
+    let remote_root = options.remote_dir.trim_end_matches('/').to_string();
+    let remote_root = if remote_root.is_empty() { "/".to_string() } else { remote_root };
+
+    job.log(format!("Scanning {}", local_root.display()));
+    let mut local = BTreeMap::new();
+    if local_root.exists() {
+        scan_local(&local_root, "", &mut local)?;
+    } else if options.direction == SyncDirection::Push {
+        return Err(format!("Local folder {} does not exist", local_root.display()));
+    }
+
+    job.log(format!("Scanning {}", remote_root));
+    let mut sync = client.sync(serial).map_err(|e| format!("Failed to open sync session: {}", e))?;
+    let mut remote = BTreeMap::new();
+    let root_stat = sync.stat(&remote_root).map_err(|e| e.to_string())?;
+    if root_stat.exists() {
+        scan_remote(&mut sync, &remote_root, "", &mut remote)?;
+    } else if options.direction == SyncDirection::Pull {
+        return Err(format!("Remote folder {} does not exist", remote_root));
+    }
+    sync.quit();
+
+    let (source, dest) = match options.direction {
+        SyncDirection::Push => (&local, &remote),
+        SyncDirection::Pull => (&remote, &local),
+    };
+
+    let mut actions = Vec::new();
+    let mut same_size = Vec::new();
+    for (path, info) in source {
+        let Some(existing) = dest.get(path) else {
+            actions.push(SyncAction { path: path.clone(), reason: SyncReason::New, size: info.size, selected: true });
+            continue;
+        };
+        // The sync protocol reports sizes modulo 4 GiB, so compare like with like
+        if info.size as u32 != existing.size as u32 {
+            actions.push(SyncAction { path: path.clone(), reason: SyncReason::SizeChanged, size: info.size, selected: true });
+        } else if options.use_checksum {
+            same_size.push(path);
+        } else if info.mtime > existing.mtime + MTIME_TOLERANCE_SECS {
+            actions.push(SyncAction { path: path.clone(), reason: SyncReason::Newer, size: info.size, selected: true });
+        }
+    }
+
+    if !same_size.is_empty() {
+        job.log(format!("Comparing checksums of {} file(s)", same_size.len()));
+        let remote_digests = remote_md5s(client, serial, &remote_root, &same_size)?;
+        for (i, path) in same_size.iter().enumerate() {
+            if job.is_cancelled() {
+                return Err("Cancelled".to_string());
+            }
+            job.set_progress(i as f32 / same_size.len() as f32);
+            let local_digest = local_md5(&local_root.join(path.as_str()))?;
+            if remote_digests.get(path.as_str()) != Some(&local_digest) {
+                actions.push(SyncAction {
+                    path: (*path).clone(),
+                    reason: SyncReason::ChecksumChanged,
+                    size: source[path.as_str()].size,
+                    selected: true,
+                });
+            }
+        }
+    }
+
+    for (path, info) in dest {
+        if !source.contains_key(path) {
+            actions.push(SyncAction {
+                path: path.clone(),
+                reason: SyncReason::Extra,
+                size: info.size,
+                selected: options.delete_extras,
+            });
+        }
+    }
+
+    let changed = actions.iter().filter(|a| !a.is_delete()).count();
+    Ok(SyncPlan {
+        options: SyncOptions { remote_dir: remote_root, ..options.clone() },
+        unchanged: source.len() - changed,
+        actions,
+    })
+}
+
+/// Carry out the selected actions of a plan, returning a summary
+pub fn apply_sync<T>(job: &JobContext<T>, client: &AdbClient, serial: &str, plan: &SyncPlan) -> Result<String, String> {
+    let local_root = PathBuf::from(&plan.options.local_dir);
+    let remote_root = &plan.options.remote_dir;
+    let total_bytes = plan.transfer_bytes().max(1);
+    let mut done_bytes = 0u64;
+    let mut copied = 0;
+    let mut deletes = Vec::new();
+
+    let mut sync = client.sync(serial).map_err(|e| format!("Failed to open sync session: {}", e))?;
+    for action in plan.actions.iter().filter(|a| a.selected) {
+        if job.is_cancelled() {
+            return Err("Cancelled".to_string());
+        }
+        if action.is_delete() {
+            deletes.push(action.path.clone());
+            continue;
+        }
+
+        let local = local_root.join(&action.path);
+        let remote = remote_join(remote_root, &action.path);
+        job.log(format!("{} ({})", action.path, action.reason.description()));
+        match plan.options.direction {
+            SyncDirection::Push => {
+                sync.send_file(&local, &remote).map_err(|e| e.to_string())?;
+            }
+            SyncDirection::Pull => {
+                let stat = sync.stat(&remote).map_err(|e| e.to_string())?;
+                sync.recv_file(&remote, &local).map_err(|e| e.to_string())?;
+                // Keep the device's mtime so the next comparison sees the files as equal
+                if let Ok(file) = fs::File::options().write(true).open(&local) {
+                    let _ = file.set_modified(UNIX_EPOCH + Duration::from_secs(stat.mtime as u64));
+                }
+            }
+        }
+        copied += 1;
+        done_bytes += action.size;
+        job.set_progress(done_bytes as f32 / total_bytes as f32);
+    }
+    sync.quit();
+
+    if !deletes.is_empty() {
+        job.log(format!("Deleting {} extra file(s)", deletes.len()));
+        match plan.options.direction {
+            SyncDirection::Push => {
+                for batch in deletes.chunks(MD5_BATCH) {
+                    let args: Vec<String> = batch.iter().map(|p| shell_quote(&remote_join(remote_root, p))).collect();
+                    client
+                        .shell(serial, &format!("rm -f {}", args.join(" ")))
+                        .map_err(|e| format!("Delete failed: {}", e))?;
+                }
+            }
+            SyncDirection::Pull => {
+                for path in &deletes {
+                    let local = local_root.join(path);
+                    fs::remove_file(&local).map_err(|e| format!("Failed to delete {}: {}", local.display(), e))?;
+                }
+            }
+        }
+    }
+
+    Ok(format!("Copied {} file(s), deleted {} file(s)", copied, deletes.len()))
+}
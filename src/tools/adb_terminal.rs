@@ -0,0 +1,1010 @@
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use egui::text::{LayoutJob, TextFormat};
+use egui::{Color32, FontId, Key, Modifiers, Stroke};
+
+use crate::tools::adb_client::{is_unsupported_service, AdbClient};
+
+pub const SCROLLBACK_LINES: usize = 5_000;
+pub const MAX_SHELL_HISTORY: usize = 500;
+pub const DEFAULT_ROWS: usize = 24;
+pub const DEFAULT_COLS: usize = 80;
+
+const READ_CHUNK: usize = 16 * 1024;
+const TAB_WIDTH: usize = 8;
+
+// shell,v2 packet ids
+const ID_STDIN: u8 = 0;
+const ID_STDOUT: u8 = 1;
+const ID_STDERR: u8 = 2;
+const ID_EXIT: u8 = 3;
+const ID_WINDOW_SIZE: u8 = 5;
+
+/// xterm's default 16-colour palette
+const PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 49, 49),
+    (13, 188, 121),
+    (229, 229, 16),
+    (36, 114, 200),
+    (188, 63, 188),
+    (17, 168, 205),
+    (229, 229, 229),
+    (102, 102, 102),
+    (241, 76, 76),
+    (35, 209, 139),
+    (245, 245, 67),
+    (59, 142, 234),
+    (214, 112, 214),
+    (41, 184, 219),
+    (255, 255, 255),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum TermColor {
+    #[default]
+    Default,
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl TermColor {
+    fn to_color32(self, default: Color32, bold: bool) -> Color32 {
+        match self {
+            Self::Default => default,
+            // Bold brightens the first eight colours, like most terminals
+            Self::Indexed(index) if bold && index < 8 => indexed_color(index + 8),
+            Self::Indexed(index) => indexed_color(index),
+            Self::Rgb(r, g, b) => Color32::from_rgb(r, g, b),
+        }
+    }
+}
+
+fn indexed_color(index: u8) -> Color32 {
+    match index {
+        0..=15 => {
+            let (r, g, b) = PALETTE[index as usize];
+            Color32::from_rgb(r, g, b)
+        }
+        // 6x6x6 colour cube
+        16..=231 => {
+            let index = index - 16;
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            Color32::from_rgb(level(index / 36), level((index / 6) % 6), level(index % 6))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            Color32::from_rgb(gray, gray, gray)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct CellStyle {
+    fg: TermColor,
+    bg: TermColor,
+    bold: bool,
+    underline: bool,
+    inverse: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cell {
+    ch: char,
+    style: CellStyle,
+}
+
+impl Cell {
+    fn blank(style: CellStyle) -> Self {
+        // Erased cells keep the background colour only
+        Self {
+            ch: ' ',
+            style: CellStyle { bg: style.bg, ..Default::default() },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ParserState {
+    Ground,
+    Escape,
+    Csi(String),
+    Osc,
+    OscEscape,
+    /// `ESC (`, `ESC )` and friends: one charset byte follows
+    Charset,
+}
+
+/// The main screen, put aside while a full-screen program uses the alternate one
+#[derive(Debug, Clone)]
+struct SavedScreen {
+    lines: VecDeque<Vec<Cell>>,
+    cursor: (usize, usize),
+}
+
+/// A VT100/xterm subset: enough for the Android shell, `top`, `vi` and coloured `ls`
+#[derive(Debug, Clone)]
+pub struct TerminalScreen {
+    rows: usize,
+    cols: usize,
+    /// Scrollback followed by the `rows` lines of the screen
+    lines: VecDeque<Vec<Cell>>,
+    cursor_row: usize,
+    cursor_col: usize,
+    saved_cursor: (usize, usize),
+    /// The last column was written; the next character wraps first
+    wrap_pending: bool,
+    style: CellStyle,
+    cursor_visible: bool,
+    scroll_top: usize,
+    scroll_bottom: usize,
+    alternate: Option<SavedScreen>,
+    parser: ParserState,
+    utf8: Vec<u8>,
+    /// Answers to device queries (cursor position reports) waiting to be sent back
+    replies: Vec<u8>,
+}
+
+impl TerminalScreen {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        let rows = rows.max(1);
+        let cols = cols.max(1);
+        Self {
+            rows,
+            cols,
+            lines: (0..rows).map(|_| vec![Cell::blank(CellStyle::default()); cols]).collect(),
+            cursor_row: 0,
+            cursor_col: 0,
+            saved_cursor: (0, 0),
+            wrap_pending: false,
+            style: CellStyle::default(),
+            cursor_visible: true,
+            scroll_top: 0,
+            scroll_bottom: rows - 1,
+            alternate: None,
+            parser: ParserState::Ground,
+            utf8: Vec::new(),
+            replies: Vec::new(),
+        }
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    /// Scrollback plus screen lines
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn take_replies(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.replies)
+    }
+
+    fn screen_start(&self) -> usize {
+        self.lines.len() - self.rows
+    }
+
+    fn blank_line(&self) -> Vec<Cell> {
+        vec![Cell::blank(self.style); self.cols]
+    }
+
+    fn row_mut(&mut self, row: usize) -> &mut Vec<Cell> {
+        let index = self.screen_start() + row;
+        &mut self.lines[index]
+    }
+
+    /// Change the screen size; lines are cut or padded rather than reflowed
+    pub fn resize(&mut self, rows: usize, cols: usize) {
+        let rows = rows.max(1);
+        let cols = cols.max(1);
+        if (rows, cols) == (self.rows, self.cols) {
+            return;
+        }
+        for line in self.lines.iter_mut() {
+            line.resize(cols, Cell::blank(CellStyle::default()));
+        }
+        if let Some(saved) = &mut self.alternate {
+            for line in saved.lines.iter_mut() {
+                line.resize(cols, Cell::blank(CellStyle::default()));
+            }
+        }
+        self.cols = cols;
+
+        if rows > self.rows {
+            // Grow downwards, keeping the cursor on the same line
+            for _ in self.rows..rows {
+                self.lines.push_back(vec![Cell::blank(CellStyle::default()); cols]);
+            }
+        } else if self.cursor_row >= rows {
+            // Shrinking below the cursor moves the top lines into the scrollback
+            let cut = self.cursor_row + 1 - rows;
+            self.cursor_row -= cut;
+            for _ in 0..(self.rows - rows - cut) {
+                self.lines.pop_back();
+            }
+        } else {
+            for _ in rows..self.rows {
+                self.lines.pop_back();
+            }
+        }
+        self.rows = rows;
+        self.scroll_top = 0;
+        self.scroll_bottom = rows - 1;
+        self.cursor_col = self.cursor_col.min(cols - 1);
+        self.wrap_pending = false;
+    }
+
+    /// Feed raw output from the device
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.utf8.extend_from_slice(bytes);
+        let data = std::mem::take(&mut self.utf8);
+        let mut rest = data.as_slice();
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(text) => {
+                    text.chars().for_each(|c| self.process(c));
+                    break;
+                }
+                Err(e) => {
+                    let (valid, after) = rest.split_at(e.valid_up_to());
+                    String::from_utf8_lossy(valid).chars().for_each(|c| self.process(c));
+                    match e.error_len() {
+                        Some(len) => {
+                            self.process(char::REPLACEMENT_CHARACTER);
+                            rest = &after[len..];
+                        }
+                        // An incomplete sequence at the end waits for the next chunk
+                        None => {
+                            self.utf8 = after.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        self.trim_scrollback();
+    }
+
+    fn trim_scrollback(&mut self) {
+        while self.lines.len() > self.rows + SCROLLBACK_LINES {
+            self.lines.pop_front();
+        }
+    }
+
+    fn process(&mut self, c: char) {
+        match std::mem::replace(&mut self.parser, ParserState::Ground) {
+            ParserState::Ground => self.ground(c),
+            ParserState::Escape => self.escape(c),
+            ParserState::Csi(mut params) => {
+                if ('\u{40}'..='\u{7e}').contains(&c) {
+                    self.csi(&params, c);
+                } else {
+                    params.push(c);
+                    self.parser = ParserState::Csi(params);
+                }
+            }
+            ParserState::Osc => match c {
+                '\u{07}' => {}
+                '\u{1b}' => self.parser = ParserState::OscEscape,
+                _ => self.parser = ParserState::Osc,
+            },
+            ParserState::OscEscape => {
+                if c != '\\' {
+                    self.parser = ParserState::Osc;
+                }
+            }
+            ParserState::Charset => {}
+        }
+    }
+
+    fn ground(&mut self, c: char) {
+        match c {
+            '\u{1b}' => self.parser = ParserState::Escape,
+            '\r' => {
+                self.cursor_col = 0;
+                self.wrap_pending = false;
+            }
+            '\n' | '\u{0b}' | '\u{0c}' => self.line_feed(),
+            '\u{08}' => {
+                self.cursor_col = self.cursor_col.saturating_sub(1);
+                self.wrap_pending = false;
+            }
+            '\t' => {
+                self.cursor_col = ((self.cursor_col / TAB_WIDTH + 1) * TAB_WIDTH).min(self.cols - 1);
+            }
+            c if c.is_control() => {}
+            c => self.put_char(c),
+        }
+    }
+
+    fn escape(&mut self, c: char) {
+        match c {
+            '[' => self.parser = ParserState::Csi(String::new()),
+            ']' => self.parser = ParserState::Osc,
+            '(' | ')' | '*' | '+' => self.parser = ParserState::Charset,
+            '7' => self.saved_cursor = (self.cursor_row, self.cursor_col),
+            '8' => {
+                (self.cursor_row, self.cursor_col) = self.saved_cursor;
+                self.clamp_cursor();
+            }
+            'D' => self.line_feed(),
+            'E' => {
+                self.cursor_col = 0;
+                self.line_feed();
+            }
+            'M' => self.reverse_index(),
+            'c' => *self = Self::new(self.rows, self.cols),
+            _ => {}
+        }
+    }
+
+    fn put_char(&mut self, c: char) {
+        if self.wrap_pending {
+            self.cursor_col = 0;
+            self.line_feed();
+        }
+        let (col, style) = (self.cursor_col, self.style);
+        let row = self.cursor_row;
+        self.row_mut(row)[col] = Cell { ch: c, style };
+        if self.cursor_col + 1 >= self.cols {
+            self.wrap_pending = true;
+        } else {
+            self.cursor_col += 1;
+        }
+    }
+
+    fn line_feed(&mut self) {
+        self.wrap_pending = false;
+        if self.cursor_row == self.scroll_bottom {
+            self.scroll_up(1);
+        } else if self.cursor_row + 1 < self.rows {
+            self.cursor_row += 1;
+        }
+    }
+
+    fn reverse_index(&mut self) {
+        if self.cursor_row == self.scroll_top {
+            self.scroll_down(1);
+        } else {
+            self.cursor_row = self.cursor_row.saturating_sub(1);
+        }
+    }
+
+    fn full_region(&self) -> bool {
+        self.scroll_top == 0 && self.scroll_bottom == self.rows - 1
+    }
+
+    fn scroll_up(&mut self, count: usize) {
+        if self.full_region() && self.alternate.is_none() {
+            // The top lines move into the scrollback
+            for _ in 0..count.min(self.rows) {
+                let line = self.blank_line();
+                self.lines.push_back(line);
+            }
+        } else {
+            self.delete_lines(self.scroll_top, count);
+        }
+    }
+
+    fn scroll_down(&mut self, count: usize) {
+        self.insert_lines(self.scroll_top, count);
+    }
+
+    /// Remove lines at `row`, pulling up the rest of the scroll region
+    fn delete_lines(&mut self, row: usize, count: usize) {
+        let start = self.screen_start();
+        for _ in 0..count.min(self.scroll_bottom + 1 - row) {
+            self.lines.remove(start + row);
+            let line = self.blank_line();
+            self.lines.insert(start + self.scroll_bottom, line);
+        }
+    }
+
+    /// Open blank lines at `row`, pushing the rest of the scroll region down
+    fn insert_lines(&mut self, row: usize, count: usize) {
+        let start = self.screen_start();
+        for _ in 0..count.min(self.scroll_bottom + 1 - row) {
+            self.lines.remove(start + self.scroll_bottom);
+            let line = self.blank_line();
+            self.lines.insert(start + row, line);
+        }
+    }
+
+    fn in_scroll_region(&self) -> bool {
+        (self.scroll_top..=self.scroll_bottom).contains(&self.cursor_row)
+    }
+
+    fn clamp_cursor(&mut self) {
+        self.cursor_row = self.cursor_row.min(self.rows - 1);
+        self.cursor_col = self.cursor_col.min(self.cols - 1);
+        self.wrap_pending = false;
+    }
+
+    fn csi(&mut self, params: &str, command: char) {
+        let private = params.starts_with('?');
+        let values: Vec<usize> = params
+            .trim_start_matches(['?', '>', '='])
+            .split(';')
+            .map(|p| p.parse().unwrap_or(0))
+            .collect();
+        let arg = |index: usize, default: usize| values.get(index).copied().filter(|v| *v != 0).unwrap_or(default);
+
+        match command {
+            'A' => self.cursor_row = self.cursor_row.saturating_sub(arg(0, 1)),
+            'B' | 'e' => self.cursor_row += arg(0, 1),
+            'C' | 'a' => self.cursor_col += arg(0, 1),
+            'D' => self.cursor_col = self.cursor_col.saturating_sub(arg(0, 1)),
+            'E' => {
+                self.cursor_row += arg(0, 1);
+                self.cursor_col = 0;
+            }
+            'F' => {
+                self.cursor_row = self.cursor_row.saturating_sub(arg(0, 1));
+                self.cursor_col = 0;
+            }
+            'G' | '`' => self.cursor_col = arg(0, 1) - 1,
+            'd' => self.cursor_row = arg(0, 1) - 1,
+            'H' | 'f' => {
+                self.cursor_row = arg(0, 1) - 1;
+                self.cursor_col = arg(1, 1) - 1;
+            }
+            'J' => self.erase_display(values.first().copied().unwrap_or(0)),
+            'K' => self.erase_line(values.first().copied().unwrap_or(0)),
+            'L' if self.in_scroll_region() => self.insert_lines(self.cursor_row, arg(0, 1)),
+            // Deleted lines are gone, they never reach the scrollback
+            'M' if self.in_scroll_region() => self.delete_lines(self.cursor_row, arg(0, 1)),
+            'P' => {
+                let (row, col, count) = (self.cursor_row, self.cursor_col, arg(0, 1));
+                let blank = Cell::blank(self.style);
+                let cols = self.cols;
+                let line = self.row_mut(row);
+                let count = count.min(cols - col);
+                line.drain(col..col + count);
+                line.resize(cols, blank);
+            }
+            '@' => {
+                let (row, col, count) = (self.cursor_row, self.cursor_col, arg(0, 1));
+                let blank = Cell::blank(self.style);
+                let cols = self.cols;
+                let line = self.row_mut(row);
+                for _ in 0..count.min(cols - col) {
+                    line.insert(col, blank);
+                }
+                line.truncate(cols);
+            }
+            'X' => {
+                let (row, col, count) = (self.cursor_row, self.cursor_col, arg(0, 1));
+                let blank = Cell::blank(self.style);
+                let cols = self.cols;
+                for cell in &mut self.row_mut(row)[col..(col + count).min(cols)] {
+                    *cell = blank;
+                }
+            }
+            'S' => self.scroll_up(arg(0, 1)),
+            'T' => self.scroll_down(arg(0, 1)),
+            'm' => self.select_graphic_rendition(&values),
+            'r' => {
+                let top = arg(0, 1) - 1;
+                let bottom = arg(1, self.rows) - 1;
+                if top < bottom && bottom < self.rows {
+                    self.scroll_top = top;
+                    self.scroll_bottom = bottom;
+                    self.cursor_row = 0;
+                    self.cursor_col = 0;
+                }
+            }
+            's' => self.saved_cursor = (self.cursor_row, self.cursor_col),
+            'u' => (self.cursor_row, self.cursor_col) = self.saved_cursor,
+            'n' if values.first() == Some(&6) => {
+                let reply = format!("\x1b[{};{}R", self.cursor_row + 1, self.cursor_col + 1);
+                self.replies.extend_from_slice(reply.as_bytes());
+            }
+            'h' | 'l' if private => {
+                let enable = command == 'h';
+                for mode in &values {
+                    match mode {
+                        25 => self.cursor_visible = enable,
+                        47 | 1047 | 1049 => self.set_alternate_screen(enable),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+        // Colour changes and reports leave a pending wrap alone
+        if !matches!(command, 'm' | 'n') {
+            self.clamp_cursor();
+        }
+    }
+
+    fn erase_display(&mut self, mode: usize) {
+        let blank = Cell::blank(self.style);
+        let (row, col) = (self.cursor_row, self.cursor_col);
+        match mode {
+            0 => {
+                self.erase_line(0);
+                for r in row + 1..self.rows {
+                    self.row_mut(r).fill(blank);
+                }
+            }
+            1 => {
+                self.erase_line(1);
+                for r in 0..row {
+                    self.row_mut(r).fill(blank);
+                }
+            }
+            2 => {
+                for r in 0..self.rows {
+                    self.row_mut(r).fill(blank);
+                }
+            }
+            3 => {
+                let start = self.screen_start();
+                self.lines.drain(..start);
+            }
+            _ => {}
+        }
+        self.cursor_col = col;
+    }
+
+    fn erase_line(&mut self, mode: usize) {
+        let blank = Cell::blank(self.style);
+        let (row, col, cols) = (self.cursor_row, self.cursor_col, self.cols);
+        let line = self.row_mut(row);
+        let range = match mode {
+            0 => col..cols,
+            1 => 0..(col + 1).min(cols),
+            _ => 0..cols,
+        };
+        line[range].fill(blank);
+    }
+
+    fn select_graphic_rendition(&mut self, values: &[usize]) {
+        let mut i = 0;
+        while i < values.len() {
+            match values[i] {
+                0 => self.style = CellStyle::default(),
+                1 => self.style.bold = true,
+                4 => self.style.underline = true,
+                7 => self.style.inverse = true,
+                21 | 22 => self.style.bold = false,
+                24 => self.style.underline = false,
+                27 => self.style.inverse = false,
+                v @ 30..=37 => self.style.fg = TermColor::Indexed((v - 30) as u8),
+                39 => self.style.fg = TermColor::Default,
+                v @ 40..=47 => self.style.bg = TermColor::Indexed((v - 40) as u8),
+                49 => self.style.bg = TermColor::Default,
+                v @ 90..=97 => self.style.fg = TermColor::Indexed((v - 90 + 8) as u8),
+                v @ 100..=107 => self.style.bg = TermColor::Indexed((v - 100 + 8) as u8),
+                v @ (38 | 48) => {
+                    let color = match values.get(i + 1) {
+                        Some(5) => {
+                            i += 2;
+                            values.get(i).map(|index| TermColor::Indexed(*index as u8))
+                        }
+                        Some(2) => {
+                            i += 4;
+                            match values.get(i - 2..=i) {
+                                Some([r, g, b]) => Some(TermColor::Rgb(*r as u8, *g as u8, *b as u8)),
+                                _ => None,
+                            }
+                        }
+                        _ => None,
+                    };
+                    if let Some(color) = color {
+                        if v == 38 {
+                            self.style.fg = color;
+                        } else {
+                            self.style.bg = color;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+
+    fn set_alternate_screen(&mut self, enable: bool) {
+        if enable && self.alternate.is_none() {
+            // The alternate screen starts blank and has no scrollback; the main one is kept whole
+            let screen = (0..self.rows).map(|_| self.blank_line()).collect();
+            let saved = std::mem::replace(&mut self.lines, screen);
+            self.alternate = Some(SavedScreen {
+                lines: saved,
+                cursor: (self.cursor_row, self.cursor_col),
+            });
+        } else if !enable {
+            if let Some(saved) = self.alternate.take() {
+                // The main screen may have been saved at a smaller size
+                self.lines = saved.lines;
+                while self.lines.len() < self.rows {
+                    self.lines.push_back(vec![Cell::blank(CellStyle::default()); self.cols]);
+                }
+                (self.cursor_row, self.cursor_col) = saved.cursor;
+                self.clamp_cursor();
+            }
+        }
+        self.scroll_top = 0;
+        self.scroll_bottom = self.rows - 1;
+    }
+
+    /// Lay out one line for egui, drawing the cursor as an inverted cell
+    pub fn line_job(&self, index: usize, font_id: &FontId, foreground: Color32, background: Color32) -> LayoutJob {
+        let mut job = LayoutJob::default();
+        let Some(line) = self.lines.get(index) else {
+            return job;
+        };
+        let cursor_col = (self.cursor_visible && index == self.screen_start() + self.cursor_row).then_some(self.cursor_col);
+
+        // Skip trailing blanks, but never the cursor cell
+        let used = line
+            .iter()
+            .rposition(|cell| *cell != Cell::blank(CellStyle::default()))
+            .map(|i| i + 1)
+            .unwrap_or(0)
+            .max(cursor_col.map(|c| c + 1).unwrap_or(0));
+
+        let mut run = String::new();
+        let mut run_format: Option<TextFormat> = None;
+        for (col, cell) in line.iter().take(used).enumerate() {
+            let mut inverse = cell.style.inverse;
+            if cursor_col == Some(col) {
+                inverse = !inverse;
+            }
+            let mut fg = cell.style.fg.to_color32(foreground, cell.style.bold);
+            let mut bg = cell.style.bg.to_color32(Color32::TRANSPARENT, false);
+            if inverse {
+                let swapped_bg = fg;
+                fg = if bg == Color32::TRANSPARENT { background } else { bg };
+                bg = swapped_bg;
+            }
+            let format = TextFormat {
+                font_id: font_id.clone(),
+                color: fg,
+                background: bg,
+                underline: if cell.style.underline { Stroke::new(1.0, fg) } else { Stroke::NONE },
+                ..Default::default()
+            };
+            if run_format.as_ref() != Some(&format) {
+                if let Some(previous) = run_format.take() {
+                    job.append(&std::mem::take(&mut run), 0.0, previous);
+                }
+                run_format = Some(format);
+            }
+            run.push(cell.ch);
+        }
+        match run_format {
+            Some(format) => job.append(&run, 0.0, format),
+            // Keep empty lines one row tall
+            None => job.append(" ", 0.0, TextFormat { font_id: font_id.clone(), ..Default::default() }),
+        }
+        job
+    }
+
+    /// Plain text of the whole buffer, for copying out
+    pub fn text(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| line.iter().map(|cell| cell.ch).collect::<String>().trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim_end()
+            .to_string()
+    }
+}
+
+/// Bytes a terminal sends for keys that produce no text; `None` for everything else
+pub fn key_bytes(key: Key, modifiers: Modifiers) -> Option<Vec<u8>> {
+    if modifiers.ctrl {
+        let name = key.name();
+        if name.len() == 1 && name.as_bytes()[0].is_ascii_uppercase() {
+            return Some(vec![name.as_bytes()[0] - b'A' + 1]);
+        }
+    }
+    let sequence: &[u8] = match key {
+        Key::Enter => b"\r",
+        Key::Backspace => b"\x7f",
+        Key::Tab => b"\t",
+        Key::Escape => b"\x1b",
+        Key::ArrowUp => b"\x1b[A",
+        Key::ArrowDown => b"\x1b[B",
+        Key::ArrowRight => b"\x1b[C",
+        Key::ArrowLeft => b"\x1b[D",
+        Key::Home => b"\x1b[H",
+        Key::End => b"\x1b[F",
+        Key::Insert => b"\x1b[2~",
+        Key::Delete => b"\x1b[3~",
+        Key::PageUp => b"\x1b[5~",
+        Key::PageDown => b"\x1b[6~",
+        _ => return None,
+    };
+    Some(sequence.to_vec())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminalStatus {
+    Running,
+    Exited(Option<u8>),
+    Failed(String),
+}
+
+impl TerminalStatus {
+    pub fn description(&self) -> String {
+        match self {
+            Self::Running => "🟢 Running".to_string(),
+            Self::Exited(Some(code)) => format!("⏹️ Exited with code {}", code),
+            Self::Exited(None) => "⏹️ Exited".to_string(),
+            Self::Failed(reason) => format!("❌ {}", reason),
+        }
+    }
+}
+
+enum TerminalEvent {
+    Output(Vec<u8>),
+    Exited(Option<u8>),
+    Failed(String),
+}
+
+/// An interactive `adb shell` with a PTY, kept open per device
+pub struct TerminalSession {
+    pub serial: String,
+    pub screen: TerminalScreen,
+    pub status: TerminalStatus,
+    /// `shell,v2` frames input and supports resizing; legacy `shell:` is a raw byte stream
+    v2: bool,
+    stream: TcpStream,
+    receiver: Receiver<TerminalEvent>,
+    reader: Option<JoinHandle<()>>,
+    /// What has been typed since the last Enter, or `None` once editing keys make it unknowable
+    typed: Option<String>,
+}
+
+impl std::fmt::Debug for TerminalSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TerminalSession")
+            .field("serial", &self.serial)
+            .field("status", &self.status)
+            .field("v2", &self.v2)
+            .finish()
+    }
+}
+
+impl TerminalSession {
+    pub fn open(
+        client: &AdbClient,
+        serial: &str,
+        rows: usize,
+        cols: usize,
+        repaint: Option<egui::Context>,
+    ) -> Result<Self, String> {
+        let (stream, v2) = match client.open_service(serial, "shell,v2,TERM=xterm-256color,pty:") {
+            Ok(stream) => (stream, true),
+            // Devices before Android 7 only have the legacy service, which is a PTY without a command
+            Err(e) if is_unsupported_service(e.as_ref()) => {
+                (client.open_service(serial, "shell:").map_err(|e| format!("Failed to open shell: {}", e))?, false)
+            }
+            Err(e) => return Err(format!("Failed to open shell: {}", e)),
+        };
+        let reader_stream = stream.try_clone().map_err(|e| e.to_string())?;
+        let (sender, receiver) = mpsc::channel();
+        let reader = thread::spawn(move || read_shell(reader_stream, v2, sender, repaint));
+
+        let mut session = Self {
+            serial: serial.to_string(),
+            screen: TerminalScreen::new(rows, cols),
+            status: TerminalStatus::Running,
+            v2,
+            stream,
+            receiver,
+            reader: Some(reader),
+            typed: Some(String::new()),
+        };
+        session.send_window_size();
+        Ok(session)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.status == TerminalStatus::Running
+    }
+
+    /// Apply output from the reader thread and answer any terminal queries
+    pub fn poll(&mut self) {
+        while let Ok(event) = self.receiver.try_recv() {
+            match event {
+                TerminalEvent::Output(bytes) => self.screen.feed(&bytes),
+                TerminalEvent::Exited(code) => self.status = TerminalStatus::Exited(code),
+                TerminalEvent::Failed(reason) => self.status = TerminalStatus::Failed(reason),
+            }
+        }
+        let replies = self.screen.take_replies();
+        if !replies.is_empty() {
+            self.send(&replies);
+        }
+    }
+
+    pub fn send(&mut self, bytes: &[u8]) {
+        if !self.is_running() {
+            return;
+        }
+        let result = if self.v2 {
+            write_packet(&mut self.stream, ID_STDIN, bytes)
+        } else {
+            self.stream.write_all(bytes)
+        };
+        if let Err(e) = result {
+            self.status = TerminalStatus::Failed(e.to_string());
+        }
+    }
+
+    /// Send typed or pasted text, tracking it as the current command line
+    pub fn type_text(&mut self, text: &str) {
+        if let Some(typed) = &mut self.typed {
+            typed.push_str(text);
+        }
+        // Terminals send Enter as CR, including in pasted text
+        let text = text.replace("\r\n", "\r").replace('\n', "\r");
+        self.send(text.as_bytes());
+    }
+
+    /// Send a non-text key; returns the finished command line when the key was Enter
+    pub fn press_key(&mut self, key: Key, modifiers: Modifiers) -> Option<String> {
+        let bytes = key_bytes(key, modifiers)?;
+        let mut finished = None;
+        match key {
+            Key::Enter => {
+                finished = self.typed.replace(String::new()).filter(|line| !line.trim().is_empty());
+            }
+            Key::Backspace => {
+                if let Some(typed) = &mut self.typed {
+                    typed.pop();
+                }
+            }
+            // Ctrl+C and Ctrl+U throw the line away
+            Key::C | Key::U if modifiers.ctrl => self.typed = Some(String::new()),
+            // Completion, history recall and cursor movement edit the line out of sight
+            _ => self.typed = None,
+        }
+        self.send(&bytes);
+        finished
+    }
+
+    /// Ctrl+C
+    pub fn interrupt(&mut self) {
+        self.typed = Some(String::new());
+        self.send(&[0x03]);
+    }
+
+    pub fn resize(&mut self, rows: usize, cols: usize) {
+        if self.screen.size() != (rows.max(1), cols.max(1)) {
+            self.screen.resize(rows, cols);
+            self.send_window_size();
+        }
+    }
+
+    fn send_window_size(&mut self) {
+        // Legacy shell has no way to resize; the PTY stays 80x24
+        if !self.v2 || !self.is_running() {
+            return;
+        }
+        let (rows, cols) = self.screen.size();
+        let payload = format!("{}x{},0x0\0", rows, cols);
+        if let Err(e) = write_packet(&mut self.stream, ID_WINDOW_SIZE, payload.as_bytes()) {
+            self.status = TerminalStatus::Failed(e.to_string());
+        }
+    }
+
+    pub fn close(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+        if let Some(handle) = self.reader.take() {
+            let _ = handle.join();
+        }
+        if self.is_running() {
+            self.status = TerminalStatus::Exited(None);
+        }
+    }
+}
+
+impl Drop for TerminalSession {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+fn write_packet(stream: &mut TcpStream, id: u8, data: &[u8]) -> std::io::Result<()> {
+    let mut packet = Vec::with_capacity(5 + data.len());
+    packet.push(id);
+    packet.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    packet.extend_from_slice(data);
+    stream.write_all(&packet)
+}
+
+fn read_shell(mut stream: TcpStream, v2: bool, sender: Sender<TerminalEvent>, repaint: Option<egui::Context>) {
+    let send = |event: TerminalEvent| {
+        let _ = sender.send(event);
+        if let Some(ctx) = &repaint {
+            ctx.request_repaint();
+        }
+    };
+
+    let result = if v2 {
+        read_v2_packets(&mut stream, &send)
+    } else {
+        let mut buffer = vec![0u8; READ_CHUNK];
+        loop {
+            match stream.read(&mut buffer) {
+                Ok(0) => break Ok(None),
+                Ok(read) => send(TerminalEvent::Output(buffer[..read].to_vec())),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => break Err(e),
+            }
+        }
+    };
+
+    match result {
+        Ok(code) => send(TerminalEvent::Exited(code)),
+        // Closing the session shuts the socket down under the reader
+        Err(e) if matches!(e.kind(), ErrorKind::NotConnected | ErrorKind::ConnectionAborted) => {
+            send(TerminalEvent::Exited(None))
+        }
+        Err(e) => send(TerminalEvent::Failed(e.to_string())),
+    }
+}
+
+fn read_v2_packets(stream: &mut TcpStream, send: &impl Fn(TerminalEvent)) -> std::io::Result<Option<u8>> {
+    let mut header = [0u8; 5];
+    loop {
+        match stream.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]) as usize;
+        let mut data = vec![0u8; len];
+        stream.read_exact(&mut data)?;
+        match header[0] {
+            ID_STDOUT | ID_STDERR => send(TerminalEvent::Output(data)),
+            ID_EXIT => return Ok(data.first().copied()),
+            _ => {}
+        }
+    }
+}
+
+/// Append a command to the history, moving a repeat to the end
+pub fn push_history(history: &mut Vec<String>, command: &str) {
+    let command = command.trim();
+    if command.is_empty() {
+        return;
+    }
+    history.retain(|entry| entry != command);
+    history.push(command.to_string());
+    if history.len() > MAX_SHELL_HISTORY {
+        let excess = history.len() - MAX_SHELL_HISTORY;
+        history.drain(..excess);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaving_the_alternate_screen_restores_the_main_screen() {
+        let mut screen = TerminalScreen::new(4, 20);
+        screen.feed(b"$ ls\r\nfile1 file2\r\n$ vi");
+        screen.feed(b"\x1b[?1049h\x1b[Hediting");
+        assert!(!screen.text().contains("file1"));
+
+        screen.feed(b"\x1b[?1049l");
+        let text = screen.text();
+        assert!(text.contains("$ ls"));
+        assert!(text.contains("file1 file2"));
+        assert!(text.contains("$ vi"));
+        assert!(!text.contains("editing"));
+    }
+}
@@ -1,10 +1,55 @@
 use egui::{ComboBox, Grid, RichText, ScrollArea, Ui};
-use egui_plot::{Line, Plot, PlotPoints};
+use egui_extras::{Column, TableBuilder};
+use egui_plot::{HLine, Legend, Line, LineStyle, Plot, PlotPoints, VLine};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
-use std::process::Command;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use crate::tools::adb_apk::{
+    inspect_apk, install_single_apk, install_splits, is_split_set, load_apk_splits, query_device_split_config, select_splits, ApkInfo,
+    ApkSplit, InstallOptions,
+};
+use crate::tools::adb_backup::{backup_app_data, restore_app_data, AppBackupOptions, BackupMethod};
+use crate::tools::adb_broadcast::{run_broadcast, BroadcastResult, BroadcastRun, BroadcastView};
+use crate::tools::adb_client::{remote_join, shell_quote, AdbClient};
+use crate::tools::adb_forward::{
+    add_forward, apply_profile, list_forwards, remove_all_forwards, remove_forward, ForwardDirection, ForwardProfile,
+    ForwardRule, SocketKind, PROFILE_CHECK_INTERVAL,
+};
+use crate::tools::adb_files::{
+    normalize_remote_dir, parse_ls_la, remote_breadcrumbs, remote_parent, RemoteFileEntry, RemoteNavigation,
+};
+use crate::tools::adb_heap::{dump_heap, HeapDumpResult};
+use crate::tools::adb_jobs::{JobContext, JobId, JobManager};
+use crate::tools::adb_logcat::{
+    parse_logcat_text, LogEntry, LogPriority, LogcatFilter, LogcatSession, DEFAULT_LOGCAT_BUFFER_LINES,
+};
+use crate::tools::adb_monitor::{
+    sample_app, sample_cpu, sample_network, sample_thermal, sample_uid_traffic, thermal_status_name, AppSample,
+    AppTracking, CpuUtilisation, InterfaceRates, NetworkCounters, ThermalInfo, UidTraffic,
+};
+use crate::tools::adb_packages::{
+    fetch_package_details, run_package_action, AppOp, ComponentState, PackageAction, PackageActionResult,
+    PackageDetails, PackageListFilter, APP_OP_MODES,
+};
+use crate::tools::adb_screen::{
+    record_screen, InputAction, RemoteInput, ScreenMirror, ScreenMirrorOptions, ScreenRecordOptions,
+    KEYPAD_KEYS, MAX_SEGMENT_SECS,
+};
+use crate::tools::adb_snippets::{
+    fill_placeholders, placeholders, run_macro, MacroStep, PromptTarget, ShellMacro, Snippet, SnippetEditor, SnippetLibrary,
+    SnippetPrompt,
+};
+use crate::tools::adb_sync::{apply_sync, plan_sync, SyncDirection, SyncOptions, SyncPlan};
+use crate::tools::adb_terminal::{push_history, TerminalSession, DEFAULT_COLS, DEFAULT_ROWS};
+use crate::tools::adb_wireless::{
+    connect, disconnect, enable_tcpip, mdns_services, normalize_address, pair, reconnect_dropped, MdnsService,
+    WirelessEndpoint, DEFAULT_TCPIP_PORT, RECONNECT_INTERVAL,
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DataPoint {
     pub timestamp: f64, // seconds since monitoring started
@@ -13,10 +58,22 @@ pub struct DataPoint {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimeSeriesData {
+    /// Total CPU utilisation in percent
     pub cpu_usage: VecDeque<DataPoint>,
+    /// Utilisation of each core in percent, by core index
+    #[serde(default)]
+    pub cpu_cores: BTreeMap<usize, VecDeque<DataPoint>>,
     pub memory_usage: VecDeque<DataPoint>,
     pub battery_level: VecDeque<DataPoint>,
     pub battery_temperature: VecDeque<DataPoint>,
+    /// Temperature of each thermal zone in °C, by zone index
+    #[serde(default)]
+    pub thermal_zones: BTreeMap<usize, VecDeque<DataPoint>>,
+    /// Throughput of each network interface, by interface name
+    #[serde(default)]
+    pub network: BTreeMap<String, InterfaceSeries>,
+    #[serde(default)]
+    pub process: ProcessSeries,
     #[serde(skip)]
     pub start_time: Option<Instant>,
     pub max_points: usize,
@@ -26,15 +83,126 @@ impl Default for TimeSeriesData {
     fn default() -> Self {
         Self {
             cpu_usage: VecDeque::new(),
+            cpu_cores: BTreeMap::new(),
             memory_usage: VecDeque::new(),
             battery_level: VecDeque::new(),
             battery_temperature: VecDeque::new(),
+            thermal_zones: BTreeMap::new(),
+            network: BTreeMap::new(),
+            process: ProcessSeries::default(),
             start_time: None,
             max_points: 1000, // Keep last 1000 data points by default
         }
     }
 }
 
+/// Resource usage of the tracked app or process
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessSeries {
+    /// Package, process name or PID being tracked
+    pub label: String,
+    pub pss_mb: VecDeque<DataPoint>,
+    pub rss_mb: VecDeque<DataPoint>,
+    pub java_heap_mb: VecDeque<DataPoint>,
+    pub native_heap_mb: VecDeque<DataPoint>,
+    pub cpu_percent: VecDeque<DataPoint>,
+    pub threads: VecDeque<DataPoint>,
+    pub fds: VecDeque<DataPoint>,
+    /// When the process came back with a new PID, in seconds since monitoring started
+    pub restarts: Vec<f64>,
+}
+
+impl ProcessSeries {
+    fn push(&mut self, timestamp: f64, sample: &AppSample, max_points: usize) {
+        let mb = |kb: Option<u64>| kb.map(|kb| kb as f64 / 1024.0);
+        let values = [
+            (&mut self.pss_mb, mb(sample.pss_kb)),
+            (&mut self.rss_mb, mb(sample.rss_kb)),
+            (&mut self.java_heap_mb, mb(sample.java_heap_kb)),
+            (&mut self.native_heap_mb, mb(sample.native_heap_kb)),
+            (&mut self.cpu_percent, sample.cpu_percent),
+            (&mut self.threads, sample.threads.map(|t| t as f64)),
+            (&mut self.fds, sample.fds.map(|f| f as f64)),
+        ];
+        for (series, value) in values {
+            if let Some(value) = value {
+                series.push_back(DataPoint { timestamp, value });
+            }
+            while series.len() > max_points {
+                series.pop_front();
+            }
+        }
+    }
+
+    fn trim(&mut self, max_points: usize) {
+        for series in [
+            &mut self.pss_mb,
+            &mut self.rss_mb,
+            &mut self.java_heap_mb,
+            &mut self.native_heap_mb,
+            &mut self.cpu_percent,
+            &mut self.threads,
+            &mut self.fds,
+        ] {
+            while series.len() > max_points {
+                series.pop_front();
+            }
+        }
+        let oldest = self.pss_mb.front().or(self.cpu_percent.front()).map(|p| p.timestamp);
+        if let Some(oldest) = oldest {
+            self.restarts.retain(|t| *t >= oldest);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pss_mb.is_empty() && self.rss_mb.is_empty() && self.cpu_percent.is_empty() && self.threads.is_empty()
+    }
+}
+
+/// Throughput of one network interface
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InterfaceSeries {
+    pub rx_kb_per_sec: VecDeque<DataPoint>,
+    pub tx_kb_per_sec: VecDeque<DataPoint>,
+    pub rx_packets_per_sec: VecDeque<DataPoint>,
+    pub tx_packets_per_sec: VecDeque<DataPoint>,
+}
+
+impl InterfaceSeries {
+    fn push(&mut self, timestamp: f64, rates: &InterfaceRates, max_points: usize) {
+        let values = [
+            (&mut self.rx_kb_per_sec, rates.rx_bytes_per_sec / 1024.0),
+            (&mut self.tx_kb_per_sec, rates.tx_bytes_per_sec / 1024.0),
+            (&mut self.rx_packets_per_sec, rates.rx_packets_per_sec),
+            (&mut self.tx_packets_per_sec, rates.tx_packets_per_sec),
+        ];
+        for (series, value) in values {
+            series.push_back(DataPoint { timestamp, value });
+            while series.len() > max_points {
+                series.pop_front();
+            }
+        }
+    }
+
+    fn trim(&mut self, max_points: usize) {
+        for series in [
+            &mut self.rx_kb_per_sec,
+            &mut self.tx_kb_per_sec,
+            &mut self.rx_packets_per_sec,
+            &mut self.tx_packets_per_sec,
+        ] {
+            while series.len() > max_points {
+                series.pop_front();
+            }
+        }
+    }
+
+    /// Interfaces that carried nothing while monitored aren't worth a plot
+    fn has_traffic(&self) -> bool {
+        self.rx_packets_per_sec.iter().chain(&self.tx_packets_per_sec).any(|p| p.value > 0.0)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessInfo {
     pub pid: String,
@@ -54,46 +222,241 @@ pub struct AdbDevice {
     pub transport_id: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Everything collected by one monitoring tick, applied to the state on the UI thread
+#[derive(Debug, Default)]
+pub struct MonitoringSnapshot {
+    pub cpu_usage: String,
+    pub cpu: Option<CpuUtilisation>,
+    pub memory_info: HashMap<String, String>,
+    pub battery_info: HashMap<String, String>,
+    pub thermal_info: String,
+    pub thermal: Option<ThermalInfo>,
+    pub network: Option<NetworkCounters>,
+    pub process_list: Vec<ProcessInfo>,
+    /// The tracked target and its sample, when an app is being tracked
+    pub app: Option<(String, Result<AppSample, String>)>,
+}
+
+/// Results produced by background ADB jobs
+#[derive(Debug)]
+pub enum AdbJobOutput {
+    Devices(Vec<AdbDevice>),
+    DeviceInfo(HashMap<String, String>),
+    Packages(Vec<String>),
+    PackageUninstalled(String),
+    PackageBatch { results: Vec<PackageActionResult>, refresh: bool },
+    PackageDetails { details: PackageDetails, app_ops: Vec<AppOp>, message: Option<String> },
+    ApkInspected { path: String, info: Option<ApkInfo>, splits: Vec<ApkSplit>, message: String },
+    Monitoring(MonitoringSnapshot),
+    Processes(Vec<ProcessInfo>),
+    ProcessKilled(String),
+    UidTraffic { source: String, entries: Vec<UidTraffic> },
+    LogcatPids { serial: String, package: String, pids: HashSet<u32> },
+    LogcatFile { name: String, entries: Vec<LogEntry> },
+    RemoteListing { path: String, entries: Vec<RemoteFileEntry>, message: Option<String> },
+    SyncPlanned(SyncPlan),
+    SyncApplied(String),
+    Broadcast(BroadcastRun),
+    HeapDump(HeapDumpResult),
+    Forwards { serial: String, rules: Vec<ForwardRule>, message: Option<String> },
+    ForwardProfilesChecked { online: HashSet<String>, applied: Vec<String>, message: Option<String> },
+    Wireless { devices: Option<Vec<AdbDevice>>, connected: Option<String>, message: Option<String> },
+    MdnsServices(Vec<MdnsService>),
+    Text(String),
+}
+
+type AdbJobContext = JobContext<AdbJobOutput>;
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct AdbToolsState {
     pub selected_device: Option<String>,
     pub devices: Vec<AdbDevice>,
     pub last_refresh: String,
+    #[serde(skip)]
+    pub device_refresh_job: Option<JobId>,
+    
+    // Wireless Debugging
+    pub wireless_endpoints: Vec<WirelessEndpoint>,
+    pub wireless_address: String,
+    #[serde(skip)]
+    pub wireless_pair_address: String,
+    #[serde(skip)]
+    pub wireless_pair_code: String,
+    pub wireless_tcpip_port: String,
+    pub wireless_result: String,
+    #[serde(skip)]
+    pub mdns_services: Vec<MdnsService>,
+    #[serde(skip)]
+    pub wireless_last_check: Option<Instant>,
+    #[serde(skip)]
+    pub wireless_check_job: Option<JobId>,
     
     // Device Info
     pub device_info: HashMap<String, String>,
     
     // App Management
     pub package_filter: String,
+    pub package_list_filter: PackageListFilter,
+    #[serde(skip)]
+    pub package_selection: HashSet<String>,
+    #[serde(skip)]
+    pub package_confirm_action: Option<PackageAction>,
+    #[serde(skip)]
+    pub package_batch_results: Vec<PackageActionResult>,
+    pub apk_pull_dir: String,
+    pub app_backup_options: AppBackupOptions,
     pub installed_packages: Vec<String>,
     pub apk_path: String,
+    #[serde(skip)]
+    pub apk_info: Option<ApkInfo>,
+    /// Inspection of `apk_path` has finished, whether or not the manifest could be decoded
+    #[serde(skip)]
+    pub apk_inspected: bool,
+    #[serde(skip)]
+    pub apk_splits: Vec<ApkSplit>,
+    pub install_options: InstallOptions,
+    #[serde(skip)]
+    pub selected_package: Option<String>,
+    #[serde(skip)]
+    pub package_details: Option<PackageDetails>,
+    #[serde(skip)]
+    pub package_app_ops: Vec<AppOp>,
+    #[serde(skip)]
+    pub app_op_name: String,
+    #[serde(skip)]
+    pub app_op_mode: String,
+    #[serde(skip)]
+    pub app_result: String,
     
     // File Operations
     pub local_path: String,
     pub remote_path: String,
     pub file_operation_result: String,
+    pub remote_navigation: RemoteNavigation,
+    #[serde(skip)]
+    pub remote_entries: Vec<RemoteFileEntry>,
+    #[serde(skip)]
+    pub remote_listed_path: Option<String>,
+    #[serde(skip)]
+    pub remote_selection: HashSet<String>,
+    #[serde(skip)]
+    pub remote_confirm_delete: bool,
+    pub remote_go_to: String,
+    pub remote_rename_to: String,
+    pub remote_new_folder: String,
+    pub remote_chmod_mode: String,
+    pub sync_options: SyncOptions,
+    #[serde(skip)]
+    pub sync_plan: Option<SyncPlan>,
     
     // Shell Commands
     pub shell_command: String,
     pub shell_output: String,
+    #[serde(skip)]
+    pub shell_broadcast: bool,
+    #[serde(skip)]
+    pub shell_broadcast_targets: HashSet<String>,
+    #[serde(skip)]
+    pub shell_broadcast_run: Option<BroadcastRun>,
+    #[serde(skip)]
+    pub shell_broadcast_view: BroadcastView,
+    #[serde(skip)]
+    pub shell_broadcast_diff_only: bool,
+    pub shell_history: Vec<String>,
+    #[serde(skip)]
+    pub terminal_sessions: HashMap<String, TerminalSession>,
+    pub snippet_library: SnippetLibrary,
+    pub snippet_file_path: String,
+    #[serde(skip)]
+    pub snippet_filter: String,
+    #[serde(skip)]
+    pub snippet_editor: SnippetEditor,
+    #[serde(skip)]
+    pub snippet_prompt: Option<SnippetPrompt>,
+    /// Last value given to each placeholder, offered again next time
+    #[serde(skip)]
+    pub snippet_values: HashMap<String, String>,
+    #[serde(skip)]
+    pub snippet_result: String,
     
     // Logcat
-    pub logcat_filter: String,
-    pub logcat_output: String,
-    pub logcat_running: bool,
+    pub logcat_filters: LogcatFilter,
+    pub logcat_buffer_lines: usize,
+    #[serde(skip)]
+    pub logcat_filter_error: Option<String>,
+    pub logcat_import_path: String,
+    pub logcat_export_path: String,
+    pub logcat_export_filtered: bool,
+    pub logcat_file_result: String,
+    #[serde(skip)]
+    pub logcat_offline: Option<LogcatSession>,
+    #[serde(skip)]
+    pub logcat_show_offline: bool,
+    #[serde(skip)]
+    pub logcat_sessions: HashMap<String, LogcatSession>,
     
     // Screen Capture
     pub screenshot_path: String,
     pub screen_record_path: String,
+    pub screen_record_options: ScreenRecordOptions,
+    #[serde(skip)]
+    pub screen_result: String,
+    #[serde(skip)]
+    pub screen_record_job: Option<JobId>,
+    #[serde(skip)]
+    pub screen_record_stop: Arc<AtomicBool>,
+    pub screen_mirror_options: ScreenMirrorOptions,
+    #[serde(skip)]
+    pub screen_mirror: Option<ScreenMirror>,
+    #[serde(skip)]
+    pub remote_input: Option<RemoteInput>,
+    #[serde(skip)]
+    pub remote_input_enabled: bool,
+    #[serde(skip)]
+    pub remote_input_text: String,
+    #[serde(skip)]
+    pub remote_gesture: RemoteGesture,
     
     // Port Forwarding
+    pub forward_direction: ForwardDirection,
+    pub forward_local_kind: SocketKind,
     pub local_port: String,
+    pub forward_remote_kind: SocketKind,
     pub remote_port: String,
-    pub forwarded_ports: Vec<(String, String)>,
+    #[serde(skip)]
+    pub port_forwards: Vec<ForwardRule>,
+    #[serde(skip)]
+    pub port_forwards_device: Option<String>,
+    #[serde(skip)]
+    pub forward_result: String,
+    pub forward_profiles: Vec<ForwardProfile>,
+    #[serde(skip)]
+    pub forward_profile_name: String,
+    #[serde(skip)]
+    pub forward_profile_online: HashSet<String>,
+    #[serde(skip)]
+    pub forward_last_check: Option<Instant>,
+    #[serde(skip)]
+    pub forward_check_job: Option<JobId>,
     
     // Device Monitoring
     pub monitoring_enabled: bool,
     pub cpu_usage: String,
+    #[serde(skip)]
+    pub cpu_utilisation: Option<CpuUtilisation>,
+    pub app_track_target: String,
+    #[serde(skip)]
+    pub app_tracking: Option<AppTracking>,
+    #[serde(skip)]
+    pub monitor_job: Option<JobId>,
+    #[serde(skip)]
+    pub monitor_result: String,
+    pub heap_dump_target: String,
+    pub heap_dump_dir: String,
+    #[serde(skip)]
+    pub heap_dump: Option<HeapDumpResult>,
+    #[serde(skip)]
+    pub heap_histogram_filter: String,
     pub memory_info: HashMap<String, String>,
     pub process_list: Vec<ProcessInfo>,
     pub selected_process: Option<String>,
@@ -102,7 +465,23 @@ pub struct AdbToolsState {
     pub monitor_interval: f32, // seconds
     pub battery_info: HashMap<String, String>,
     pub thermal_info: String,
-    pub network_stats: HashMap<String, String>,
+    #[serde(skip)]
+    pub thermal: Option<ThermalInfo>,
+    /// Alert temperature in °C, by zone type
+    pub thermal_thresholds: BTreeMap<String, f64>,
+    /// Zone types drawn on the thermal plot
+    pub thermal_plot_zones: BTreeSet<String>,
+    #[serde(skip)]
+    pub network_counters: Option<NetworkCounters>,
+    #[serde(skip)]
+    pub network_rates: Vec<InterfaceRates>,
+    pub network_hide_idle: bool,
+    pub network_plot_packets: bool,
+    #[serde(skip)]
+    pub uid_traffic: Vec<UidTraffic>,
+    #[serde(skip)]
+    pub uid_traffic_source: String,
+    pub uid_traffic_filter: String,
     
     #[serde(skip)]
     pub last_update_time: Option<Instant>,
@@ -130,6 +509,14 @@ pub struct AdbToolsState {
     pub systemd_service_name: String,
     pub systemd_unit_filter: String,
     pub systemd_service_list: Vec<String>,
+    
+    // ADB server connection
+    #[serde(skip)]
+    pub adb_client: AdbClient,
+    
+    // Background jobs
+    #[serde(skip)]
+    pub jobs: JobManager<AdbJobOutput>,
 }
 
 impl Default for AdbToolsState {
@@ -144,25 +531,112 @@ impl Default for AdbToolsState {
             selected_device: None,
             devices: Vec::new(),
             last_refresh: "Never".to_string(),
+            device_refresh_job: None,
+            wireless_endpoints: Vec::new(),
+            wireless_address: String::new(),
+            wireless_pair_address: String::new(),
+            wireless_pair_code: String::new(),
+            wireless_tcpip_port: DEFAULT_TCPIP_PORT.to_string(),
+            wireless_result: String::new(),
+            mdns_services: Vec::new(),
+            wireless_last_check: None,
+            wireless_check_job: None,
             device_info: HashMap::new(),
             package_filter: String::new(),
+            package_list_filter: PackageListFilter::default(),
+            package_selection: HashSet::new(),
+            package_confirm_action: None,
+            package_batch_results: Vec::new(),
+            apk_pull_dir: "apks".to_string(),
+            app_backup_options: AppBackupOptions::default(),
             installed_packages: Vec::new(),
             apk_path: String::new(),
+            apk_info: None,
+            apk_inspected: false,
+            apk_splits: Vec::new(),
+            install_options: InstallOptions::default(),
+            selected_package: None,
+            package_details: None,
+            package_app_ops: Vec::new(),
+            app_op_name: String::new(),
+            app_op_mode: "allow".to_string(),
+            app_result: String::new(),
             local_path: String::new(),
             remote_path: "/sdcard/".to_string(),
             file_operation_result: String::new(),
+            remote_navigation: RemoteNavigation::default(),
+            remote_entries: Vec::new(),
+            remote_listed_path: None,
+            remote_selection: HashSet::new(),
+            remote_confirm_delete: false,
+            remote_go_to: String::new(),
+            remote_rename_to: String::new(),
+            remote_new_folder: String::new(),
+            remote_chmod_mode: String::new(),
+            sync_options: SyncOptions::default(),
+            sync_plan: None,
             shell_command: String::new(),
             shell_output: String::new(),
-            logcat_filter: String::new(),
-            logcat_output: String::new(),
-            logcat_running: false,
+            shell_broadcast: false,
+            shell_broadcast_targets: HashSet::new(),
+            shell_broadcast_run: None,
+            shell_broadcast_view: BroadcastView::default(),
+            shell_broadcast_diff_only: false,
+            shell_history: Vec::new(),
+            terminal_sessions: HashMap::new(),
+            snippet_library: SnippetLibrary::default(),
+            snippet_file_path: "snippets.toml".to_string(),
+            snippet_filter: String::new(),
+            snippet_editor: SnippetEditor::default(),
+            snippet_prompt: None,
+            snippet_values: HashMap::new(),
+            snippet_result: String::new(),
+            logcat_filters: LogcatFilter::default(),
+            logcat_buffer_lines: DEFAULT_LOGCAT_BUFFER_LINES,
+            logcat_filter_error: None,
+            logcat_import_path: String::new(),
+            logcat_export_path: "logcat.txt".to_string(),
+            logcat_export_filtered: false,
+            logcat_file_result: String::new(),
+            logcat_offline: None,
+            logcat_show_offline: false,
+            logcat_sessions: HashMap::new(),
             screenshot_path: "screenshot.png".to_string(),
             screen_record_path: "screen_record.mp4".to_string(),
+            screen_record_options: ScreenRecordOptions::default(),
+            screen_result: String::new(),
+            screen_record_job: None,
+            screen_record_stop: Arc::new(AtomicBool::new(false)),
+            screen_mirror_options: ScreenMirrorOptions::default(),
+            screen_mirror: None,
+            remote_input: None,
+            remote_input_enabled: false,
+            remote_input_text: String::new(),
+            remote_gesture: RemoteGesture::default(),
+            forward_direction: ForwardDirection::default(),
+            forward_local_kind: SocketKind::default(),
             local_port: "8080".to_string(),
+            forward_remote_kind: SocketKind::default(),
             remote_port: "8080".to_string(),
-            forwarded_ports: Vec::new(),
+            port_forwards: Vec::new(),
+            port_forwards_device: None,
+            forward_result: String::new(),
+            forward_profiles: Vec::new(),
+            forward_profile_name: String::new(),
+            forward_profile_online: HashSet::new(),
+            forward_last_check: None,
+            forward_check_job: None,
             monitoring_enabled: false,
             cpu_usage: String::new(),
+            cpu_utilisation: None,
+            app_track_target: String::new(),
+            app_tracking: None,
+            monitor_job: None,
+            monitor_result: String::new(),
+            heap_dump_target: String::new(),
+            heap_dump_dir: "heapdumps".to_string(),
+            heap_dump: None,
+            heap_histogram_filter: String::new(),
             memory_info: HashMap::new(),
             process_list: Vec::new(),
             selected_process: None,
@@ -171,7 +645,16 @@ impl Default for AdbToolsState {
             monitor_interval: 0.5,
             battery_info: HashMap::new(),
             thermal_info: String::new(),
-            network_stats: HashMap::new(),
+            thermal: None,
+            thermal_thresholds: BTreeMap::new(),
+            thermal_plot_zones: BTreeSet::new(),
+            network_counters: None,
+            network_rates: Vec::new(),
+            network_hide_idle: true,
+            network_plot_packets: false,
+            uid_traffic: Vec::new(),
+            uid_traffic_source: String::new(),
+            uid_traffic_filter: String::new(),
             last_update_time: None,
             time_series: TimeSeriesData::default(),
             show_plots: false,
@@ -185,6 +668,8 @@ impl Default for AdbToolsState {
             systemd_service_name: String::new(),
             systemd_unit_filter: String::new(),
             systemd_service_list: Vec::new(),
+            adb_client: AdbClient::new(),
+            jobs: JobManager::new(),
         }
     }
 }
@@ -201,6 +686,7 @@ pub enum AdbFunction {
     PortForwarding,
     SelinuxManagement,
     SystemdManagement,
+    WirelessDebugging,
 }
 
 impl AdbFunction {
@@ -216,6 +702,7 @@ impl AdbFunction {
             Self::PortForwarding,
             Self::SelinuxManagement,
             Self::SystemdManagement,
+            Self::WirelessDebugging,
         ]
     }
 
@@ -231,6 +718,7 @@ impl AdbFunction {
             Self::PortForwarding => "Port Forwarding",
             Self::SelinuxManagement => "SELinux Management",
             Self::SystemdManagement => "Systemd Management",
+            Self::WirelessDebugging => "Wireless Debugging",
         }
     }
 
@@ -246,6 +734,7 @@ impl AdbFunction {
             Self::PortForwarding => "🔗",
             Self::SelinuxManagement => "🔒",
             Self::SystemdManagement => "⚙️",
+            Self::WirelessDebugging => "📶",
         }
     }
 
@@ -261,6 +750,7 @@ impl AdbFunction {
             Self::PortForwarding => "Set up network port forwarding between device and computer",
             Self::SelinuxManagement => "Manage SELinux policies, contexts, and security settings",
             Self::SystemdManagement => "Manage systemd services, units, and system daemon control",
+            Self::WirelessDebugging => "Pair, connect, and automatically reconnect devices over Wi-Fi",
         }
     }
 }
@@ -269,11 +759,41 @@ pub fn show_adb_tools(ui: &mut Ui, state: &mut AdbToolsState) {
     ui.heading("🤖 Android Debug Bridge (ADB) Tools");
     ui.separator();
     
+    // Collect results from background jobs
+    state.jobs.set_repaint_context(ui.ctx());
+    apply_job_results(state);
+    state.logcat_filter_error = None;
+    for session in state.logcat_sessions.values_mut() {
+        session.poll();
+        if let Err(e) = session.set_filter(&state.logcat_filters) {
+            state.logcat_filter_error = Some(e);
+        }
+    }
+    if let Some(session) = &mut state.logcat_offline {
+        if let Err(e) = session.set_filter(&state.logcat_filters) {
+            state.logcat_filter_error = Some(e);
+        }
+    }
+    for session in state.terminal_sessions.values_mut() {
+        session.poll();
+    }
+    if let Some(mirror) = &mut state.screen_mirror {
+        mirror.poll(ui.ctx());
+    }
+    
     // Auto-refresh devices on first load
     if !state.initial_refresh_done {
         refresh_devices(state);
         state.initial_refresh_done = true;
     }
+    if state.adb_function_visibility.get(&AdbFunction::WirelessDebugging).copied().unwrap_or(true) {
+        check_wireless_endpoints(state);
+        ui.ctx().request_repaint_after(RECONNECT_INTERVAL);
+    }
+    if state.adb_function_visibility.get(&AdbFunction::PortForwarding).copied().unwrap_or(true) {
+        check_forward_profiles(state);
+        ui.ctx().request_repaint_after(PROFILE_CHECK_INTERVAL);
+    }
     
     // Real-time monitoring update check
     if state.monitoring_enabled {
@@ -344,6 +864,8 @@ pub fn show_adb_tools(ui: &mut Ui, state: &mut AdbToolsState) {
             });
         }
         
+        show_jobs_panel(ui, state);
+        
         if !state.devices.is_empty() {
             ui.collapsing("📱 Connected Devices", |ui| {
                 for device in &state.devices {
@@ -358,11 +880,21 @@ pub fn show_adb_tools(ui: &mut Ui, state: &mut AdbToolsState) {
                 }
             });
         }
+
+        // Wireless endpoints are managed without a selected device, since they are how one appears
+        if state.adb_function_visibility.get(&AdbFunction::WirelessDebugging).copied().unwrap_or(true) {
+            ui.collapsing("📶 Wireless Debugging", |ui| show_wireless_section(ui, state));
+        }
     });
-    
+
     ui.separator();
       if state.selected_device.is_none() {
         ui.colored_label(egui::Color32::YELLOW, "⚠️ Please select a device to use ADB tools");
+        
+        // Saved log files can still be viewed without a device
+        if state.adb_function_visibility.get(&AdbFunction::Logcat).copied().unwrap_or(true) {
+            ui.collapsing("📜 Logcat", |ui| show_logcat_tab(ui, state));
+        }
         return;
     }
 
@@ -404,7 +936,129 @@ pub fn show_adb_tools(ui: &mut Ui, state: &mut AdbToolsState) {
         });
 }
 
+fn show_wireless_section(ui: &mut Ui, state: &mut AdbToolsState) {
+    show_job_status(ui, state, AdbFunction::WirelessDebugging);
+    
+    Grid::new("wireless_grid").num_columns(3).show(ui, |ui| {
+        ui.label("Connect:");
+        ui.add(egui::TextEdit::singleline(&mut state.wireless_address).hint_text("192.168.1.20:5555").desired_width(180.0));
+        let address = state.wireless_address.trim().to_string();
+        if ui.add_enabled(!address.is_empty(), egui::Button::new("🔌 Connect")).clicked() {
+            connect_wireless_device(state, address);
+        }
+        ui.end_row();
+        
+        ui.label("Pair:");
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut state.wireless_pair_address).hint_text("host:port").desired_width(180.0));
+            ui.add(egui::TextEdit::singleline(&mut state.wireless_pair_code).hint_text("code").desired_width(70.0));
+        });
+        let can_pair = !state.wireless_pair_address.trim().is_empty() && !state.wireless_pair_code.trim().is_empty();
+        if ui.add_enabled(can_pair, egui::Button::new("🤝 Pair"))
+            .on_hover_text("Use the address and code from Developer options > Wireless debugging > Pair device with pairing code")
+            .clicked() {
+            pair_wireless_device(state);
+        }
+        ui.end_row();
+        
+        ui.label("TCP/IP port:");
+        ui.add(egui::TextEdit::singleline(&mut state.wireless_tcpip_port).desired_width(70.0));
+        if ui.add_enabled(state.selected_device.is_some(), egui::Button::new("📡 Switch to TCP/IP"))
+            .on_hover_text("Restart adbd on the selected USB device in network mode and connect to it")
+            .clicked() {
+            switch_to_tcpip(state);
+        }
+        ui.end_row();
+    });
+    
+    ui.horizontal(|ui| {
+        if ui.button("🔍 Discover (mDNS)").clicked() {
+            discover_mdns_services(state);
+        }
+        if !state.wireless_result.is_empty() {
+            ui.label(&state.wireless_result);
+        }
+    });
+    
+    if !state.mdns_services.is_empty() {
+        let mut to_connect = None;
+        Grid::new("mdns_services_grid").num_columns(4).striped(true).show(ui, |ui| {
+            for service in &state.mdns_services {
+                ui.label(&service.instance);
+                ui.label(RichText::new(&service.service_type).weak());
+                ui.label(&service.address);
+                if service.is_connect() {
+                    if ui.small_button("🔌 Connect").clicked() {
+                        to_connect = Some(service.address.clone());
+                    }
+                } else if service.is_pairing() {
+                    if ui.small_button("🤝 Pair…").on_hover_text("Fill in the pairing address").clicked() {
+                        state.wireless_pair_address = service.address.clone();
+                    }
+                } else {
+                    ui.label("");
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(address) = to_connect {
+            connect_wireless_device(state, address);
+        }
+    }
+    
+    if state.wireless_endpoints.is_empty() {
+        return;
+    }
+    ui.label(RichText::new("Known endpoints").strong());
+    let mut to_connect = None;
+    let mut to_disconnect = None;
+    let mut to_remove = None;
+    Grid::new("wireless_endpoints_grid").num_columns(5).striped(true).show(ui, |ui| {
+        for (index, endpoint) in state.wireless_endpoints.iter_mut().enumerate() {
+            let address = normalize_address(&endpoint.address);
+            let status = state.devices.iter().find(|d| d.id == address).map(|d| d.status.as_str());
+            match status {
+                Some("device") => ui.label(RichText::new("● online").color(egui::Color32::from_rgb(0, 200, 0))),
+                Some(other) => ui.label(RichText::new(format!("● {}", other)).color(egui::Color32::from_rgb(200, 200, 0))),
+                None => ui.label(RichText::new("○ disconnected").weak()),
+            };
+            ui.label(&address);
+            ui.add(egui::TextEdit::singleline(&mut endpoint.name).hint_text("name").desired_width(120.0));
+            ui.checkbox(&mut endpoint.auto_reconnect, "Auto-reconnect");
+            ui.horizontal(|ui| {
+                if status.is_some() {
+                    if ui.small_button("⏏ Disconnect").clicked() {
+                        to_disconnect = Some(address.clone());
+                    }
+                } else if ui.small_button("🔌 Connect").clicked() {
+                    to_connect = Some(address.clone());
+                }
+                if ui.small_button("🗑️").on_hover_text("Forget this endpoint").clicked() {
+                    to_remove = Some(index);
+                }
+            });
+            ui.end_row();
+        }
+    });
+    
+    if let Some(address) = to_connect {
+        connect_wireless_device(state, address);
+    }
+    if let Some(address) = to_disconnect {
+        // Stop the next check from dialling straight back in
+        if let Some(endpoint) = state.wireless_endpoints.iter_mut().find(|e| normalize_address(&e.address) == address) {
+            endpoint.auto_reconnect = false;
+        }
+        disconnect_wireless_device(state, address);
+    }
+    if let Some(index) = to_remove {
+        state.wireless_endpoints.remove(index);
+    }
+}
+
 fn show_device_info_tab(ui: &mut Ui, state: &mut AdbToolsState) {
+    show_job_status(ui, state, AdbFunction::DeviceInfo);
+    
     ui.horizontal(|ui| {
         if ui.button("📊 Get Device Info").clicked() {
             get_device_info(state);
@@ -438,12 +1092,21 @@ fn show_device_info_tab(ui: &mut Ui, state: &mut AdbToolsState) {
 }
 
 fn show_app_management_tab(ui: &mut Ui, state: &mut AdbToolsState) {
+    show_job_status(ui, state, AdbFunction::AppManagement);
+    
     ui.group(|ui| {
         ui.label(RichText::new("Package Management").strong());
         
         ui.horizontal(|ui| {
             ui.label("Filter:");
             ui.text_edit_singleline(&mut state.package_filter);
+            ComboBox::from_id_salt("package_list_filter")
+                .selected_text(state.package_list_filter.name())
+                .show_ui(ui, |ui| {
+                    for filter in PackageListFilter::all() {
+                        ui.selectable_value(&mut state.package_list_filter, filter, filter.name());
+                    }
+                });
             if ui.button("📦 List Packages").clicked() {
                 list_packages(state);
             }
@@ -451,24 +1114,87 @@ fn show_app_management_tab(ui: &mut Ui, state: &mut AdbToolsState) {
         
         ui.horizontal(|ui| {
             ui.label("APK Path:");
-            ui.text_edit_singleline(&mut state.apk_path);
+            let response = ui.text_edit_singleline(&mut state.apk_path);
+            if response.changed() {
+                state.apk_info = None;
+                state.apk_inspected = false;
+                state.apk_splits.clear();
+            }
+            if ui.button("🔍 Inspect").clicked()
+                || (response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter))) {
+                inspect_apk_file(state);
+            }
             if ui.button("📥 Install APK").clicked() {
-                install_apk(state);
+                // Show what is about to be installed first; the next click installs it
+                if !state.apk_splits.is_empty() {
+                    install_apk_splits(state);
+                } else if state.apk_inspected {
+                    install_apk(state);
+                } else {
+                    inspect_apk_file(state);
+                    state.app_result = "Review the APK details, then click Install APK again".to_string();
+                }
             }
         });
+        ui.label(RichText::new("A single .apk, a folder of split APKs, or an .apks/.xapk bundle").small().weak());
+        
+        ui.horizontal(|ui| {
+            let options = &mut state.install_options;
+            ui.checkbox(&mut options.replace, "-r Replace");
+            ui.checkbox(&mut options.downgrade, "-d Downgrade");
+            ui.checkbox(&mut options.grant_permissions, "-g Grant permissions");
+            ui.checkbox(&mut options.allow_test, "-t Allow test");
+            ui.label("--user:");
+            ui.add(egui::TextEdit::singleline(&mut options.user).desired_width(40.0));
+        });
+        
+        if let Some(info) = &state.apk_info {
+            ui.separator();
+            show_apk_info(ui, info, &state.device_info);
+        }
+        if !state.apk_splits.is_empty() {
+            ui.separator();
+            show_apk_splits(ui, &mut state.apk_splits);
+        }
     });
     
+    if !state.app_result.is_empty() {
+        ui.label(&state.app_result);
+    }
+    
     ui.separator();
     
     if !state.installed_packages.is_empty() {
-        ui.label(format!("Found {} packages:", state.installed_packages.len()));
-        ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.label(format!("Found {} packages, {} selected", state.installed_packages.len(), state.package_selection.len()));
+            if ui.small_button("Select all").clicked() {
+                state.package_selection.extend(state.installed_packages.iter().cloned());
+            }
+            if ui.small_button("Select none").clicked() {
+                state.package_selection.clear();
+            }
+        });
+        show_package_batch_actions(ui, state);
+        
+        ScrollArea::vertical().id_salt("installed_packages").max_height(300.0).show(ui, |ui| {
             let mut package_to_remove: Option<String> = None;
+            let mut package_to_show: Option<String> = None;
             
             for package in &state.installed_packages {
                 ui.horizontal(|ui| {
+                    let mut checked = state.package_selection.contains(package);
+                    if ui.checkbox(&mut checked, "").changed() {
+                        if checked {
+                            state.package_selection.insert(package.clone());
+                        } else {
+                            state.package_selection.remove(package);
+                        }
+                    }
                     ui.label("📦");
-                    ui.label(package);
+                    let selected = state.selected_package.as_ref() == Some(package);
+                    if ui.selectable_label(selected, package).clicked() {
+                        package_to_show = Some(package.clone());
+                    }
                     if ui.small_button("🗑️").clicked() {
                         package_to_remove = Some(package.clone());
                     }
@@ -479,184 +1205,2508 @@ fn show_app_management_tab(ui: &mut Ui, state: &mut AdbToolsState) {
             if let Some(package_name) = package_to_remove {
                 uninstall_package(state, &package_name);
             }
+            if let Some(package_name) = package_to_show {
+                load_package_details(state, &package_name);
+            }
         });
     }
+    
+    if !state.package_batch_results.is_empty() {
+        ui.separator();
+        show_package_batch_results(ui, state);
+    }
+    
+    show_app_data_backup(ui, state);
+    
+    if state.selected_package.is_some() {
+        ui.separator();
+        show_package_details(ui, state);
+    }
 }
 
-fn show_file_operations_tab(ui: &mut Ui, state: &mut AdbToolsState) {
+fn show_app_data_backup(ui: &mut Ui, state: &mut AdbToolsState) {
     ui.group(|ui| {
-        ui.label(RichText::new("File Transfer").strong());
+        ui.label(RichText::new("App Data Backup").strong());
         
-        Grid::new("file_ops_grid").num_columns(2).show(ui, |ui| {
-            ui.label("Local Path:");
-            ui.text_edit_singleline(&mut state.local_path);
+        Grid::new("app_backup_grid").num_columns(3).show(ui, |ui| {
+            ui.label("Access:");
+            ComboBox::from_id_salt("app_backup_method")
+                .selected_text(state.app_backup_options.method.name())
+                .show_ui(ui, |ui| {
+                    for method in BackupMethod::all() {
+                        ui.selectable_value(&mut state.app_backup_options.method, method, method.name());
+                    }
+                });
             ui.end_row();
             
-            ui.label("Remote Path:");
-            ui.text_edit_singleline(&mut state.remote_path);
+            ui.label("Backup folder:");
+            ui.text_edit_singleline(&mut state.app_backup_options.backup_dir);
+            let label = match &state.selected_package {
+                Some(package) => format!("💾 Back up {}", package),
+                None => "💾 Back up".to_string(),
+            };
+            if ui.add_enabled(state.selected_package.is_some(), egui::Button::new(label))
+                .on_hover_text("Select a package in the list first")
+                .clicked() {
+                backup_selected_package(state);
+            }
+            ui.end_row();
+            
+            ui.label("Restore archive:");
+            ui.text_edit_singleline(&mut state.app_backup_options.restore_path);
+            if ui.add_enabled(!state.app_backup_options.restore_path.trim().is_empty(), egui::Button::new("♻ Restore"))
+                .on_hover_text("Restore into the package named in the archive on the selected device")
+                .clicked() {
+                restore_package_data(state);
+            }
             ui.end_row();
         });
-        
-        ui.horizontal(|ui| {
-            if ui.button("📤 Push to Device").clicked() {
-                push_file(state);
+    });
+}
+
+fn show_package_batch_actions(ui: &mut Ui, state: &mut AdbToolsState) {
+    let selected = state.package_selection.len();
+    let mut action_to_run = None;
+    
+    ui.horizontal_wrapped(|ui| {
+        for action in PackageAction::all() {
+            if ui.add_enabled(selected > 0, egui::Button::new(action.name())).clicked() {
+                if action.is_destructive() {
+                    state.package_confirm_action = Some(action);
+                } else {
+                    action_to_run = Some(action);
+                }
             }
-            if ui.button("📥 Pull from Device").clicked() {
-                pull_file(state);
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Save APKs to:");
+        ui.text_edit_singleline(&mut state.apk_pull_dir);
+    });
+    
+    if let Some(action) = state.package_confirm_action {
+        ui.horizontal(|ui| {
+            let prompt = format!("⚠️ Confirm {} on {} package(s)", action.name(), selected);
+            if ui.button(RichText::new(prompt).color(egui::Color32::RED)).clicked() {
+                action_to_run = Some(action);
+                state.package_confirm_action = None;
             }
-            if ui.button("📁 List Remote Dir").clicked() {
-                list_remote_directory(state);
+            if ui.button("Cancel").clicked() {
+                state.package_confirm_action = None;
             }
         });
+    }
+    
+    if let Some(action) = action_to_run {
+        run_package_batch(state, action);
+    }
+}
+
+fn show_package_batch_results(ui: &mut Ui, state: &mut AdbToolsState) {
+    let failed = state.package_batch_results.iter().filter(|r| !r.success).count();
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("Batch Results").strong());
+        ui.label(format!("{} succeeded, {} failed", state.package_batch_results.len() - failed, failed));
+        if ui.small_button("Clear").clicked() {
+            state.package_batch_results.clear();
+        }
     });
     
-    if !state.file_operation_result.is_empty() {
-        ui.separator();
-        ui.label("Result:");
-        ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
-            ui.label(&state.file_operation_result);
+    let row_height = ui.text_style_height(&egui::TextStyle::Body) + 4.0;
+    TableBuilder::new(ui)
+        .id_salt("package_batch_results")
+        .striped(true)
+        .resizable(true)
+        .max_scroll_height(200.0)
+        .auto_shrink([false, true])
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::initial(260.0).clip(true))
+        .column(Column::initial(140.0))
+        .column(Column::remainder().clip(true))
+        .header(row_height, |mut header| {
+            for title in ["Package", "Action", "Result"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(row_height, state.package_batch_results.len(), |mut row| {
+                let result = &state.package_batch_results[row.index()];
+                row.col(|ui| {
+                    ui.label(&result.package);
+                });
+                row.col(|ui| {
+                    ui.label(result.action.name());
+                });
+                row.col(|ui| {
+                    let icon = if result.success { "✅" } else { "❌" };
+                    ui.label(format!("{} {}", icon, result.message)).on_hover_text(&result.message);
+                });
+            });
+        });
+}
+
+fn show_package_details(ui: &mut Ui, state: &mut AdbToolsState) {
+    let Some(package) = state.selected_package.clone() else {
+        return;
+    };
+    
+    ui.group(|ui| {
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(format!("Package Details: {}", package)).strong());
+            if ui.small_button("🔄").on_hover_text("Refresh").clicked() {
+                load_package_details(state, &package);
+            }
+            if ui.small_button("✖").on_hover_text("Close").clicked() {
+                state.selected_package = None;
+                state.package_details = None;
+            }
+        });
+        
+        let Some(details) = state.package_details.clone() else {
+            ui.label("Loading...");
+            return;
+        };
+        
+        Grid::new("package_details_grid").num_columns(2).striped(true).show(ui, |ui| {
+            let rows = [
+                ("Version:", format!("{} (code {})", details.version_name, details.version_code)),
+                ("SDK:", format!("min {} / target {}", details.min_sdk, details.target_sdk)),
+                ("UID:", details.uid.clone()),
+                ("ABI:", details.primary_abi.clone()),
+                ("Installer:", details.installer.clone()),
+                ("Installed:", details.first_install_time.clone()),
+                ("Updated:", details.last_update_time.clone()),
+                ("Data dir:", details.data_dir.clone()),
+            ];
+            for (label, value) in rows {
+                ui.label(label);
+                ui.label(value);
+                ui.end_row();
+            }
+            
+            ui.label("APK paths:");
+            ui.vertical(|ui| {
+                if details.apk_paths.is_empty() {
+                    ui.monospace(&details.code_path);
+                }
+                for path in &details.apk_paths {
+                    ui.monospace(path);
+                }
+            });
+            ui.end_row();
+        });
+        
+        egui::CollapsingHeader::new(format!("Runtime permissions ({})", details.runtime_permissions.len()))
+            .id_salt("package_runtime_permissions")
+            .default_open(true)
+            .show(ui, |ui| {
+                for permission in &details.runtime_permissions {
+                    ui.horizontal(|ui| {
+                        let mut granted = permission.granted;
+                        if ui.checkbox(&mut granted, &permission.name).changed() {
+                            set_runtime_permission(state, &permission.name, granted);
+                        }
+                        if !permission.flags.is_empty() {
+                            ui.label(RichText::new(permission.flags.join(" | ")).small().weak());
+                        }
+                    });
+                }
+            });
+        
+        egui::CollapsingHeader::new(format!("App ops ({})", state.package_app_ops.len()))
+            .id_salt("package_app_ops")
+            .show(ui, |ui| {
+                let mut change = None;
+                Grid::new("package_app_ops_grid").num_columns(3).striped(true).show(ui, |ui| {
+                    for (index, op) in state.package_app_ops.iter().enumerate() {
+                        ui.label(if op.uid_mode { format!("{} (uid)", op.name) } else { op.name.clone() });
+                        let mut mode = op.mode.clone();
+                        ComboBox::from_id_salt(("app_op_mode", index))
+                            .selected_text(&mode)
+                            .show_ui(ui, |ui| {
+                                for candidate in APP_OP_MODES {
+                                    ui.selectable_value(&mut mode, candidate.to_string(), candidate);
+                                }
+                            });
+                        if mode != op.mode {
+                            change = Some((op.name.clone(), mode, op.uid_mode));
+                        }
+                        ui.label(RichText::new(&op.details).small().weak());
+                        ui.end_row();
+                    }
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.label("Op:");
+                    ui.add(egui::TextEdit::singleline(&mut state.app_op_name).hint_text("e.g. RUN_IN_BACKGROUND").desired_width(180.0));
+                    ComboBox::from_id_salt("app_op_custom_mode")
+                        .selected_text(&state.app_op_mode)
+                        .show_ui(ui, |ui| {
+                            for candidate in APP_OP_MODES {
+                                ui.selectable_value(&mut state.app_op_mode, candidate.to_string(), candidate);
+                            }
+                        });
+                    if ui.add_enabled(!state.app_op_name.trim().is_empty(), egui::Button::new("Set")).clicked() {
+                        change = Some((state.app_op_name.trim().to_uppercase(), state.app_op_mode.clone(), false));
+                    }
+                });
+                
+                if let Some((op, mode, uid_mode)) = change {
+                    set_app_op(state, &op, &mode, uid_mode);
+                }
+            });
+        
+        egui::CollapsingHeader::new(format!("Install permissions ({})", details.install_permissions.len()))
+            .id_salt("package_install_permissions")
+            .show(ui, |ui| {
+                for permission in &details.install_permissions {
+                    ui.label(format!("{} {}", if permission.granted { "✅" } else { "❌" }, permission.name));
+                }
+            });
+        
+        egui::CollapsingHeader::new(format!("Requested permissions ({})", details.requested_permissions.len()))
+            .id_salt("package_requested_permissions")
+            .show(ui, |ui| {
+                for permission in &details.requested_permissions {
+                    ui.monospace(permission);
+                }
+            });
+        
+        egui::CollapsingHeader::new(format!("Components ({})", details.components.len()))
+            .id_salt("package_components")
+            .show(ui, |ui| {
+                Grid::new("package_components_grid").num_columns(3).striped(true).show(ui, |ui| {
+                    for component in &details.components {
+                        ui.label(component.kind.name());
+                        ui.monospace(&component.name);
+                        match component.state {
+                            ComponentState::Default => ui.label("default"),
+                            ComponentState::Enabled => ui.colored_label(egui::Color32::GREEN, "enabled"),
+                            ComponentState::Disabled => ui.colored_label(egui::Color32::RED, "disabled"),
+                        };
+                        ui.end_row();
+                    }
+                });
+            });
+    });
+}
+
+fn show_apk_info(ui: &mut Ui, info: &ApkInfo, device_info: &HashMap<String, String>) {
+    let device_sdk = device_info.get("API Level").and_then(|level| level.parse().ok());
+    let device_abi = device_info.get("ABI").map(|abi| abi.as_str());
+    for warning in info.compatibility_warnings(device_abi, device_sdk) {
+        ui.colored_label(egui::Color32::RED, format!("⚠️ {}", warning));
+    }
+    
+    let sdk_label = |sdk: Option<u32>| sdk.map(|v| v.to_string()).unwrap_or_else(|| "?".to_string());
+    Grid::new("apk_info_grid").num_columns(2).striped(true).show(ui, |ui| {
+        ui.label("Package:");
+        ui.label(RichText::new(&info.package).monospace());
+        ui.end_row();
+        
+        ui.label("Version:");
+        ui.label(format!("{} (code {})", info.version_name, info.version_code));
+        ui.end_row();
+        
+        ui.label("SDK:");
+        ui.label(format!("min {} / target {}", sdk_label(info.min_sdk), sdk_label(info.target_sdk)));
+        ui.end_row();
+        
+        ui.label("ABIs:");
+        if info.abis.is_empty() {
+            ui.label("Any (no native code)");
+        } else {
+            ui.label(info.abis.join(", "));
+        }
+        ui.end_row();
+        
+        ui.label("Size:");
+        ui.label(format_bytes(info.file_size));
+        ui.end_row();
+        
+        ui.label("Launchable:");
+        ui.vertical(|ui| {
+            if info.launchable_activities.is_empty() {
+                ui.label("None");
+            }
+            for activity in &info.launchable_activities {
+                ui.monospace(activity);
+            }
+        });
+        ui.end_row();
+    });
+    
+    egui::CollapsingHeader::new(format!("Permissions ({})", info.permissions.len()))
+        .id_salt("apk_permissions")
+        .show(ui, |ui| {
+            ScrollArea::vertical().id_salt("apk_permissions_scroll").max_height(200.0).show(ui, |ui| {
+                for permission in &info.permissions {
+                    ui.monospace(permission);
+                }
+            });
+        });
+}
+
+fn show_apk_splits(ui: &mut Ui, splits: &mut [ApkSplit]) {
+    let selected = splits.iter().filter(|s| s.selected).count();
+    let bytes: u64 = splits.iter().filter(|s| s.selected).map(|s| s.size).sum();
+    ui.label(format!("{} of {} splits selected ({})", selected, splits.len(), format_bytes(bytes)));
+    
+    let row_height = ui.text_style_height(&egui::TextStyle::Body) + 4.0;
+    TableBuilder::new(ui)
+        .id_salt("apk_splits_table")
+        .striped(true)
+        .resizable(true)
+        .max_scroll_height(250.0)
+        .auto_shrink([false, true])
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::exact(24.0))
+        .column(Column::initial(160.0))
+        .column(Column::initial(80.0))
+        .column(Column::remainder().clip(true))
+        .header(row_height, |mut header| {
+            for title in ["", "Split", "Size", "File"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(row_height, splits.len(), |mut row| {
+                let split = &mut splits[row.index()];
+                row.col(|ui| {
+                    ui.checkbox(&mut split.selected, "");
+                });
+                row.col(|ui| {
+                    ui.label(split.kind.label());
+                });
+                row.col(|ui| {
+                    ui.label(format_bytes(split.size));
+                });
+                row.col(|ui| {
+                    ui.label(&split.name);
+                });
+            });
+        });
+}
+
+fn show_file_operations_tab(ui: &mut Ui, state: &mut AdbToolsState) {
+    show_job_status(ui, state, AdbFunction::FileOperations);
+    
+    ui.group(|ui| {
+        ui.label(RichText::new("File Transfer").strong());
+        
+        Grid::new("file_ops_grid").num_columns(2).show(ui, |ui| {
+            ui.label("Local Path:");
+            ui.text_edit_singleline(&mut state.local_path);
+            ui.end_row();
+            
+            ui.label("Remote Path:");
+            ui.text_edit_singleline(&mut state.remote_path);
+            ui.end_row();
+        });
+        
+        ui.horizontal(|ui| {
+            if ui.button("📤 Push to Device").clicked() {
+                push_file(state);
+            }
+            if ui.button("📥 Pull from Device").clicked() {
+                pull_file(state);
+            }
+        });
+    });
+    
+    show_remote_browser(ui, state);
+    show_folder_sync(ui, state);
+    
+    if !state.file_operation_result.is_empty() {
+        ui.separator();
+        ui.label("Result:");
+        ScrollArea::vertical().id_salt("file_operation_result").max_height(200.0).show(ui, |ui| {
+            ui.label(&state.file_operation_result);
+        });
+    }
+}
+
+fn show_remote_browser(ui: &mut Ui, state: &mut AdbToolsState) {
+    // List the current folder the first time the browser is shown
+    if state.remote_listed_path.is_none() && !state.jobs.is_running(AdbFunction::FileOperations) {
+        browse_remote(state, None);
+    }
+    
+    ui.group(|ui| {
+        ui.label(RichText::new("Remote Browser").strong());
+        
+        ui.horizontal(|ui| {
+            if ui.add_enabled(!state.remote_navigation.back.is_empty(), egui::Button::new("⬅")).on_hover_text("Back").clicked()
+                && state.remote_navigation.go_back(&mut state.remote_path) {
+                browse_remote(state, None);
+            }
+            if ui.add_enabled(!state.remote_navigation.forward.is_empty(), egui::Button::new("➡")).on_hover_text("Forward").clicked()
+                && state.remote_navigation.go_forward(&mut state.remote_path) {
+                browse_remote(state, None);
+            }
+            if ui.button("⬆").on_hover_text("Parent folder").clicked() {
+                browse_remote(state, Some(remote_parent(&state.remote_path)));
+            }
+            if ui.button("🔄").on_hover_text("Refresh").clicked() {
+                browse_remote(state, None);
+            }
+            
+            ui.separator();
+            for (label, path) in remote_breadcrumbs(&state.remote_path) {
+                if ui.small_button(label).clicked() {
+                    browse_remote(state, Some(path));
+                }
+            }
+        });
+        
+        ui.horizontal(|ui| {
+            ui.label("Go to:");
+            let response = ui.text_edit_singleline(&mut state.remote_go_to);
+            if (ui.button("Go").clicked() || (response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter))))
+                && !state.remote_go_to.trim().is_empty() {
+                let path = normalize_remote_dir(&state.remote_go_to);
+                browse_remote(state, Some(path));
+            }
+        });
+        
+        ui.separator();
+        show_remote_actions(ui, state);
+        ui.separator();
+        show_remote_entries(ui, state);
+    });
+}
+
+fn show_remote_actions(ui: &mut Ui, state: &mut AdbToolsState) {
+    let selected = state.remote_selection.len();
+    
+    ui.horizontal(|ui| {
+        ui.label(format!("{} selected", selected));
+        if ui.add_enabled(selected > 0, egui::Button::new("📥 Pull Selected"))
+            .on_hover_text("Pull into the Local Path folder")
+            .clicked() {
+            pull_remote_selection(state);
+        }
+        if ui.button("📤 Push Here").on_hover_text("Push the Local Path into this folder").clicked() {
+            push_into_remote_folder(state);
+        }
+        
+        if state.remote_confirm_delete {
+            if ui.button(RichText::new(format!("⚠️ Confirm delete of {}", selected)).color(egui::Color32::RED)).clicked() {
+                delete_remote_selection(state);
+                state.remote_confirm_delete = false;
+            }
+            if ui.button("Cancel").clicked() {
+                state.remote_confirm_delete = false;
+            }
+        } else if ui.add_enabled(selected > 0, egui::Button::new("🗑️ Delete")).clicked() {
+            state.remote_confirm_delete = true;
+        }
+    });
+    
+    Grid::new("remote_actions_grid").num_columns(3).show(ui, |ui| {
+        ui.label("Rename to:");
+        ui.text_edit_singleline(&mut state.remote_rename_to);
+        if ui.add_enabled(selected == 1 && !state.remote_rename_to.trim().is_empty(), egui::Button::new("✏️ Rename")).clicked() {
+            rename_remote_selection(state);
+        }
+        ui.end_row();
+        
+        ui.label("New folder:");
+        ui.text_edit_singleline(&mut state.remote_new_folder);
+        if ui.add_enabled(!state.remote_new_folder.trim().is_empty(), egui::Button::new("📁 Create")).clicked() {
+            make_remote_folder(state);
+        }
+        ui.end_row();
+        
+        ui.label("Mode:");
+        ui.add(egui::TextEdit::singleline(&mut state.remote_chmod_mode).hint_text("e.g. 755 or u+x"));
+        if ui.add_enabled(selected > 0 && !state.remote_chmod_mode.trim().is_empty(), egui::Button::new("🔐 chmod")).clicked() {
+            chmod_remote_selection(state);
+        }
+        ui.end_row();
+    });
+}
+
+fn show_remote_entries(ui: &mut Ui, state: &mut AdbToolsState) {
+    if state.remote_entries.is_empty() {
+        ui.label("Folder is empty or not listed yet");
+        return;
+    }
+    
+    let mut navigate_to = None;
+    let row_height = ui.text_style_height(&egui::TextStyle::Body) + 4.0;
+    
+    TableBuilder::new(ui)
+        .id_salt("remote_browser_table")
+        .striped(true)
+        .resizable(true)
+        .max_scroll_height(350.0)
+        .auto_shrink([false, true])
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::exact(24.0))
+        .column(Column::initial(260.0).clip(true))
+        .column(Column::initial(80.0))
+        .column(Column::initial(90.0))
+        .column(Column::initial(110.0).clip(true))
+        .column(Column::initial(130.0))
+        .column(Column::remainder().clip(true))
+        .header(row_height, |mut header| {
+            for title in ["", "Name", "Size", "Permissions", "Owner", "Modified", "Link Target"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(row_height, state.remote_entries.len(), |mut row| {
+                let entry = &state.remote_entries[row.index()];
+                
+                row.col(|ui| {
+                    let mut checked = state.remote_selection.contains(&entry.name);
+                    if ui.checkbox(&mut checked, "").changed() {
+                        if checked {
+                            state.remote_selection.insert(entry.name.clone());
+                        } else {
+                            state.remote_selection.remove(&entry.name);
+                        }
+                    }
+                });
+                row.col(|ui| {
+                    let label = format!("{} {}", entry.file_type.icon(), entry.name);
+                    if entry.is_browsable() {
+                        if ui.link(label).clicked() {
+                            navigate_to = Some(remote_join(&state.remote_path, &entry.name));
+                        }
+                    } else {
+                        ui.label(label);
+                    }
+                });
+                row.col(|ui| {
+                    ui.label(entry.size.map(format_bytes).unwrap_or_default());
+                });
+                row.col(|ui| {
+                    ui.monospace(&entry.permissions);
+                });
+                row.col(|ui| {
+                    ui.label(format!("{}:{}", entry.owner, entry.group));
+                });
+                row.col(|ui| {
+                    ui.label(&entry.modified);
+                });
+                row.col(|ui| {
+                    ui.label(entry.link_target.as_deref().unwrap_or_default());
+                });
+            });
+        });
+    
+    if let Some(path) = navigate_to {
+        browse_remote(state, Some(path));
+    }
+}
+
+fn show_folder_sync(ui: &mut Ui, state: &mut AdbToolsState) {
+    ui.group(|ui| {
+        ui.label(RichText::new("Folder Sync").strong());
+        
+        Grid::new("folder_sync_grid").num_columns(2).show(ui, |ui| {
+            ui.label("Local folder:");
+            ui.text_edit_singleline(&mut state.sync_options.local_dir);
+            ui.end_row();
+            
+            ui.label("Remote folder:");
+            ui.text_edit_singleline(&mut state.sync_options.remote_dir);
+            ui.end_row();
+            
+            ui.label("Direction:");
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut state.sync_options.direction, SyncDirection::Push, "📤 Local → Device");
+                ui.radio_value(&mut state.sync_options.direction, SyncDirection::Pull, "📥 Device → Local");
+            });
+            ui.end_row();
+        });
+        
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut state.sync_options.use_checksum, "Compare checksums")
+                .on_hover_text("Use md5sum on the device instead of modification times for same-sized files");
+            ui.checkbox(&mut state.sync_options.delete_extras, "Delete extra files")
+                .on_hover_text("Remove files from the destination that are not in the source");
+        });
+        
+        let busy = state.jobs.is_running(AdbFunction::FileOperations);
+        ui.horizontal(|ui| {
+            let can_compare = !busy
+                && !state.sync_options.local_dir.trim().is_empty()
+                && !state.sync_options.remote_dir.trim().is_empty();
+            if ui.add_enabled(can_compare, egui::Button::new("🔍 Compare")).clicked() {
+                compare_sync_folders(state);
+            }
+            if let Some(plan) = &state.sync_plan {
+                let selected = plan.selected_count();
+                if ui.add_enabled(!busy && selected > 0, egui::Button::new(format!("▶ Apply {} change(s)", selected))).clicked() {
+                    apply_sync_plan(state);
+                }
+                if ui.button("✖ Discard").clicked() {
+                    state.sync_plan = None;
+                }
+            }
+        });
+        
+        if state.sync_plan.is_some() {
+            ui.separator();
+            show_sync_plan(ui, state);
+        }
+    });
+}
+
+fn show_sync_plan(ui: &mut Ui, state: &mut AdbToolsState) {
+    let Some(plan) = state.sync_plan.as_mut() else {
+        return;
+    };
+    
+    let deletes = plan.actions.iter().filter(|a| a.selected && a.is_delete()).count();
+    ui.label(format!(
+        "{} to copy ({}), {} to delete, {} unchanged",
+        plan.selected_count() - deletes,
+        format_bytes(plan.transfer_bytes()),
+        deletes,
+        plan.unchanged
+    ));
+    if plan.actions.is_empty() {
+        ui.label("✅ Folders are already in sync");
+        return;
+    }
+    
+    ui.horizontal(|ui| {
+        if ui.small_button("Select all").clicked() {
+            plan.actions.iter_mut().for_each(|a| a.selected = true);
+        }
+        if ui.small_button("Select none").clicked() {
+            plan.actions.iter_mut().for_each(|a| a.selected = false);
+        }
+    });
+    
+    let row_height = ui.text_style_height(&egui::TextStyle::Body) + 4.0;
+    TableBuilder::new(ui)
+        .id_salt("sync_plan_table")
+        .striped(true)
+        .resizable(true)
+        .max_scroll_height(300.0)
+        .auto_shrink([false, true])
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::exact(24.0))
+        .column(Column::initial(110.0))
+        .column(Column::initial(80.0))
+        .column(Column::remainder().clip(true))
+        .header(row_height, |mut header| {
+            for title in ["", "Action", "Size", "Path"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(row_height, plan.actions.len(), |mut row| {
+                let action = &mut plan.actions[row.index()];
+                
+                row.col(|ui| {
+                    ui.checkbox(&mut action.selected, "");
+                });
+                row.col(|ui| {
+                    let text = RichText::new(action.reason.description());
+                    if action.is_delete() {
+                        ui.label(text.color(egui::Color32::RED));
+                    } else {
+                        ui.label(text);
+                    }
+                });
+                row.col(|ui| {
+                    ui.label(format_bytes(action.size));
+                });
+                row.col(|ui| {
+                    ui.label(&action.path);
+                });
+            });
         });
-    }
 }
 
 fn show_shell_tab(ui: &mut Ui, state: &mut AdbToolsState) {
+    show_job_status(ui, state, AdbFunction::ShellCommands);
+    
+    ui.group(|ui| {
+        ui.label(RichText::new("ADB Shell").strong());
+        
+        ui.horizontal(|ui| {
+            ui.label("Command:");
+            ui.text_edit_singleline(&mut state.shell_command);
+            if let Some(command) = show_shell_history_menu(ui, &mut state.shell_history) {
+                state.shell_command = command;
+            }
+            let label = if state.shell_broadcast {
+                format!("📡 Run on {} device(s)", state.shell_broadcast_targets.len())
+            } else {
+                "▶️ Execute".to_string()
+            };
+            if ui.button(label).clicked() {
+                if state.shell_broadcast {
+                    execute_broadcast_command(state);
+                } else {
+                    execute_shell_command(state);
+                }
+            }
+        });
+        
+        // Common commands
+        ui.horizontal(|ui| {
+            if ui.small_button("ls /sdcard/").clicked() {
+                state.shell_command = "ls /sdcard/".to_string();
+            }
+            if ui.small_button("ps").clicked() {
+                state.shell_command = "ps".to_string();
+            }
+            if ui.small_button("df -h").clicked() {
+                state.shell_command = "df -h".to_string();
+            }
+            if ui.small_button("getprop").clicked() {
+                state.shell_command = "getprop".to_string();
+            }
+        });
+
+        ui.checkbox(&mut state.shell_broadcast, "📡 Broadcast to several devices");
+        if state.shell_broadcast {
+            show_broadcast_targets(ui, state);
+        }
+    });
+
+    ui.collapsing("📚 Snippets & Macros", |ui| {
+        show_snippets(ui, state);
+    });
+
+    if state.shell_broadcast_run.is_some() {
+        ui.separator();
+        show_broadcast_results(ui, state);
+    }
+
+    if !state.shell_output.is_empty() {
+        ui.separator();
+        ui.label("Output:");
+        ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            ui.code(&state.shell_output);
+        });
+    }
+
+    ui.separator();
+    show_terminal(ui, state);
+}
+
+enum SnippetAction {
+    Run(PromptTarget),
+    Edit(SnippetEditor),
+    DeleteSnippet(Snippet),
+    DeleteMacro(ShellMacro),
+}
+
+fn show_snippets(ui: &mut Ui, state: &mut AdbToolsState) {
+    ui.horizontal(|ui| {
+        ui.label("Filter:");
+        ui.add(egui::TextEdit::singleline(&mut state.snippet_filter).hint_text("name, folder or command").desired_width(200.0));
+    });
+
+    let filter = state.snippet_filter.trim().to_lowercase();
+    let matches = |fields: [&str; 3]| filter.is_empty() || fields.iter().any(|f| f.to_lowercase().contains(&filter));
+    let mut action = None;
+
+    if state.snippet_library.snippets.is_empty() && state.snippet_library.macros.is_empty() {
+        ui.label(RichText::new("No snippets yet: save one below or import a snippet file").weak());
+    }
+    ScrollArea::vertical().id_salt("snippet_library").max_height(250.0).show(ui, |ui| {
+        for folder in state.snippet_library.folders() {
+            let snippets: Vec<&Snippet> = state.snippet_library.snippets.iter()
+                .filter(|s| s.folder == folder && matches([&s.name, &s.folder, &s.command]))
+                .collect();
+            let macros: Vec<&ShellMacro> = state.snippet_library.macros.iter()
+                .filter(|m| m.folder == folder && matches([&m.name, &m.folder, &m.description]))
+                .collect();
+            if snippets.is_empty() && macros.is_empty() {
+                continue;
+            }
+            let title = if folder.is_empty() { "📁 (top level)".to_string() } else { format!("📁 {}", folder) };
+            egui::CollapsingHeader::new(title)
+                .id_salt(("snippet_folder", &folder))
+                .default_open(true)
+                .show(ui, |ui| {
+                    for snippet in snippets {
+                        ui.horizontal(|ui| {
+                            if ui.small_button("▶️").on_hover_text("Run").clicked() {
+                                action = Some(SnippetAction::Run(PromptTarget::Snippet(snippet.clone())));
+                            }
+                            if ui.small_button("✏️").on_hover_text("Edit").clicked() {
+                                action = Some(SnippetAction::Edit(SnippetEditor::from_snippet(snippet)));
+                            }
+                            if ui.small_button("🗑️").on_hover_text("Delete").clicked() {
+                                action = Some(SnippetAction::DeleteSnippet(snippet.clone()));
+                            }
+                            let hover = if snippet.description.is_empty() {
+                                snippet.command.clone()
+                            } else {
+                                format!("{}\n\n{}", snippet.description, snippet.command)
+                            };
+                            ui.label(RichText::new(&snippet.name).strong()).on_hover_text(hover);
+                            ui.label(RichText::new(&snippet.command).monospace().weak());
+                        });
+                    }
+                    for shell_macro in macros {
+                        ui.horizontal(|ui| {
+                            if ui.small_button("▶️").on_hover_text("Run").clicked() {
+                                action = Some(SnippetAction::Run(PromptTarget::Macro(shell_macro.clone())));
+                            }
+                            if ui.small_button("✏️").on_hover_text("Edit").clicked() {
+                                action = Some(SnippetAction::Edit(SnippetEditor::from_macro(shell_macro)));
+                            }
+                            if ui.small_button("🗑️").on_hover_text("Delete").clicked() {
+                                action = Some(SnippetAction::DeleteMacro(shell_macro.clone()));
+                            }
+                            let steps: Vec<String> = shell_macro.steps.iter().map(MacroStep::line).collect();
+                            ui.label(RichText::new(format!("🔁 {}", shell_macro.name)).strong()).on_hover_text(steps.join("\n"));
+                            ui.label(RichText::new(format!("{} steps", shell_macro.steps.len())).weak());
+                            if !shell_macro.description.is_empty() {
+                                ui.label(RichText::new(&shell_macro.description).weak());
+                            }
+                        });
+                    }
+                });
+        }
+    });
+
+    match action {
+        Some(SnippetAction::Run(target)) => run_snippet(state, target),
+        Some(SnippetAction::Edit(editor)) => state.snippet_editor = editor,
+        Some(SnippetAction::DeleteSnippet(snippet)) => {
+            state.snippet_library.snippets.retain(|s| *s != snippet);
+            state.snippet_result = format!("Deleted snippet '{}'", snippet.name);
+        }
+        Some(SnippetAction::DeleteMacro(shell_macro)) => {
+            state.snippet_library.macros.retain(|m| *m != shell_macro);
+            state.snippet_result = format!("Deleted macro '{}'", shell_macro.name);
+        }
+        None => {}
+    }
+
+    if state.snippet_prompt.is_some() {
+        ui.separator();
+        show_snippet_prompt(ui, state);
+    }
+
+    ui.separator();
+    show_snippet_editor(ui, state);
+
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.label("Snippet file:");
+        ui.add(egui::TextEdit::singleline(&mut state.snippet_file_path).desired_width(250.0));
+        if ui.button("📥 Import").clicked() {
+            import_snippets(state);
+        }
+        if ui.button("📤 Export").clicked() {
+            export_snippets(state);
+        }
+    });
+
+    if !state.snippet_result.is_empty() {
+        ui.label(&state.snippet_result);
+    }
+}
+
+fn show_snippet_prompt(ui: &mut Ui, state: &mut AdbToolsState) {
+    let Some(prompt) = &mut state.snippet_prompt else {
+        return;
+    };
+    let is_snippet = matches!(prompt.target, PromptTarget::Snippet(_));
+    ui.label(RichText::new(format!("Values for '{}'", prompt.name())).strong());
+    Grid::new("snippet_prompt_grid").num_columns(2).show(ui, |ui| {
+        for (name, value) in &mut prompt.values {
+            ui.label(format!("{{{}}}", name));
+            ui.add(egui::TextEdit::singleline(value).desired_width(250.0));
+            ui.end_row();
+        }
+    });
+
+    let mut run = false;
+    let mut insert = false;
+    let mut cancel = false;
+    ui.horizontal(|ui| {
+        run = ui.button("▶️ Run").clicked();
+        if is_snippet {
+            insert = ui.button("📝 Insert").on_hover_text("Put the command in the command field without running it").clicked();
+        }
+        cancel = ui.button("Cancel").clicked();
+    });
+
+    if cancel {
+        state.snippet_prompt = None;
+    } else if run || insert {
+        if let Some(prompt) = state.snippet_prompt.take() {
+            state.snippet_values.extend(prompt.values.iter().cloned());
+            run_snippet_with(state, prompt, insert);
+        }
+    }
+}
+
+fn show_snippet_editor(ui: &mut Ui, state: &mut AdbToolsState) {
+    let editor = &mut state.snippet_editor;
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("Editor").strong());
+        ui.radio_value(&mut editor.is_macro, false, "Snippet");
+        ui.radio_value(&mut editor.is_macro, true, "Macro");
+    });
+    Grid::new("snippet_editor_grid").num_columns(2).show(ui, |ui| {
+        ui.label("Name:");
+        ui.add(egui::TextEdit::singleline(&mut editor.name).desired_width(250.0));
+        ui.end_row();
+        ui.label("Folder:");
+        ui.add(egui::TextEdit::singleline(&mut editor.folder).hint_text("e.g. network/wifi").desired_width(250.0));
+        ui.end_row();
+        ui.label("Description:");
+        ui.add(egui::TextEdit::singleline(&mut editor.description).desired_width(250.0));
+        ui.end_row();
+    });
+
+    let hint = if editor.is_macro {
+        "One step per line, stops at the first failure.\nShell steps run in adb shell; prefix adb steps with `adb`, e.g.\nadb install {apk}\nam start -n {activity}"
+    } else {
+        "e.g. pm list packages {filter}"
+    };
+    let rows = if editor.is_macro { 5 } else { 1 };
+    ui.add(egui::TextEdit::multiline(&mut editor.body)
+        .code_editor()
+        .hint_text(hint)
+        .desired_rows(rows)
+        .desired_width(f32::INFINITY));
+
+    let names = placeholders(&editor.body);
+    if !names.is_empty() {
+        ui.label(RichText::new(format!("Placeholders: {}", names.join(", "))).weak());
+    }
+
+    ui.horizontal(|ui| {
+        if ui.button("💾 Save").clicked() {
+            state.snippet_result = match state.snippet_editor.save(&mut state.snippet_library) {
+                Ok(message) => message,
+                Err(e) => e,
+            };
+        }
+        if ui.button("🆕 New").clicked() {
+            state.snippet_editor = SnippetEditor {
+                is_macro: state.snippet_editor.is_macro,
+                folder: state.snippet_editor.folder.clone(),
+                ..Default::default()
+            };
+        }
+    });
+}
+
+/// Recent commands, newest first; returns the one picked
+fn show_shell_history_menu(ui: &mut Ui, history: &mut Vec<String>) -> Option<String> {
+    let mut picked = None;
+    ui.add_enabled_ui(!history.is_empty(), |ui| {
+        ui.menu_button("📜 History", |ui| {
+            ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                for command in history.iter().rev() {
+                    if ui.button(RichText::new(command).monospace()).clicked() {
+                        picked = Some(command.clone());
+                        ui.close_menu();
+                    }
+                }
+            });
+            ui.separator();
+            if ui.button("🗑️ Clear History").clicked() {
+                history.clear();
+                ui.close_menu();
+            }
+        });
+    });
+    picked
+}
+
+fn show_terminal(ui: &mut Ui, state: &mut AdbToolsState) {
+    ui.group(|ui| {
+        ui.label(RichText::new("🖥 Terminal").strong());
+
+        let Some(device_id) = state.selected_device.clone() else {
+            ui.label("Select a device to open an interactive shell");
+            return;
+        };
+        let running = state.terminal_sessions.get(&device_id).is_some_and(|s| s.is_running());
+
+        ui.horizontal(|ui| {
+            if running {
+                if ui.button("⏹️ Close").clicked() {
+                    close_terminal(state, &device_id);
+                }
+            } else if ui.button("▶️ Open Terminal").clicked() {
+                open_terminal(state, &device_id, ui.ctx());
+            }
+            if ui.add_enabled(running, egui::Button::new("⛔ Ctrl+C")).clicked() {
+                if let Some(session) = state.terminal_sessions.get_mut(&device_id) {
+                    session.interrupt();
+                }
+            }
+            if let Some(command) = show_shell_history_menu(ui, &mut state.shell_history) {
+                if let Some(session) = state.terminal_sessions.get_mut(&device_id) {
+                    session.type_text(&command);
+                }
+            }
+            if let Some(session) = state.terminal_sessions.get(&device_id) {
+                if ui.button("📋 Copy").on_hover_text("Copy the scrollback and screen").clicked() {
+                    ui.ctx().copy_text(session.screen.text());
+                }
+                ui.label(session.status.description());
+            }
+        });
+
+        if let Some(session) = state.terminal_sessions.get_mut(&device_id) {
+            show_terminal_view(ui, session, &mut state.shell_history);
+        }
+    });
+}
+
+fn show_terminal_view(ui: &mut Ui, session: &mut TerminalSession, history: &mut Vec<String>) {
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let (glyph_width, row_height) = ui.fonts(|f| (f.glyph_width(&font_id, 'M'), f.row_height(&font_id)));
+    let foreground = ui.visuals().text_color();
+    let background = ui.visuals().extreme_bg_color;
+    let id = ui.id().with(("adb_terminal", &session.serial));
+
+    egui::Resize::default()
+        .id_salt(("adb_terminal_resize", &session.serial))
+        .default_width(ui.available_width())
+        .default_height(360.0)
+        .min_height(120.0)
+        .show(ui, |ui| {
+            let frame = egui::Frame::canvas(ui.style()).fill(background).show(ui, |ui| {
+                ui.set_min_size(ui.available_size());
+
+                // Fit the PTY to the visible area
+                let size = ui.available_size();
+                let cols = ((size.x - ui.spacing().scroll.allocated_width()) / glyph_width).floor() as usize;
+                let rows = (size.y / row_height).floor() as usize;
+                session.resize(rows, cols);
+
+                ui.spacing_mut().item_spacing.y = 0.0;
+                ScrollArea::vertical()
+                    .id_salt(("adb_terminal_scroll", &session.serial))
+                    .auto_shrink([false, false])
+                    .stick_to_bottom(true)
+                    .show_rows(ui, row_height, session.screen.line_count(), |ui, range| {
+                        for index in range {
+                            let job = session.screen.line_job(index, &font_id, foreground, background);
+                            ui.add(egui::Label::new(job).extend().selectable(false));
+                        }
+                    });
+            });
+
+            let rect = frame.response.rect;
+            let response = ui.interact(rect, id, egui::Sense::focusable_noninteractive());
+            if ui.rect_contains_pointer(rect) && ui.input(|i| i.pointer.primary_clicked()) {
+                response.request_focus();
+            }
+            if !response.has_focus() {
+                return;
+            }
+            ui.painter().rect_stroke(rect, 2.0, ui.visuals().selection.stroke);
+
+            // Keep Tab, arrows and Escape for the shell instead of egui's focus navigation
+            ui.memory_mut(|m| m.set_focus_lock_filter(id, egui::EventFilter {
+                tab: true,
+                horizontal_arrows: true,
+                vertical_arrows: true,
+                escape: true,
+            }));
+            for event in ui.input(|i| i.events.clone()) {
+                match event {
+                    egui::Event::Text(text) | egui::Event::Paste(text) => session.type_text(&text),
+                    // Ctrl+C arrives as a copy request rather than a key press
+                    egui::Event::Copy => session.interrupt(),
+                    egui::Event::Cut => session.send(&[0x18]),
+                    egui::Event::Key { key, pressed: true, modifiers, .. } => {
+                        if let Some(command) = session.press_key(key, modifiers) {
+                            push_history(history, &command);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        });
+}
+
+fn show_broadcast_targets(ui: &mut Ui, state: &mut AdbToolsState) {
+    ui.horizontal_wrapped(|ui| {
+        if ui.small_button("All").clicked() {
+            state.shell_broadcast_targets = state.devices.iter()
+                .filter(|d| d.status == "device")
+                .map(|d| d.id.clone())
+                .collect();
+        }
+        if ui.small_button("None").clicked() {
+            state.shell_broadcast_targets.clear();
+        }
+        for device in &state.devices {
+            let mut checked = state.shell_broadcast_targets.contains(&device.id);
+            let label = if device.model.is_empty() {
+                device.id.clone()
+            } else {
+                format!("{} ({})", device.id, device.model)
+            };
+            let online = device.status == "device";
+            if ui.add_enabled(online, egui::Checkbox::new(&mut checked, label))
+                .on_disabled_hover_text(format!("Device is {}", device.status))
+                .changed() {
+                if checked {
+                    state.shell_broadcast_targets.insert(device.id.clone());
+                } else {
+                    state.shell_broadcast_targets.remove(&device.id);
+                }
+            }
+        }
+    });
+}
+
+fn show_broadcast_results(ui: &mut Ui, state: &mut AdbToolsState) {
+    let Some(run) = &state.shell_broadcast_run else {
+        return;
+    };
+    let differ_color = egui::Color32::from_rgb(230, 150, 40);
+
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(format!("`{}` on {} device(s)", run.command, run.results.len())).strong());
+        ui.label(format!("{} failed", run.failed_count()));
+        let differing = run.differing_count();
+        if differing > 0 {
+            ui.label(RichText::new(format!("{} differ from the majority", differing)).color(differ_color));
+        } else {
+            ui.label(RichText::new("all identical").color(egui::Color32::from_rgb(0, 200, 0)));
+        }
+    });
+    ui.horizontal(|ui| {
+        for view in BroadcastView::all() {
+            ui.radio_value(&mut state.shell_broadcast_view, view, view.name());
+        }
+        ui.checkbox(&mut state.shell_broadcast_diff_only, "Differences only");
+    });
+
+    let results: Vec<&BroadcastResult> = run.results.iter()
+        .filter(|r| !state.shell_broadcast_diff_only || r.differs)
+        .collect();
+    if results.is_empty() {
+        ui.label(RichText::new("Every device returned the same output").weak());
+        return;
+    }
+
+    let device_label = |result: &BroadcastResult| {
+        let text = if result.model.is_empty() {
+            result.serial.clone()
+        } else {
+            format!("{} ({})", result.serial, result.model)
+        };
+        if result.differs {
+            RichText::new(text).color(differ_color)
+        } else {
+            RichText::new(text)
+        }
+    };
+    let exit_label = |result: &BroadcastResult| match (&result.error, result.exit_code) {
+        (Some(_), _) => "error".to_string(),
+        (None, Some(code)) => code.to_string(),
+        (None, None) => "?".to_string(),
+    };
+
+    match state.shell_broadcast_view {
+        BroadcastView::Table => {
+            let row_height = ui.text_style_height(&egui::TextStyle::Body) + 4.0;
+            TableBuilder::new(ui)
+                .id_salt("broadcast_results_table")
+                .striped(true)
+                .resizable(true)
+                .max_scroll_height(300.0)
+                .auto_shrink([false, true])
+                .column(Column::initial(200.0))
+                .column(Column::initial(40.0))
+                .column(Column::initial(60.0))
+                .column(Column::remainder())
+                .header(row_height, |mut header| {
+                    header.col(|ui| { ui.strong("Device"); });
+                    header.col(|ui| { ui.strong("Exit"); });
+                    header.col(|ui| { ui.strong("Time"); });
+                    header.col(|ui| { ui.strong("Output"); });
+                })
+                .body(|mut body| {
+                    for result in &results {
+                        body.row(row_height, |mut row| {
+                            row.col(|ui| { ui.label(device_label(result)); });
+                            row.col(|ui| { ui.label(exit_label(result)); });
+                            row.col(|ui| { ui.label(format!("{:.2}s", result.duration.as_secs_f32())); });
+                            row.col(|ui| {
+                                let output = result.output();
+                                let first_line = output.lines().next().unwrap_or_default().to_string();
+                                let lines = output.lines().count();
+                                let text = if lines > 1 {
+                                    format!("{} … (+{} lines)", first_line, lines - 1)
+                                } else {
+                                    first_line
+                                };
+                                ui.label(RichText::new(text).monospace()).on_hover_text(RichText::new(output).monospace());
+                            });
+                        });
+                    }
+                });
+        }
+        BroadcastView::SideBySide => {
+            ScrollArea::horizontal().id_salt("broadcast_side_by_side").show(ui, |ui| {
+                ui.horizontal_top(|ui| {
+                    for result in &results {
+                        ui.group(|ui| {
+                            ui.set_width(320.0);
+                            ui.vertical(|ui| {
+                                ui.label(device_label(result));
+                                ui.label(RichText::new(format!(
+                                    "exit {} · {:.2}s",
+                                    exit_label(result),
+                                    result.duration.as_secs_f32()
+                                )).weak());
+                                ScrollArea::vertical()
+                                    .id_salt(format!("broadcast_output_{}", result.serial))
+                                    .max_height(300.0)
+                                    .show(ui, |ui| {
+                                        ui.code(result.output());
+                                    });
+                            });
+                        });
+                    }
+                });
+            });
+        }
+    }
+}
+
+fn show_logcat_tab(ui: &mut Ui, state: &mut AdbToolsState) {
+    show_job_status(ui, state, AdbFunction::Logcat);
+    
+    // Without a device only an opened log file can be shown
+    let offline = state.logcat_show_offline || state.selected_device.is_none();
+    let device_id = state.selected_device.clone();
+    let running = device_id.as_ref()
+        .and_then(|id| state.logcat_sessions.get(id))
+        .is_some_and(|s| s.is_running());
+    
+    ui.group(|ui| {
+        ui.label(RichText::new("Logcat").strong());
+        
+        ui.horizontal(|ui| {
+            ui.label("Source:");
+            ui.add_enabled_ui(device_id.is_some(), |ui| {
+                let label = format!("📱 {}", device_id.as_deref().unwrap_or("No device"));
+                ui.radio_value(&mut state.logcat_show_offline, false, label);
+            });
+            ui.add_enabled_ui(state.logcat_offline.is_some(), |ui| {
+                let label = match &state.logcat_offline {
+                    Some(session) => format!("📄 {}", session.serial),
+                    None => "📄 No file".to_string(),
+                };
+                ui.radio_value(&mut state.logcat_show_offline, true, label);
+            });
+        });
+        
+        if !offline {
+            ui.horizontal(|ui| {
+                if !running {
+                    if ui.button("▶️ Start Logcat").clicked() {
+                        start_logcat(state, ui.ctx());
+                    }
+                } else {
+                    if ui.button("⏹️ Stop Logcat").clicked() {
+                        stop_logcat(state);
+                    }
+                }
+                
+                if ui.button("🗑️ Clear").on_hover_text("Clear the device log buffer and this view").clicked() {
+                    clear_logcat(state);
+                }
+                
+                ui.separator();
+                ui.label("Buffer lines:");
+                if ui.add(egui::DragValue::new(&mut state.logcat_buffer_lines)
+                    .range(1_000..=1_000_000)
+                    .speed(1_000)).changed() {
+                    let capacity = state.logcat_buffer_lines;
+                    for session in state.logcat_sessions.values_mut() {
+                        session.set_capacity(capacity);
+                    }
+                }
+            });
+        }
+    });
+    
+    ui.group(|ui| {
+        ui.label(RichText::new("Log Files").strong());
+        
+        Grid::new("logcat_files_grid").num_columns(3).show(ui, |ui| {
+            ui.label("Open File:");
+            ui.text_edit_singleline(&mut state.logcat_import_path);
+            ui.horizontal(|ui| {
+                if ui.button("📂 Open").clicked() {
+                    import_logcat_file(state);
+                }
+                if state.logcat_offline.is_some() && ui.button("✖ Close").clicked() {
+                    state.logcat_offline = None;
+                    state.logcat_show_offline = false;
+                }
+            });
+            ui.end_row();
+            
+            ui.label("Save To:");
+            ui.text_edit_singleline(&mut state.logcat_export_path);
+            ui.horizontal(|ui| {
+                if ui.button("💾 Save").clicked() {
+                    export_logcat_file(state);
+                }
+                ui.checkbox(&mut state.logcat_export_filtered, "Only filtered lines");
+            });
+            ui.end_row();
+        });
+        
+        if !state.logcat_file_result.is_empty() {
+            ui.label(&state.logcat_file_result);
+        }
+    });
+    
+    show_logcat_filters(ui, state);
+    
+    let Some(session) = current_logcat_session(state) else {
+        return;
+    };
+    
+    if offline {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut session.auto_scroll, "Auto-scroll");
+            ui.separator();
+            ui.label(format!("📄 {}", session.serial));
+        });
+    } else {
+        ui.horizontal(|ui| {
+            let pause_label = if session.paused { "▶️ Resume" } else { "⏸️ Pause" };
+            if ui.button(pause_label).clicked() {
+                session.set_paused(!session.paused);
+            }
+            ui.checkbox(&mut session.auto_scroll, "Auto-scroll");
+            ui.separator();
+            ui.label(session.status.description());
+        });
+    }
+    
+    ui.horizontal(|ui| {
+        ui.label(format!("Showing: {} of {}", session.visible_len(), session.len()));
+        if !offline {
+            ui.label(format!("Buffer: {}", session.capacity()));
+            ui.label(format!("Received: {}", session.total_lines));
+        }
+        if session.dropped_lines > 0 {
+            ui.colored_label(egui::Color32::YELLOW, format!("Dropped: {}", session.dropped_lines));
+        }
+        if session.paused && session.pending_lines() > 0 {
+            ui.colored_label(egui::Color32::LIGHT_BLUE, format!("{} new while paused", session.pending_lines()));
+        }
+    });
+    
+    ui.separator();
+    
+    if !session.is_empty() {
+        show_logcat_table(ui, session);
+    }
+}
+
+/// The session the Logcat view is showing: an opened file or the selected device's stream
+fn current_logcat_session(state: &mut AdbToolsState) -> Option<&mut LogcatSession> {
+    if state.logcat_show_offline || state.selected_device.is_none() {
+        state.logcat_offline.as_mut()
+    } else {
+        let device_id = state.selected_device.as_ref()?;
+        state.logcat_sessions.get_mut(device_id)
+    }
+}
+
+fn show_logcat_filters(ui: &mut Ui, state: &mut AdbToolsState) {
+    ui.collapsing("🔍 Filters", |ui| {
+        let filter = &mut state.logcat_filters;
+        let mut package_committed = false;
+        
+        Grid::new("logcat_filter_grid").num_columns(4).show(ui, |ui| {
+            ui.label("Min Level:");
+            ComboBox::from_id_salt("logcat_min_priority")
+                .selected_text(filter.min_priority.display_name())
+                .show_ui(ui, |ui| {
+                    for priority in LogPriority::all() {
+                        ui.selectable_value(&mut filter.min_priority, priority, priority.display_name());
+                    }
+                });
+            ui.label("PID:");
+            ui.text_edit_singleline(&mut filter.pid);
+            ui.end_row();
+            
+            ui.label("Tags:");
+            ui.text_edit_singleline(&mut filter.tag_include)
+                .on_hover_text("Comma separated; only these tags are shown");
+            ui.label("Exclude Tags:");
+            ui.text_edit_singleline(&mut filter.tag_exclude)
+                .on_hover_text("Comma separated; these tags are hidden");
+            ui.end_row();
+            
+            ui.label("Package:");
+            package_committed = ui.text_edit_singleline(&mut filter.package)
+                .on_hover_text("Only show processes of this package")
+                .lost_focus();
+            ui.label("Message Regex:");
+            ui.text_edit_singleline(&mut filter.message_regex);
+            ui.end_row();
+        });
+        
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut filter.case_sensitive, "Case sensitive");
+            if ui.button("↺ Reset Filters").clicked() {
+                *filter = LogcatFilter::default();
+            }
+        });
+        
+        if let Some(error) = &state.logcat_filter_error {
+            ui.colored_label(egui::Color32::RED, format!("❌ {}", error));
+        }
+        
+        if package_committed {
+            resolve_logcat_package(state);
+        }
+    });
+}
+
+fn show_logcat_table(ui: &mut Ui, session: &LogcatSession) {
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+    
+    TableBuilder::new(ui)
+        .id_salt("logcat_table")
+        .striped(true)
+        .resizable(true)
+        .stick_to_bottom(session.auto_scroll)
+        .max_scroll_height(400.0)
+        .auto_shrink([false, true])
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::exact(140.0))
+        .column(Column::exact(50.0))
+        .column(Column::exact(50.0))
+        .column(Column::exact(20.0))
+        .column(Column::initial(160.0).clip(true))
+        .column(Column::remainder().clip(true))
+        .header(row_height + 4.0, |mut header| {
+            for title in ["Time", "PID", "TID", "", "Tag", "Message"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(row_height, session.visible_len(), |mut row| {
+                let Some(entry) = session.visible_entry(row.index()) else {
+                    return;
+                };
+                let color = entry.priority.map(|p| p.color()).unwrap_or(egui::Color32::GRAY);
+                let cell = |text: String| RichText::new(text).monospace().color(color);
+                
+                row.col(|ui| {
+                    ui.label(cell(entry.timestamp.clone()));
+                });
+                row.col(|ui| {
+                    ui.label(cell(entry.pid.map(|p| p.to_string()).unwrap_or_default()));
+                });
+                row.col(|ui| {
+                    ui.label(cell(entry.tid.map(|t| t.to_string()).unwrap_or_default()));
+                });
+                row.col(|ui| {
+                    ui.label(cell(entry.priority.map(|p| p.letter()).unwrap_or_default().to_string()));
+                });
+                row.col(|ui| {
+                    ui.label(cell(entry.tag.clone()));
+                });
+                row.col(|ui| {
+                    ui.label(cell(entry.message.clone()));
+                });
+            });
+        });
+}
+
+fn show_screen_tab(ui: &mut Ui, state: &mut AdbToolsState) {
+    show_job_status(ui, state, AdbFunction::ScreenCapture);
+    
+    let recording = is_screen_recording(state);
+    
+    ui.group(|ui| {
+        ui.label(RichText::new("Screen Capture").strong());
+        
+        Grid::new("screen_grid").num_columns(2).show(ui, |ui| {
+            ui.label("Screenshot Path:");
+            ui.text_edit_singleline(&mut state.screenshot_path);
+            ui.end_row();
+            
+            ui.label("Screen Record Path:");
+            ui.text_edit_singleline(&mut state.screen_record_path);
+            ui.end_row();
+        });
+        
+        if ui.button("📸 Take Screenshot").clicked() {
+            take_screenshot(state);
+        }
+    });
+    
+    ui.group(|ui| {
+        ui.label(RichText::new("Screen Recording").strong());
+        
+        ui.add_enabled_ui(!recording, |ui| {
+            let options = &mut state.screen_record_options;
+            Grid::new("screen_record_grid").num_columns(2).show(ui, |ui| {
+                ui.label("Bit Rate (Mbps):");
+                ui.add(egui::DragValue::new(&mut options.bit_rate_mbps).range(0.5..=100.0).speed(0.5));
+                ui.end_row();
+                
+                ui.label("Size:");
+                ui.add(egui::TextEdit::singleline(&mut options.size).hint_text("e.g. 1280x720, empty for native"));
+                ui.end_row();
+                
+                ui.label("Time Limit (s):");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut options.time_limit_secs).range(0..=3600));
+                    ui.label(if options.time_limit_secs == 0 {
+                        "until stopped".to_string()
+                    } else if options.time_limit_secs > MAX_SEGMENT_SECS {
+                        format!("{} segments", options.time_limit_secs.div_ceil(MAX_SEGMENT_SECS))
+                    } else {
+                        String::new()
+                    });
+                });
+                ui.end_row();
+                
+                ui.label("Overlay:");
+                ui.checkbox(&mut options.bugreport, "Bugreport timestamps (--bugreport)");
+                ui.end_row();
+            });
+        });
+        
+        ui.horizontal(|ui| {
+            if ui.add_enabled(!recording, egui::Button::new("🎥 Start Recording")).clicked() {
+                start_screen_record(state);
+            }
+            if ui.add_enabled(recording, egui::Button::new("⏹️ Stop Recording")).clicked() {
+                stop_screen_record(state);
+            }
+        });
+        
+        ui.label(format!(
+            "Recordings longer than {} seconds are saved as numbered segments",
+            MAX_SEGMENT_SECS
+        ));
+    });
+    
+    show_screen_mirror(ui, state);
+    
+    if !state.screen_result.is_empty() {
+        ui.label(&state.screen_result);
+    }
+}
+
+fn show_screen_mirror(ui: &mut Ui, state: &mut AdbToolsState) {
+    ui.group(|ui| {
+        ui.label(RichText::new("Live Mirror").strong());
+        
+        ui.horizontal(|ui| {
+            let options = &mut state.screen_mirror_options;
+            ui.label("FPS:");
+            let fps_changed = ui.add(egui::DragValue::new(&mut options.fps).range(0.2..=15.0).speed(0.1)).changed();
+            ui.label("Scale:");
+            let scale_changed = ui.add(egui::Slider::new(&mut options.scale, 0.1..=1.0)).changed();
+            if fps_changed || scale_changed {
+                if let Some(mirror) = &state.screen_mirror {
+                    mirror.set_options(options);
+                }
+            }
+        });
+        
+        ui.horizontal(|ui| {
+            match &state.screen_mirror {
+                None => {
+                    if ui.button("▶️ Start Mirror").clicked() {
+                        if let Some(device_id) = &state.selected_device {
+                            state.screen_mirror = Some(ScreenMirror::start(
+                                &state.adb_client,
+                                device_id,
+                                &state.screen_mirror_options,
+                                ui.ctx().clone(),
+                            ));
+                        }
+                    }
+                }
+                Some(mirror) => {
+                    if ui.button("⏹️ Stop Mirror").clicked() {
+                        state.screen_mirror = None;
+                    } else {
+                        let paused = mirror.is_paused();
+                        if ui.button(if paused { "▶️ Resume" } else { "⏸️ Pause" }).clicked() {
+                            mirror.set_paused(!paused);
+                        }
+                        if ui.button("💾 Save Frame").clicked() {
+                            state.screen_result = save_mirror_frame(mirror, &state.screenshot_path);
+                        }
+                        ui.separator();
+                        ui.label(format!("📱 {}", mirror.serial));
+                        let [width, height] = mirror.device_size();
+                        if width > 0 {
+                            ui.label(format!("{}x{}", width, height));
+                        }
+                        ui.label(format!("{:.1} fps", mirror.measured_fps()));
+                    }
+                }
+            }
+        });
+        
+        if state.screen_mirror.is_some() {
+            ui.checkbox(&mut state.remote_input_enabled, "🖱 Control device (click = tap, drag = swipe, wheel = roll)");
+        }
+        
+        let mut actions = Vec::new();
+        if let Some(mirror) = &state.screen_mirror {
+            if let Some(error) = mirror.error() {
+                ui.colored_label(egui::Color32::RED, format!("❌ {}", error));
+            }
+            match mirror.texture() {
+                Some(texture) => {
+                    let sense = if state.remote_input_enabled { egui::Sense::click_and_drag() } else { egui::Sense::hover() };
+                    let response = ui.add(egui::Image::new((texture.id(), texture.size_vec2())).sense(sense));
+                    if state.remote_input_enabled {
+                        actions = mirror_input_actions(ui, &response, mirror, &mut state.remote_gesture);
+                    }
+                }
+                None => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Waiting for first frame...");
+                    });
+                }
+            }
+        }
+        
+        show_remote_keypad(ui, state, &mut actions);
+        send_input_actions(state, actions);
+    });
+}
+
+/// In-progress pointer gesture on the mirrored frame
+#[derive(Debug, Default)]
+pub struct RemoteGesture {
+    drag_start: Option<((i32, i32), Instant)>,
+    scroll: egui::Vec2,
+}
+
+/// Turn clicks, drags and wheel scrolling on the mirror image into device input
+fn mirror_input_actions(ui: &Ui, response: &egui::Response, mirror: &ScreenMirror, gesture: &mut RemoteGesture) -> Vec<InputAction> {
+    let rect = response.rect;
+    let to_device = |pos: egui::Pos2| mirror.to_device_point((pos - rect.min) / rect.size());
+    let mut actions = Vec::new();
+    
+    if response.drag_started() {
+        let origin = ui.input(|i| i.pointer.press_origin());
+        gesture.drag_start = origin.and_then(to_device).map(|point| (point, Instant::now()));
+    }
+    if response.drag_stopped() {
+        if let (Some((from, started)), Some(to)) = (gesture.drag_start.take(), response.interact_pointer_pos().and_then(to_device)) {
+            let duration_ms = (started.elapsed().as_millis() as u32).clamp(50, 3000);
+            actions.push(InputAction::Swipe { from, to, duration_ms });
+        }
+    } else if response.clicked() {
+        if let Some(point) = response.interact_pointer_pos().and_then(to_device) {
+            actions.push(InputAction::Tap(point.0, point.1));
+        }
+    }
+    
+    if response.hovered() {
+        // One roll step per wheel notch; scrolling down rolls down
+        const SCROLL_STEP: f32 = 50.0;
+        gesture.scroll += ui.input(|i| i.raw_scroll_delta);
+        let steps = egui::vec2((gesture.scroll.x / SCROLL_STEP).trunc(), (gesture.scroll.y / SCROLL_STEP).trunc());
+        if steps != egui::Vec2::ZERO {
+            gesture.scroll -= steps * SCROLL_STEP;
+            actions.push(InputAction::Roll(-steps.x as i32, -steps.y as i32));
+        }
+    }
+    
+    if !actions.is_empty() && mirror.geometry().is_none() {
+        log::warn!("Display geometry not known yet, dropping input");
+        actions.clear();
+    }
+    actions
+}
+
+fn show_remote_keypad(ui: &mut Ui, state: &mut AdbToolsState, actions: &mut Vec<InputAction>) {
+    ui.separator();
+    ui.label(RichText::new("Remote Input").strong());
+    
+    ui.horizontal_wrapped(|ui| {
+        for (label, key) in KEYPAD_KEYS {
+            if ui.button(*label).clicked() {
+                actions.push(InputAction::KeyEvent(key.to_string()));
+            }
+        }
+    });
+    
+    ui.horizontal(|ui| {
+        ui.label("Text:");
+        let response = ui.text_edit_singleline(&mut state.remote_input_text);
+        let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        if (ui.button("⌨ Send Text").clicked() || submitted) && !state.remote_input_text.is_empty() {
+            if !state.remote_input_text.is_ascii() {
+                state.screen_result = "⚠️ input text only supports ASCII characters".to_string();
+            }
+            actions.push(InputAction::Text(std::mem::take(&mut state.remote_input_text)));
+        }
+    });
+    
+    if let Some(error) = state.remote_input.as_ref().and_then(|input| input.last_error()) {
+        ui.colored_label(egui::Color32::RED, format!("❌ {}", error));
+    }
+}
+
+fn send_input_actions(state: &mut AdbToolsState, actions: Vec<InputAction>) {
+    if actions.is_empty() {
+        return;
+    }
+    let Some(device_id) = state.screen_mirror.as_ref().map(|m| m.serial.clone()).or_else(|| state.selected_device.clone()) else {
+        return;
+    };
+    if state.remote_input.as_ref().map(|input| &input.serial) != Some(&device_id) {
+        state.remote_input = Some(RemoteInput::new(&state.adb_client, &device_id));
+    }
+    if let Some(input) = &state.remote_input {
+        for action in actions {
+            input.send(action);
+        }
+    }
+}
+
+/// Save the mirrored frame next to the screenshot path, stamped with the current time
+fn save_mirror_frame(mirror: &ScreenMirror, screenshot_path: &str) -> String {
+    let base = Path::new(screenshot_path);
+    let stem = base.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| "frame".to_string());
+    let name = format!("{}_{}.png", stem, chrono::Local::now().format("%Y%m%d_%H%M%S"));
+    let path = base.with_file_name(name);
+    
+    match mirror.save_frame(&path) {
+        Ok(()) => format!("✅ Frame saved to: {}", path.display()),
+        Err(e) => format!("❌ {}", e),
+    }
+}
+
+fn show_port_forward_tab(ui: &mut Ui, state: &mut AdbToolsState) {
+    show_job_status(ui, state, AdbFunction::PortForwarding);
+
+    // The list always comes from the server, so reload it whenever the device changes
+    if state.port_forwards_device != state.selected_device && !state.jobs.is_running(AdbFunction::PortForwarding) {
+        list_port_forwards(state);
+    }
+
     ui.group(|ui| {
-        ui.label(RichText::new("ADB Shell").strong());
-        
-        ui.horizontal(|ui| {
-            ui.label("Command:");
-            ui.text_edit_singleline(&mut state.shell_command);
-            if ui.button("▶️ Execute").clicked() {
-                execute_shell_command(state);
-            }
+        ui.label(RichText::new("Port Forwarding").strong());
+
+        Grid::new("port_grid").num_columns(3).show(ui, |ui| {
+            ui.label("Direction:");
+            ComboBox::from_id_salt("forward_direction")
+                .selected_text(state.forward_direction.name())
+                .show_ui(ui, |ui| {
+                    for direction in ForwardDirection::all() {
+                        ui.selectable_value(&mut state.forward_direction, direction, direction.name());
+                    }
+                });
+            ui.label("");
+            ui.end_row();
+
+            let direction = state.forward_direction;
+            ui.label("Local (host):");
+            show_socket_kind_combo(ui, "forward_local_kind", &mut state.forward_local_kind, direction, false);
+            ui.add(egui::TextEdit::singleline(&mut state.local_port).hint_text(state.forward_local_kind.hint()));
+            ui.end_row();
+
+            ui.label("Remote (device):");
+            show_socket_kind_combo(ui, "forward_remote_kind", &mut state.forward_remote_kind, direction, true);
+            ui.add(egui::TextEdit::singleline(&mut state.remote_port).hint_text(state.forward_remote_kind.hint()));
+            ui.end_row();
         });
-        
-        // Common commands
+
         ui.horizontal(|ui| {
-            if ui.small_button("ls /sdcard/").clicked() {
-                state.shell_command = "ls /sdcard/".to_string();
+            if ui.button("➕ Add").clicked() {
+                add_port_forward(state);
             }
-            if ui.small_button("ps").clicked() {
-                state.shell_command = "ps".to_string();
+            if ui.button("🔄 Refresh").clicked() {
+                list_port_forwards(state);
             }
-            if ui.small_button("df -h").clicked() {
-                state.shell_command = "df -h".to_string();
+            if ui.button("🗑️ Remove All Forwards").clicked() {
+                remove_all_port_forwards(state, ForwardDirection::Forward);
             }
-            if ui.small_button("getprop").clicked() {
-                state.shell_command = "getprop".to_string();
+            if ui.button("🗑️ Remove All Reverses").clicked() {
+                remove_all_port_forwards(state, ForwardDirection::Reverse);
             }
         });
+
+        if !state.forward_result.is_empty() {
+            ui.label(&state.forward_result);
+        }
     });
-    
-    if !state.shell_output.is_empty() {
-        ui.separator();
-        ui.label("Output:");
-        ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
-            ui.code(&state.shell_output);
+
+    ui.separator();
+    ui.label(RichText::new("Active on this device").strong());
+    if state.port_forwards.is_empty() {
+        ui.label(RichText::new("No forwards or reverses").weak());
+    } else {
+        let mut to_remove = None;
+        Grid::new("port_forward_list").num_columns(4).striped(true).show(ui, |ui| {
+            for rule in &state.port_forwards {
+                ui.label(match rule.direction {
+                    ForwardDirection::Forward => "forward",
+                    ForwardDirection::Reverse => "reverse",
+                });
+                ui.label(&rule.local);
+                ui.label(format!("{} {}", rule.direction.arrow(), rule.remote));
+                if ui.small_button("❌").on_hover_text("Remove").clicked() {
+                    to_remove = Some(rule.clone());
+                }
+                ui.end_row();
+            }
         });
+        if let Some(rule) = to_remove {
+            remove_port_forward(state, rule);
+        }
     }
+
+    ui.separator();
+    show_forward_profiles(ui, state);
 }
 
-fn show_logcat_tab(ui: &mut Ui, state: &mut AdbToolsState) {
-    ui.group(|ui| {
-        ui.label(RichText::new("Logcat").strong());
-        
-        ui.horizontal(|ui| {
-            ui.label("Filter/Tag:");
-            ui.text_edit_singleline(&mut state.logcat_filter);
-            
-            if !state.logcat_running {
-                if ui.button("▶️ Start Logcat").clicked() {
-                    start_logcat(state);
-                }
-            } else {
-                if ui.button("⏹️ Stop Logcat").clicked() {
-                    stop_logcat(state);
-                }
-            }
-            
-            if ui.button("🗑️ Clear").clicked() {
-                clear_logcat(state);
+fn show_socket_kind_combo(ui: &mut Ui, id: &str, kind: &mut SocketKind, direction: ForwardDirection, device_side: bool) {
+    if !kind.allowed(direction, device_side) {
+        *kind = SocketKind::Tcp;
+    }
+    ComboBox::from_id_salt(id)
+        .selected_text(kind.prefix())
+        .show_ui(ui, |ui| {
+            for option in SocketKind::all().into_iter().filter(|k| k.allowed(direction, device_side)) {
+                ui.selectable_value(kind, option, option.prefix());
             }
         });
+}
+
+fn show_forward_profiles(ui: &mut Ui, state: &mut AdbToolsState) {
+    ui.label(RichText::new("Profiles").strong());
+    ui.label(RichText::new("Auto-apply profiles are re-applied whenever a matching device comes online").weak());
+
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(&mut state.forward_profile_name).hint_text("profile name").desired_width(160.0));
+        let can_save = !state.forward_profile_name.trim().is_empty() && !state.port_forwards.is_empty();
+        if ui.add_enabled(can_save, egui::Button::new("💾 Save Current"))
+            .on_hover_text("Store the active forwards and reverses of this device under this name")
+            .clicked() {
+            save_forward_profile(state);
+        }
     });
-    
-    ui.separator();
-    
-    if !state.logcat_output.is_empty() {
-        ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
-            ui.code(&state.logcat_output);
+
+    if state.forward_profiles.is_empty() {
+        return;
+    }
+    let mut to_apply = None;
+    let mut to_remove = None;
+    Grid::new("forward_profiles_grid").num_columns(5).striped(true).show(ui, |ui| {
+        for (index, profile) in state.forward_profiles.iter_mut().enumerate() {
+            ui.label(RichText::new(&profile.name).strong())
+                .on_hover_text(profile.rules.iter().map(ForwardRule::describe).collect::<Vec<_>>().join("\n"));
+            ui.label(format!("{} rule(s)", profile.rules.len()));
+            ui.add(egui::TextEdit::singleline(&mut profile.device).hint_text("any device").desired_width(140.0));
+            ui.checkbox(&mut profile.auto_apply, "Auto-apply");
+            ui.horizontal(|ui| {
+                if ui.small_button("▶ Apply").on_hover_text("Apply to the selected device").clicked() {
+                    to_apply = Some(index);
+                }
+                if ui.small_button("🗑️").on_hover_text("Delete profile").clicked() {
+                    to_remove = Some(index);
+                }
+            });
+            ui.end_row();
+        }
+    });
+
+    if let Some(index) = to_apply {
+        let profile = state.forward_profiles[index].clone();
+        submit_forward_action(state, format!("Apply profile {}", profile.name), move |client, serial| {
+            let added = apply_profile(client, serial, &profile)?;
+            Ok(format!("✅ Applied {}: {} rule(s) added", profile.name, added))
         });
     }
+    if let Some(index) = to_remove {
+        state.forward_profiles.remove(index);
+    }
 }
 
-fn show_screen_tab(ui: &mut Ui, state: &mut AdbToolsState) {
+fn show_app_tracking(ui: &mut Ui, state: &mut AdbToolsState) {
     ui.group(|ui| {
-        ui.label(RichText::new("Screen Capture").strong());
+        ui.label(RichText::new("📦 App Tracking").strong());
         
-        Grid::new("screen_grid").num_columns(2).show(ui, |ui| {
-            ui.label("Screenshot Path:");
-            ui.text_edit_singleline(&mut state.screenshot_path);
-            ui.end_row();
-            
-            ui.label("Screen Record Path:");
-            ui.text_edit_singleline(&mut state.screen_record_path);
-            ui.end_row();
+        ui.horizontal(|ui| {
+            ui.label("Package or PID:");
+            ui.add(egui::TextEdit::singleline(&mut state.app_track_target)
+                .hint_text("com.example.app or 1234")
+                .desired_width(220.0));
+            if state.app_tracking.is_some() {
+                if ui.button("⏹️ Stop Tracking").clicked() {
+                    state.app_tracking = None;
+                }
+            } else if ui.add_enabled(!state.app_track_target.trim().is_empty(), egui::Button::new("▶️ Track"))
+                .on_hover_text("Packages are followed across restarts; a PID is tracked until it exits")
+                .clicked() {
+                start_app_tracking(state);
+            }
         });
         
+        let Some(tracking) = &state.app_tracking else {
+            return;
+        };
+        ui.label(tracking.status());
+        let Some(sample) = tracking.last.as_ref().filter(|s| s.pid.is_some()) else {
+            return;
+        };
+        let memory = |kb: Option<u64>| kb.map(|kb| format_bytes(kb * 1024)).unwrap_or_else(|| "—".to_string());
+        let count = |value: Option<u64>| value.map(|v| v.to_string()).unwrap_or_else(|| "—".to_string());
+        Grid::new("app_tracking_grid")
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("PSS:");
+                ui.label(memory(sample.pss_kb));
+                ui.label("RSS:");
+                ui.label(memory(sample.rss_kb));
+                ui.end_row();
+                ui.label("Java Heap:");
+                ui.label(memory(sample.java_heap_kb));
+                ui.label("Native Heap:");
+                ui.label(memory(sample.native_heap_kb));
+                ui.end_row();
+                ui.label("CPU:");
+                ui.label(sample.cpu_percent.map(|c| format!("{:.1}%", c)).unwrap_or_else(|| "—".to_string()));
+                ui.label("Threads:");
+                ui.label(count(sample.threads));
+                ui.end_row();
+                ui.label("Open FDs:");
+                ui.label(count(sample.fds)).on_hover_text("Needs root, or a debuggable app, to read another app's /proc/<pid>/fd");
+                ui.end_row();
+            });
+    });
+}
+
+fn show_heap_dump(ui: &mut Ui, state: &mut AdbToolsState) {
+    ui.group(|ui| {
+        ui.label(RichText::new("🧠 Heap Dump").strong());
+        
         ui.horizontal(|ui| {
-            if ui.button("📸 Take Screenshot").clicked() {
-                take_screenshot(state);
-            }
-            if ui.button("🎥 Start Recording").clicked() {
-                start_screen_record(state);
-            }
-            if ui.button("⏹️ Stop Recording").clicked() {
-                stop_screen_record(state);
+            ui.label("Package or PID:");
+            ui.add(egui::TextEdit::singleline(&mut state.heap_dump_target)
+                .hint_text("com.example.app or 1234")
+                .desired_width(220.0));
+            if ui.add_enabled(!state.heap_dump_target.trim().is_empty(), egui::Button::new("🧠 Dump Heap"))
+                .on_hover_text("am dumpheap, then pull and convert to standard hprof")
+                .clicked() {
+                start_heap_dump(state);
             }
         });
+        ui.horizontal(|ui| {
+            ui.label("Save to:");
+            ui.add(egui::TextEdit::singleline(&mut state.heap_dump_dir).desired_width(220.0));
+        });
+        
+        if !state.monitor_result.is_empty() {
+            ui.label(&state.monitor_result);
+        }
+        
+        if state.heap_dump.is_some() {
+            show_heap_histogram(ui, state);
+        }
     });
 }
 
-fn show_port_forward_tab(ui: &mut Ui, state: &mut AdbToolsState) {
+fn show_heap_histogram(ui: &mut Ui, state: &mut AdbToolsState) {
+    let Some(dump) = &state.heap_dump else {
+        return;
+    };
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(format!(
+            "{} classes, {} instances, {} shallow",
+            dump.histogram.len(),
+            dump.total_instances(),
+            format_bytes(dump.total_bytes())
+        )).strong());
+        ui.label("Filter:");
+        ui.add(egui::TextEdit::singleline(&mut state.heap_histogram_filter)
+            .hint_text("class name")
+            .desired_width(200.0));
+    });
+    
+    let filter = state.heap_histogram_filter.trim().to_lowercase();
+    let entries: Vec<_> = dump.histogram.iter()
+        .filter(|e| filter.is_empty() || e.name.to_lowercase().contains(&filter))
+        .collect();
+    let row_height = ui.text_style_height(&egui::TextStyle::Body) + 4.0;
+    TableBuilder::new(ui)
+        .id_salt("heap_histogram_table")
+        .striped(true)
+        .resizable(true)
+        .max_scroll_height(300.0)
+        .auto_shrink([false, true])
+        .column(Column::initial(360.0).clip(true))
+        .column(Column::initial(100.0))
+        .column(Column::remainder())
+        .header(row_height, |mut header| {
+            for title in ["Class", "Instances", "Shallow Size"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(row_height, entries.len(), |mut row| {
+                let entry = entries[row.index()];
+                row.col(|ui| {
+                    ui.label(RichText::new(&entry.name).monospace()).on_hover_text(&entry.name);
+                });
+                row.col(|ui| {
+                    ui.label(entry.instances.to_string());
+                });
+                row.col(|ui| {
+                    ui.label(format_bytes(entry.shallow_bytes));
+                });
+            });
+        });
+}
+
+fn show_thermal(ui: &mut Ui, state: &mut AdbToolsState) {
+    let Some(thermal) = &state.thermal else {
+        return;
+    };
     ui.group(|ui| {
-        ui.label(RichText::new("Port Forwarding").strong());
+        ui.label(RichText::new("🌡️ Thermal Zones & Cooling").strong());
         
-        Grid::new("port_grid").num_columns(2).show(ui, |ui| {
-            ui.label("Local Port:");
-            ui.text_edit_singleline(&mut state.local_port);
-            ui.end_row();
-            
-            ui.label("Remote Port:");
-            ui.text_edit_singleline(&mut state.remote_port);
-            ui.end_row();
+        let alerts = thermal.alerts(&state.thermal_thresholds);
+        if !alerts.is_empty() {
+            let names: Vec<String> = alerts
+                .iter()
+                .map(|(zone, threshold)| format!("{} {:.1}°C ≥ {:.0}°C", zone.name, zone.celsius.unwrap_or_default(), threshold))
+                .collect();
+            ui.colored_label(egui::Color32::from_rgb(255, 100, 100), format!("⚠️ Over threshold: {}", names.join(", ")));
+        }
+        
+        if let Some(service) = &thermal.service {
+            ui.horizontal_wrapped(|ui| {
+                if let Some(status) = service.status {
+                    let color = if status > 0 {
+                        egui::Color32::from_rgb(255, 150, 50)
+                    } else {
+                        egui::Color32::from_rgb(100, 200, 100)
+                    };
+                    ui.label("thermalservice:");
+                    ui.colored_label(color, thermal_status_name(status));
+                }
+                for temperature in &service.temperatures {
+                    ui.label(format!("{} ({}) {:.1}°C", temperature.name, temperature.kind, temperature.celsius))
+                        .on_hover_text(format!("Throttling status {}", thermal_status_name(temperature.status)));
+                }
+            });
+        }
+        
+        let row_height = ui.text_style_height(&egui::TextStyle::Body) + 4.0;
+        ui.label(format!("Zones ({})", thermal.zones.len()));
+        TableBuilder::new(ui)
+            .id_salt("thermal_zone_table")
+            .striped(true)
+            .resizable(true)
+            .max_scroll_height(250.0)
+            .auto_shrink([false, true])
+            .column(Column::exact(40.0))
+            .column(Column::exact(50.0))
+            .column(Column::initial(200.0).clip(true))
+            .column(Column::initial(90.0))
+            .column(Column::remainder())
+            .header(row_height, |mut header| {
+                for title in ["Plot", "Zone", "Type", "Temp", "Alert Threshold"] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(row_height, thermal.zones.len(), |mut row| {
+                    let zone = &thermal.zones[row.index()];
+                    let threshold = state.thermal_thresholds.get(&zone.name).copied();
+                    let over = threshold.zip(zone.celsius).is_some_and(|(limit, celsius)| celsius >= limit);
+                    row.col(|ui| {
+                        let mut plotted = state.thermal_plot_zones.contains(&zone.name);
+                        if ui.checkbox(&mut plotted, "").changed() {
+                            if plotted {
+                                state.thermal_plot_zones.insert(zone.name.clone());
+                            } else {
+                                state.thermal_plot_zones.remove(&zone.name);
+                            }
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.label(zone.index.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(&zone.name);
+                    });
+                    row.col(|ui| {
+                        let text = zone.celsius.map(|c| format!("{:.1}°C", c)).unwrap_or_else(|| "—".to_string());
+                        if over {
+                            ui.colored_label(egui::Color32::from_rgb(255, 100, 100), text);
+                        } else {
+                            ui.label(text);
+                        }
+                    });
+                    row.col(|ui| {
+                        match state.thermal_thresholds.get_mut(&zone.name) {
+                            Some(limit) => {
+                                ui.add(egui::DragValue::new(limit).range(-40.0..=150.0).speed(0.5).suffix("°C"));
+                                if ui.small_button("✖").on_hover_text("Remove the alert").clicked() {
+                                    state.thermal_thresholds.remove(&zone.name);
+                                }
+                            }
+                            None => {
+                                if ui.small_button("➕ Alert").clicked() {
+                                    let limit = zone.celsius.map(|c| (c + 10.0).round()).unwrap_or(80.0);
+                                    state.thermal_thresholds.insert(zone.name.clone(), limit);
+                                }
+                            }
+                        }
+                    });
+                });
+            });
+        
+        if !thermal.cooling.is_empty() {
+            let active = thermal.cooling.iter().filter(|c| c.is_active()).count();
+            ui.label(format!("Cooling devices ({}, {} active)", thermal.cooling.len(), active));
+            TableBuilder::new(ui)
+                .id_salt("cooling_device_table")
+                .striped(true)
+                .resizable(true)
+                .max_scroll_height(200.0)
+                .auto_shrink([false, true])
+                .column(Column::exact(50.0))
+                .column(Column::initial(200.0).clip(true))
+                .column(Column::remainder())
+                .header(row_height, |mut header| {
+                    for title in ["Device", "Type", "State"] {
+                        header.col(|ui| {
+                            ui.strong(title);
+                        });
+                    }
+                })
+                .body(|body| {
+                    body.rows(row_height, thermal.cooling.len(), |mut row| {
+                        let device = &thermal.cooling[row.index()];
+                        row.col(|ui| {
+                            ui.label(device.index.to_string());
+                        });
+                        row.col(|ui| {
+                            ui.label(&device.name);
+                        });
+                        row.col(|ui| {
+                            match (device.current, device.max) {
+                                (Some(current), Some(max)) if max > 0 => {
+                                    ui.add(egui::ProgressBar::new(current as f32 / max as f32)
+                                        .desired_width(160.0)
+                                        .text(format!("{} / {}", current, max)));
+                                }
+                                (current, max) => {
+                                    let value = |v: Option<u64>| v.map(|v| v.to_string()).unwrap_or_else(|| "—".to_string());
+                                    ui.label(format!("{} / {}", value(current), value(max)));
+                                }
+                            }
+                        });
+                    });
+                });
+        }
+    });
+}
+
+fn show_thermal_plot(ui: &mut Ui, state: &AdbToolsState) {
+    // Zone names come from the latest sample; series are kept by index
+    let names: BTreeMap<usize, &str> = state
+        .thermal
+        .iter()
+        .flat_map(|t| t.zones.iter())
+        .map(|z| (z.index, z.name.as_str()))
+        .collect();
+    let plotted: Vec<(usize, &str)> = names
+        .iter()
+        .filter(|(_, name)| state.thermal_plot_zones.contains(**name))
+        .map(|(index, name)| (*index, *name))
+        .collect();
+    if plotted.is_empty() {
+        return;
+    }
+    
+    ui.label("Thermal Zones °C");
+    Plot::new("thermal_plot")
+        .height(180.0)
+        .view_aspect(3.0)
+        .legend(Legend::default())
+        .show(ui, |plot_ui| {
+            let count = plotted.len() as f32;
+            for (position, (index, name)) in plotted.iter().enumerate() {
+                let color = egui::Color32::from(egui::ecolor::Hsva::new(position as f32 / count, 0.7, 0.9, 1.0));
+                if let Some(data) = state.time_series.thermal_zones.get(index) {
+                    let points: PlotPoints = data.iter().map(|p| [p.timestamp, p.value]).collect();
+                    plot_ui.line(Line::new(points).color(color).name(*name));
+                }
+                if let Some(limit) = state.thermal_thresholds.get(*name) {
+                    plot_ui.hline(
+                        HLine::new(*limit)
+                            .color(color)
+                            .style(LineStyle::dashed_loose())
+                            .name(format!("{} alert", name))
+                    );
+                }
+            }
         });
+}
+
+fn show_network(ui: &mut Ui, state: &mut AdbToolsState) {
+    ui.group(|ui| {
+        ui.label(RichText::new("🌐 Network Statistics").strong());
         
         ui.horizontal(|ui| {
-            if ui.button("➡️ Forward Port").clicked() {
-                forward_port(state);
-            }
-            if ui.button("🗑️ Remove All").clicked() {
-                remove_all_forwards(state);
+            ui.checkbox(&mut state.network_hide_idle, "Hide idle interfaces");
+            if ui.button("📶 Per-UID Traffic").on_hover_text("From /proc/net/xt_qtaguid, or dumpsys netstats on newer releases").clicked() {
+                refresh_uid_traffic(state);
             }
         });
+        
+        let rates: Vec<&InterfaceRates> = state.network_rates
+            .iter()
+            .filter(|r| !state.network_hide_idle || !r.is_idle())
+            .collect();
+        if state.network_rates.is_empty() {
+            ui.label("Measuring throughput…");
+        } else {
+            let rate = |bytes: f64| format!("{}/s", format_bytes(bytes as u64));
+            let row_height = ui.text_style_height(&egui::TextStyle::Body) + 4.0;
+            TableBuilder::new(ui)
+                .id_salt("network_rates_table")
+                .striped(true)
+                .resizable(true)
+                .max_scroll_height(250.0)
+                .auto_shrink([false, true])
+                .column(Column::initial(120.0).clip(true))
+                .columns(Column::initial(95.0), 6)
+                .header(row_height, |mut header| {
+                    for title in ["Interface", "RX", "TX", "RX pkt/s", "TX pkt/s", "RX Total", "TX Total"] {
+                        header.col(|ui| {
+                            ui.strong(title);
+                        });
+                    }
+                })
+                .body(|body| {
+                    body.rows(row_height, rates.len(), |mut row| {
+                        let entry = rates[row.index()];
+                        let cells = [
+                            entry.name.clone(),
+                            rate(entry.rx_bytes_per_sec),
+                            rate(entry.tx_bytes_per_sec),
+                            format!("{:.1}", entry.rx_packets_per_sec),
+                            format!("{:.1}", entry.tx_packets_per_sec),
+                            format_bytes(entry.totals.rx_bytes),
+                            format_bytes(entry.totals.tx_bytes),
+                        ];
+                        for cell in cells {
+                            row.col(|ui| {
+                                ui.label(cell);
+                            });
+                        }
+                    });
+                });
+        }
+        
+        if !state.uid_traffic.is_empty() {
+            show_uid_traffic(ui, state);
+        }
+    });
+}
+
+fn show_uid_traffic(ui: &mut Ui, state: &mut AdbToolsState) {
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(format!("Per-UID traffic ({})", state.uid_traffic_source)).strong());
+        ui.label("Filter:");
+        ui.add(egui::TextEdit::singleline(&mut state.uid_traffic_filter)
+            .hint_text("package or UID")
+            .desired_width(180.0));
     });
     
-    if !state.forwarded_ports.is_empty() {
-        ui.separator();
-        ui.label("Active Port Forwards:");
-        for (local, remote) in &state.forwarded_ports.clone() {
-            ui.horizontal(|ui| {
-                ui.label(format!("{}:{} → {}", local, remote, remote));
-                if ui.small_button("❌").clicked() {
-                    remove_port_forward(state, local);
+    let filter = state.uid_traffic_filter.trim().to_lowercase();
+    let entries: Vec<&UidTraffic> = state.uid_traffic
+        .iter()
+        .filter(|e| filter.is_empty() || e.name.to_lowercase().contains(&filter) || e.uid.to_string().contains(&filter))
+        .collect();
+    let row_height = ui.text_style_height(&egui::TextStyle::Body) + 4.0;
+    TableBuilder::new(ui)
+        .id_salt("uid_traffic_table")
+        .striped(true)
+        .resizable(true)
+        .max_scroll_height(250.0)
+        .auto_shrink([false, true])
+        .column(Column::exact(60.0))
+        .column(Column::initial(280.0).clip(true))
+        .columns(Column::initial(95.0), 3)
+        .header(row_height, |mut header| {
+            for title in ["UID", "App", "RX", "TX", "Total"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(row_height, entries.len(), |mut row| {
+                let entry = entries[row.index()];
+                let cells = [
+                    entry.uid.to_string(),
+                    entry.name.clone(),
+                    format_bytes(entry.counters.rx_bytes),
+                    format_bytes(entry.counters.tx_bytes),
+                    format_bytes(entry.total_bytes()),
+                ];
+                for cell in cells {
+                    row.col(|ui| {
+                        ui.label(cell);
+                    });
                 }
             });
-        }
+        });
+}
+
+fn show_network_plots(ui: &mut Ui, state: &mut AdbToolsState) {
+    ui.horizontal(|ui| {
+        ui.label("Network Throughput");
+        ui.checkbox(&mut state.network_plot_packets, "Packets/s instead of KB/s");
+    });
+    let packets = state.network_plot_packets;
+    for (name, series) in state.time_series.network.iter().filter(|(_, s)| s.has_traffic()) {
+        let (rx, tx, unit) = if packets {
+            (&series.rx_packets_per_sec, &series.tx_packets_per_sec, "packets/s")
+        } else {
+            (&series.rx_kb_per_sec, &series.tx_kb_per_sec, "KB/s")
+        };
+        ui.label(format!("{} ({})", name, unit));
+        Plot::new(format!("network_plot_{}", name))
+            .height(120.0)
+            .view_aspect(3.0)
+            .include_y(0.0)
+            .legend(Legend::default())
+            .show(ui, |plot_ui| {
+                let points = |data: &VecDeque<DataPoint>| -> PlotPoints {
+                    data.iter().map(|p| [p.timestamp, p.value]).collect()
+                };
+                plot_ui.line(Line::new(points(rx)).color(egui::Color32::from_rgb(100, 200, 255)).name("RX"));
+                plot_ui.line(Line::new(points(tx)).color(egui::Color32::from_rgb(255, 150, 80)).name("TX"));
+            });
     }
 }
 
+fn show_app_plots(ui: &mut Ui, series: &ProcessSeries) {
+    let points = |data: &VecDeque<DataPoint>| -> PlotPoints {
+        data.iter().map(|p| [p.timestamp, p.value]).collect()
+    };
+    let restart_color = egui::Color32::from_rgb(230, 150, 40);
+    let restart_lines = |plot_ui: &mut egui_plot::PlotUi| {
+        for restart in &series.restarts {
+            plot_ui.vline(
+                VLine::new(*restart)
+                    .color(restart_color)
+                    .style(LineStyle::dashed_loose())
+                    .name("Restart")
+            );
+        }
+    };
+    
+    ui.label(format!("{} Memory (MB)", series.label));
+    Plot::new("app_memory_plot")
+        .height(150.0)
+        .view_aspect(3.0)
+        .include_y(0.0)
+        .legend(Legend::default())
+        .show(ui, |plot_ui| {
+            let lines = [
+                (&series.pss_mb, "PSS", egui::Color32::from_rgb(255, 100, 100)),
+                (&series.rss_mb, "RSS", egui::Color32::from_rgb(255, 180, 100)),
+                (&series.java_heap_mb, "Java Heap", egui::Color32::from_rgb(100, 200, 255)),
+                (&series.native_heap_mb, "Native Heap", egui::Color32::from_rgb(180, 120, 255)),
+            ];
+            for (data, name, color) in lines {
+                if !data.is_empty() {
+                    plot_ui.line(Line::new(points(data)).color(color).name(name));
+                }
+            }
+            restart_lines(plot_ui);
+        });
+    
+    ui.label(format!("{} CPU % (100% = one core)", series.label));
+    Plot::new("app_cpu_plot")
+        .height(150.0)
+        .view_aspect(3.0)
+        .include_y(0.0)
+        .legend(Legend::default())
+        .show(ui, |plot_ui| {
+            plot_ui.line(
+                Line::new(points(&series.cpu_percent))
+                    .color(egui::Color32::from_rgb(255, 100, 100))
+                    .name("CPU")
+            );
+            restart_lines(plot_ui);
+        });
+    
+    ui.label(format!("{} Threads / Open FDs", series.label));
+    Plot::new("app_threads_plot")
+        .height(150.0)
+        .view_aspect(3.0)
+        .include_y(0.0)
+        .legend(Legend::default())
+        .show(ui, |plot_ui| {
+            plot_ui.line(
+                Line::new(points(&series.threads))
+                    .color(egui::Color32::from_rgb(100, 255, 100))
+                    .name("Threads")
+            );
+            if !series.fds.is_empty() {
+                plot_ui.line(
+                    Line::new(points(&series.fds))
+                        .color(egui::Color32::from_rgb(100, 100, 255))
+                        .name("FDs")
+                );
+            }
+            restart_lines(plot_ui);
+        });
+}
+
 fn show_device_monitor_tab(ui: &mut Ui, state: &mut AdbToolsState) {
+    show_job_status(ui, state, AdbFunction::DeviceMonitor);
+    
     // Monitor controls
     ui.group(|ui| {
         ui.label(RichText::new("Real-time Monitoring").strong());
@@ -712,6 +3762,12 @@ fn show_device_monitor_tab(ui: &mut Ui, state: &mut AdbToolsState) {
         }
     });
     
+    show_app_tracking(ui, state);
+    
+    ui.separator();
+    
+    show_heap_dump(ui, state);
+    
     ui.separator();
     
     // Plot Section
@@ -720,11 +3776,29 @@ fn show_device_monitor_tab(ui: &mut Ui, state: &mut AdbToolsState) {
             ui.label(RichText::new("📈 Performance Trends").strong());
             
             // CPU Usage Plot
-            ui.label("CPU Load Average");
+            ui.label("CPU Usage % (total and per core)");
             Plot::new("cpu_plot")
                 .height(150.0)
                 .view_aspect(3.0)
+                .include_y(0.0)
+                .include_y(100.0)
+                .legend(Legend::default())
                 .show(ui, |plot_ui| {
+                    let core_count = state.time_series.cpu_cores.len().max(1) as f32;
+                    for (position, (core, points)) in state.time_series.cpu_cores.iter().enumerate() {
+                        let core_points: PlotPoints = points
+                            .iter()
+                            .map(|p| [p.timestamp, p.value])
+                            .collect();
+                        let hue = position as f32 / core_count;
+                        plot_ui.line(
+                            Line::new(core_points)
+                                .color(egui::ecolor::Hsva::new(hue, 0.6, 0.8, 1.0))
+                                .width(1.0)
+                                .name(format!("cpu{}", core))
+                        );
+                    }
+                    
                     let cpu_points: PlotPoints = state.time_series.cpu_usage
                         .iter()
                         .map(|p| [p.timestamp, p.value])
@@ -734,7 +3808,8 @@ fn show_device_monitor_tab(ui: &mut Ui, state: &mut AdbToolsState) {
                         plot_ui.line(
                             Line::new(cpu_points)
                                 .color(egui::Color32::from_rgb(255, 100, 100))
-                                .name("CPU Load")
+                                .width(2.5)
+                                .name("Total")
                         );
                     }
                 });
@@ -799,6 +3874,18 @@ fn show_device_monitor_tab(ui: &mut Ui, state: &mut AdbToolsState) {
                     });
             }
             
+            if !state.thermal_plot_zones.is_empty() && !state.time_series.thermal_zones.is_empty() {
+                show_thermal_plot(ui, state);
+            }
+            
+            if state.time_series.network.values().any(|s| s.has_traffic()) {
+                show_network_plots(ui, state);
+            }
+            
+            if !state.time_series.process.is_empty() {
+                show_app_plots(ui, &state.time_series.process);
+            }
+            
             ui.horizontal(|ui| {
                 if ui.button("🗑️ Clear Plot Data").clicked() {
                     clear_plot_data(state);
@@ -825,6 +3912,22 @@ fn show_device_monitor_tab(ui: &mut Ui, state: &mut AdbToolsState) {
                     ui.label(&state.cpu_usage);
                     ui.end_row();
                     
+                    if let Some(cpu) = &state.cpu_utilisation {
+                        ui.label("Per Core:");
+                        ui.horizontal_wrapped(|ui| {
+                            for (core, load) in &cpu.cores {
+                                ui.add(egui::ProgressBar::new((load.busy / 100.0) as f32)
+                                    .desired_width(110.0)
+                                    .text(format!("cpu{} {:.0}%", core, load.busy)))
+                                    .on_hover_text(format!(
+                                        "user {:.1}%, system {:.1}%, iowait {:.1}%, irq {:.1}%",
+                                        load.user, load.system, load.iowait, load.irq
+                                    ));
+                            }
+                        });
+                        ui.end_row();
+                    }
+                    
                     if !state.memory_info.is_empty() {
                         for (key, value) in &state.memory_info {
                             ui.label(key);
@@ -861,22 +3964,14 @@ fn show_device_monitor_tab(ui: &mut Ui, state: &mut AdbToolsState) {
         
         ui.separator();
         
+        if state.thermal.is_some() {
+            show_thermal(ui, state);
+            ui.separator();
+        }
+        
         // Network Statistics
-        if !state.network_stats.is_empty() {
-            ui.group(|ui| {
-                ui.label(RichText::new("🌐 Network Statistics").strong());
-                
-                Grid::new("network_stats_grid")
-                    .num_columns(2)
-                    .striped(true)
-                    .show(ui, |ui| {
-                        for (key, value) in &state.network_stats {
-                            ui.label(key);
-                            ui.label(value);
-                            ui.end_row();
-                        }
-                    });
-            });
+        if state.network_counters.is_some() {
+            show_network(ui, state);
             
             ui.separator();
         }
@@ -896,6 +3991,12 @@ fn show_device_monitor_tab(ui: &mut Ui, state: &mut AdbToolsState) {
                         kill_process(state, &pid);
                     }
                 }
+                if ui.button("🧠 Dump Heap").clicked() {
+                    if let Some(pid) = state.selected_process.clone() {
+                        state.heap_dump_target = pid;
+                        start_heap_dump(state);
+                    }
+                }
             });
             
             if !state.process_list.is_empty() {
@@ -956,524 +4057,1604 @@ fn show_device_monitor_tab(ui: &mut Ui, state: &mut AdbToolsState) {
             }
         });
     } else {
-        ui.colored_label(egui::Color32::GRAY, "Start monitoring to see real-time device statistics");
+        ui.colored_label(egui::Color32::GRAY, "Start monitoring to see real-time device statistics");
+    }
+}
+
+// ADB Command Implementation Functions
+fn refresh_devices(state: &mut AdbToolsState) {
+    if state.device_refresh_job.and_then(|id| state.jobs.job(id)).is_some_and(|job| job.is_running()) {
+        return;
+    }
+    let client = state.adb_client.clone();
+    let id = state.jobs.submit(AdbFunction::DeviceInfo, "Refresh devices", move |job| {
+        let client = job.client(&client);
+        client
+            .devices()
+            .map(AdbJobOutput::Devices)
+            .map_err(|e| format!("Failed to refresh devices: {}", e))
+    });
+    state.device_refresh_job = Some(id);
+}
+
+fn set_devices(state: &mut AdbToolsState, devices: Vec<AdbDevice>) {
+    state.devices = devices;
+
+    // Auto-connect to device if there's only one device available
+    if state.devices.len() == 1 {
+        let device_id = state.devices[0].id.clone();
+        state.selected_device = Some(device_id);
+        log::info!("Auto-connected to single device: {}", state.devices[0].id);
+    } else if state.devices.is_empty() {
+        // Clear selection if no devices are available
+        state.selected_device = None;
+    } else {
+        // Multiple devices available - check if current selection is still valid
+        if let Some(ref selected_id) = state.selected_device {
+            let device_still_exists = state.devices.iter().any(|d| d.id == *selected_id);
+            if !device_still_exists {
+                // Current selection is no longer available, clear it
+                state.selected_device = None;
+                log::info!("Previously selected device is no longer available, cleared selection");
+            }
+        }
+    }
+
+    state.last_refresh = chrono::Utc::now().format("%H:%M:%S").to_string();
+}
+
+// Wireless debugging
+/// Run a server-side wireless action, then report the device list it left behind
+fn submit_wireless_action<F>(state: &mut AdbToolsState, label: String, action: F)
+where
+    F: FnOnce(&AdbClient) -> Result<(String, Option<String>), String> + Send + 'static,
+{
+    let client = state.adb_client.clone();
+    state.jobs.submit(AdbFunction::WirelessDebugging, label, move |job| {
+        let client = job.client(&client);
+        let (message, connected) = action(&client)?;
+        Ok(AdbJobOutput::Wireless {
+            devices: client.devices().ok(),
+            connected,
+            message: Some(format!("✅ {}", message.trim())),
+        })
+    });
+}
+
+fn pair_wireless_device(state: &mut AdbToolsState) {
+    let address = state.wireless_pair_address.trim().to_string();
+    let code = state.wireless_pair_code.trim().to_string();
+    state.wireless_pair_code.clear();
+    submit_wireless_action(state, format!("Pair {}", address), move |client| {
+        pair(client, &address, &code).map(|message| (message, None))
+    });
+}
+
+fn connect_wireless_device(state: &mut AdbToolsState, address: String) {
+    let address = normalize_address(&address);
+    submit_wireless_action(state, format!("Connect {}", address), move |client| {
+        connect(client, &address).map(|message| (message, Some(address)))
+    });
+}
+
+fn disconnect_wireless_device(state: &mut AdbToolsState, address: String) {
+    submit_wireless_action(state, format!("Disconnect {}", address), move |client| {
+        disconnect(client, &address).map(|message| (message, None))
+    });
+}
+
+fn switch_to_tcpip(state: &mut AdbToolsState) {
+    let Some(device_id) = state.selected_device.clone() else {
+        return;
+    };
+    let port = match state.wireless_tcpip_port.trim().parse::<u16>() {
+        Ok(port) if port > 0 => port,
+        _ => {
+            state.wireless_result = format!("Invalid port: {}", state.wireless_tcpip_port);
+            return;
+        }
+    };
+    submit_wireless_action(state, format!("tcpip {} on {}", port, device_id), move |client| {
+        let address = enable_tcpip(client, &device_id, port)?;
+        Ok((format!("{} is now reachable at {}", device_id, address), Some(address)))
+    });
+}
+
+fn discover_mdns_services(state: &mut AdbToolsState) {
+    let client = state.adb_client.clone();
+    state.jobs.submit(AdbFunction::WirelessDebugging, "Discover mDNS services", move |job| {
+        let client = job.client(&client);
+        mdns_services(&client).map(AdbJobOutput::MdnsServices)
+    });
+}
+
+/// Periodically bring dropped auto-reconnect endpoints back, refreshing the device list as it goes
+fn check_wireless_endpoints(state: &mut AdbToolsState) {
+    let checking = state.wireless_check_job.and_then(|id| state.jobs.job(id)).is_some_and(|job| job.is_running());
+    if checking || state.jobs.is_running(AdbFunction::WirelessDebugging) {
+        return;
+    }
+    let due = state
+        .wireless_last_check
+        .is_none_or(|last| last.elapsed() >= RECONNECT_INTERVAL);
+    if !due || !state.wireless_endpoints.iter().any(|e| e.auto_reconnect) {
+        return;
+    }
+    state.wireless_last_check = Some(Instant::now());
+
+    let client = state.adb_client.clone();
+    let endpoints = state.wireless_endpoints.clone();
+    let id = state.jobs.submit_background(AdbFunction::WirelessDebugging, "Check wireless devices", move |job| {
+        let client = job.client(&client);
+        let devices = client.devices().map_err(|e| e.to_string())?;
+        let reconnected = reconnect_dropped(&client, &endpoints, &devices);
+        if reconnected.is_empty() {
+            return Ok(AdbJobOutput::Wireless { devices: Some(devices), connected: None, message: None });
+        }
+        job.log(format!("Reconnected {}", reconnected.join(", ")));
+        Ok(AdbJobOutput::Wireless {
+            devices: client.devices().ok(),
+            connected: None,
+            message: Some(format!("🔄 Reconnected {}", reconnected.join(", "))),
+        })
+    });
+    state.wireless_check_job = Some(id);
+}
+
+fn get_device_info(state: &mut AdbToolsState) {
+    if let Some(device_id) = state.selected_device.clone() {
+        state.device_info.clear();
+        let client = state.adb_client.clone();
+        
+        state.jobs.submit(AdbFunction::DeviceInfo, "Get device info", move |job| {
+            let client = job.client(&client);
+            // Get various device properties
+            let properties = [
+                ("Model", "ro.product.model"),
+                ("Brand", "ro.product.brand"),
+                ("Manufacturer", "ro.product.manufacturer"),
+                ("Android Version", "ro.build.version.release"),
+                ("API Level", "ro.build.version.sdk"),
+                ("Build ID", "ro.build.id"),
+                ("Serial", "ro.serialno"),
+                ("ABI", "ro.product.cpu.abi"),
+                ("Fingerprint", "ro.build.fingerprint"),
+            ];
+            
+            let mut info = HashMap::new();
+            for (index, (key, prop)) in properties.iter().enumerate() {
+                if job.is_cancelled() {
+                    break;
+                }
+                if let Ok(value) = client.shell(&device_id, &format!("getprop {}", prop)) {
+                    info.insert(key.to_string(), value.trim().to_string());
+                }
+                job.set_progress((index + 1) as f32 / properties.len() as f32);
+            }
+            Ok(AdbJobOutput::DeviceInfo(info))
+        });
+    }
+}
+
+fn get_battery_info(state: &mut AdbToolsState) {
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        state.jobs.submit(AdbFunction::DeviceInfo, "Get battery info", move |job| {
+            let client = job.client(&client);
+            let mut info = HashMap::new();
+            if let Ok(output) = client.shell(&device_id, "dumpsys battery") {
+                info.insert("Battery Info".to_string(), output);
+            }
+            Ok(AdbJobOutput::DeviceInfo(info))
+        });
+    }
+}
+
+fn get_display_info(state: &mut AdbToolsState) {
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        state.jobs.submit(AdbFunction::DeviceInfo, "Get display info", move |job| {
+            let client = job.client(&client);
+            let mut info = HashMap::new();
+            if let Ok(output) = client.shell(&device_id, "wm size") {
+                info.insert("Display Size".to_string(), output.trim().to_string());
+            }
+            if let Ok(output) = client.shell(&device_id, "wm density") {
+                info.insert("Display Density".to_string(), output.trim().to_string());
+            }
+            Ok(AdbJobOutput::DeviceInfo(info))
+        });
+    }
+}
+
+fn list_packages(state: &mut AdbToolsState) {
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        let package_filter = state.package_filter.clone();
+        let list_filter = state.package_list_filter;
+        state.jobs.submit(AdbFunction::AppManagement, "List packages", move |job| {
+            let mut cmd = "pm list packages".to_string();
+            if let Some(flag) = list_filter.flag() {
+                cmd.push_str(&format!(" {}", flag));
+            }
+            if !package_filter.is_empty() {
+                cmd.push_str(&format!(" {}", shell_quote(&package_filter)));
+            }
+            
+            let output = job.client(&client).shell(&device_id, &cmd).map_err(|e| e.to_string())?;
+            Ok(AdbJobOutput::Packages(
+                output
+                    .lines()
+                    .map(|line| line.trim().replace("package:", ""))
+                    .filter(|line| !line.is_empty())
+                    .collect(),
+            ))
+        });
+    }
+}
+
+fn inspect_apk_file(state: &mut AdbToolsState) {
+    let apk_path = state.apk_path.trim().to_string();
+    if apk_path.is_empty() {
+        return;
+    }
+    state.apk_info = None;
+    state.apk_inspected = false;
+    state.apk_splits.clear();
+    let device = state.selected_device.clone();
+    let client = state.adb_client.clone();
+    state.jobs.submit(AdbFunction::AppManagement, format!("Inspect {}", apk_path), move |job| {
+        let client = job.client(&client);
+        let path = PathBuf::from(&apk_path);
+        // Inspection is advisory: an undecodable manifest still leaves the APK installable
+        if !is_split_set(&path) {
+            let (info, message) = match inspect_apk(&path) {
+                Ok(info) => (Some(info), format!("Inspected {}", apk_path)),
+                Err(e) => (None, format!("⚠️ Could not decode the manifest ({}); Install APK installs it anyway", e)),
+            };
+            return Ok(AdbJobOutput::ApkInspected { path: apk_path, info, splits: Vec::new(), message });
+        }
+        
+        let (mut splits, info) = load_apk_splits(&path)?;
+        let (info, warning) = match info {
+            Ok(info) => (info, String::new()),
+            Err(e) => (None, format!("\n⚠️ Could not decode the base manifest ({})", e)),
+        };
+        let message = match device {
+            Some(device_id) => {
+                job.log("Querying device ABI, density and locales");
+                match query_device_split_config(&client, &device_id) {
+                    Ok(config) => {
+                        select_splits(&mut splits, &config);
+                        format!("Selected splits for {}", config.summary())
+                    }
+                    Err(e) => format!("⚠️ Could not query the device configuration ({}); select the splits manually", e),
+                }
+            }
+            None => "No device selected; select the configuration splits manually".to_string(),
+        };
+        Ok(AdbJobOutput::ApkInspected { path: apk_path, info, splits, message: message + &warning })
+    });
+}
+
+fn install_apk(state: &mut AdbToolsState) {
+    if let Some(device_id) = state.selected_device.clone() {
+        if !state.apk_path.is_empty() {
+            let client = state.adb_client.clone();
+            let apk_path = state.apk_path.clone();
+            let options = state.install_options.clone();
+            state.jobs.submit(AdbFunction::AppManagement, format!("Install {}", apk_path), move |job| {
+                job.log(format!("Installing {} ...", apk_path));
+                match install_single_apk(job, &client, &device_id, Path::new(apk_path.trim()), &options) {
+                    Ok(report) => Ok(AdbJobOutput::Text(format!("Install result:\n{}", report))),
+                    Err(report) => Err(format!("Install failed:\n{}", report)),
+                }
+            });
+        }
+    }
+}
+
+fn install_apk_splits(state: &mut AdbToolsState) {
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        let source = PathBuf::from(state.apk_path.trim());
+        let splits = state.apk_splits.clone();
+        let options = state.install_options.clone();
+        let count = splits.iter().filter(|s| s.selected).count();
+        state.jobs.submit(AdbFunction::AppManagement, format!("Install {} split(s)", count), move |job| {
+            install_splits(job, &client, &device_id, &source, &splits, &options)
+                .map(|report| AdbJobOutput::Text(format!("Install result:\n{}", report)))
+                .map_err(|report| format!("Install failed:\n{}", report))
+        });
+    }
+}
+
+fn run_package_batch(state: &mut AdbToolsState, action: PackageAction) {
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        let mut packages: Vec<String> = state.package_selection.iter().cloned().collect();
+        packages.sort();
+        let apk_dir = PathBuf::from(state.apk_pull_dir.trim());
+        state.package_batch_results.clear();
+        
+        state.jobs.submit(AdbFunction::AppManagement, format!("{} {} package(s)", action.name(), packages.len()), move |job| {
+            let client = job.client(&client);
+            let mut results = Vec::new();
+            for (index, package) in packages.iter().enumerate() {
+                if job.is_cancelled() {
+                    break;
+                }
+                job.log(format!("{} {}", action.name(), package));
+                let result = run_package_action(&client, &device_id, package, action, &apk_dir);
+                results.push(PackageActionResult {
+                    package: package.clone(),
+                    action,
+                    success: result.is_ok(),
+                    message: result.unwrap_or_else(|e| e),
+                });
+                job.set_progress((index + 1) as f32 / packages.len() as f32);
+            }
+            Ok(AdbJobOutput::PackageBatch { results, refresh: action.changes_package_list() })
+        });
+    }
+}
+
+fn backup_selected_package(state: &mut AdbToolsState) {
+    if let (Some(device_id), Some(package)) = (state.selected_device.clone(), state.selected_package.clone()) {
+        let client = state.adb_client.clone();
+        let method = state.app_backup_options.method;
+        let backup_dir = PathBuf::from(state.app_backup_options.backup_dir.trim());
+        state.jobs.submit(AdbFunction::AppManagement, format!("Back up {}", package), move |job| {
+            let archive = backup_app_data(job, &client, &device_id, &package, method, &backup_dir)?;
+            let size = std::fs::metadata(&archive).map(|m| m.len()).unwrap_or(0);
+            Ok(AdbJobOutput::Text(format!("✅ Backed up {} to {} ({})", package, archive.display(), format_bytes(size))))
+        });
+    }
+}
+
+fn restore_package_data(state: &mut AdbToolsState) {
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        let method = state.app_backup_options.method;
+        let archive = PathBuf::from(state.app_backup_options.restore_path.trim());
+        state.jobs.submit(AdbFunction::AppManagement, format!("Restore {}", archive.display()), move |job| {
+            restore_app_data(job, &client, &device_id, &archive, method).map(AdbJobOutput::Text)
+        });
+    }
+}
+
+fn load_package_details(state: &mut AdbToolsState, package: &str) {
+    if state.selected_package.as_deref() != Some(package) {
+        state.package_details = None;
+        state.package_app_ops.clear();
+    }
+    state.selected_package = Some(package.to_string());
+    submit_package_action(state, format!("Details of {}", package), |_, _, _| Ok(None));
+}
+
+/// Run an action on the selected package, then reload its details
+fn submit_package_action<F>(state: &mut AdbToolsState, label: String, action: F)
+where
+    F: FnOnce(&AdbClient, &str, &str) -> Result<Option<String>, String> + Send + 'static,
+{
+    if let (Some(device_id), Some(package)) = (state.selected_device.clone(), state.selected_package.clone()) {
+        let client = state.adb_client.clone();
+        state.jobs.submit(AdbFunction::AppManagement, label, move |job| {
+            let client = job.client(&client);
+            let message = action(&client, &device_id, &package)?;
+            let (details, app_ops) = fetch_package_details(&client, &device_id, &package)?;
+            Ok(AdbJobOutput::PackageDetails { details, app_ops, message })
+        });
+    }
+}
+
+fn set_runtime_permission(state: &mut AdbToolsState, permission: &str, grant: bool) {
+    let user = state.package_details.as_ref()
+        .and_then(|d| d.user_id)
+        .map(|id| format!("--user {} ", id))
+        .unwrap_or_default();
+    // The displayed grant only changes once the reload reports what the device actually did
+    let verb = if grant { "grant" } else { "revoke" };
+    let permission = permission.to_string();
+    submit_package_action(state, format!("{} {}", verb, permission), move |client, serial, package| {
+        client.shell(serial, &format!("pm {} {}{} {}", verb, user, shell_quote(package), shell_quote(&permission)))
+            .map_err(|e| format!("pm {} failed: {}", verb, e))?;
+        Ok(Some(format!("✅ {} {}", if grant { "Granted" } else { "Revoked" }, permission)))
+    });
+}
+
+fn set_app_op(state: &mut AdbToolsState, op: &str, mode: &str, uid_mode: bool) {
+    let scope = if uid_mode { "--uid " } else { "" };
+    let (op, mode) = (op.to_string(), mode.to_string());
+    submit_package_action(state, format!("appops {} {}", op, mode), move |client, serial, package| {
+        client.shell(serial, &format!("appops set {}{} {} {}", scope, shell_quote(package), shell_quote(&op), mode))
+            .map_err(|e| format!("appops set failed: {}", e))?;
+        Ok(Some(format!("✅ Set {} to {}", op, mode)))
+    });
+}
+
+fn uninstall_package(state: &mut AdbToolsState, package: &str) {
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        let package = package.to_string();
+        state.jobs.submit(AdbFunction::AppManagement, format!("Uninstall {}", package), move |job| {
+            match job.client(&client).shell(&device_id, &format!("pm uninstall {}", shell_quote(&package))) {
+                Ok(output) => Ok(AdbJobOutput::PackageUninstalled(format!("Uninstall result: {}", output))),
+                Err(e) => Err(format!("Uninstall failed: {}", e)),
+            }
+        });
+    }
+}
+
+fn push_file(state: &mut AdbToolsState) {
+    if let Some(device_id) = state.selected_device.clone() {
+        if !state.local_path.is_empty() && !state.remote_path.is_empty() {
+            let client = state.adb_client.clone();
+            let local_path = state.local_path.clone();
+            let remote_path = state.remote_path.clone();
+            state.jobs.submit(AdbFunction::FileOperations, format!("Push {}", local_path), move |job| {
+                let client = job.client(&client);
+                match client.push(&device_id, Path::new(&local_path), &remote_path) {
+                    Ok(stats) => Ok(AdbJobOutput::Text(format!("Push successful: {} file(s), {}", stats.files, format_bytes(stats.bytes)))),
+                    Err(e) => Err(format!("Push failed: {}", e)),
+                }
+            });
+        }
+    }
+}
+
+fn pull_file(state: &mut AdbToolsState) {
+    if let Some(device_id) = state.selected_device.clone() {
+        if !state.local_path.is_empty() && !state.remote_path.is_empty() {
+            let client = state.adb_client.clone();
+            let local_path = state.local_path.clone();
+            let remote_path = state.remote_path.clone();
+            state.jobs.submit(AdbFunction::FileOperations, format!("Pull {}", remote_path), move |job| {
+                let client = job.client(&client);
+                match client.pull(&device_id, &remote_path, Path::new(&local_path)) {
+                    Ok(stats) => Ok(AdbJobOutput::Text(format!("Pull successful: {} file(s), {}", stats.files, format_bytes(stats.bytes)))),
+                    Err(e) => Err(format!("Pull failed: {}", e)),
+                }
+            });
+        }
+    }
+}
+
+/// List `path` (or the current folder) in the remote browser, recording history when moving
+fn browse_remote(state: &mut AdbToolsState, path: Option<String>) {
+    if let Some(path) = path {
+        state.remote_navigation.navigate(&mut state.remote_path, normalize_remote_dir(&path));
+    }
+    let path = normalize_remote_dir(&state.remote_path);
+    state.remote_listed_path = Some(path.clone());
+    state.remote_selection.clear();
+    state.remote_confirm_delete = false;
+    
+    submit_remote_file_op(state, format!("List {}", path), |_, _, _| Ok(None));
+}
+
+fn list_remote_entries(client: &AdbClient, serial: &str, dir: &str) -> Result<Vec<RemoteFileEntry>, String> {
+    // The trailing slash makes `ls` follow a symlinked folder such as /sdcard
+    let target = if dir.ends_with('/') { dir.to_string() } else { format!("{}/", dir) };
+    let output = client.shell_v2(serial, &format!("ls -la {}", shell_quote(&target)))
+        .map_err(|e| format!("List failed: {}", e))?;
+    
+    // Unreadable entries make ls exit non-zero while still listing the rest
+    let stdout = output.stdout_string();
+    if stdout.trim().is_empty() && !output.success() {
+        return Err(format!("List failed: {}", output.stderr_string().trim()));
+    }
+    
+    let mut entries = parse_ls_la(&stdout);
+    entries.sort_by(|a, b| {
+        b.is_browsable().cmp(&a.is_browsable()).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    Ok(entries)
+}
+
+/// Run an operation in the current remote folder, then re-list it
+fn submit_remote_file_op<F>(state: &mut AdbToolsState, label: String, operation: F)
+where
+    F: FnOnce(&AdbClient, &str, &AdbJobContext) -> Result<Option<String>, String> + Send + 'static,
+{
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        let path = normalize_remote_dir(&state.remote_path);
+        state.jobs.submit(AdbFunction::FileOperations, label, move |job| {
+            let client = job.client(&client);
+            let message = operation(&client, &device_id, job)?;
+            let entries = list_remote_entries(&client, &device_id, &path)?;
+            Ok(AdbJobOutput::RemoteListing { path, entries, message })
+        });
+    }
+}
+
+/// Full remote paths of the selected entries, shell quoted
+fn quoted_remote_selection(state: &AdbToolsState) -> Vec<String> {
+    let mut names: Vec<&String> = state.remote_selection.iter().collect();
+    names.sort();
+    names.into_iter()
+        .map(|name| shell_quote(&remote_join(&state.remote_path, name)))
+        .collect()
+}
+
+fn pull_remote_selection(state: &mut AdbToolsState) {
+    if state.local_path.trim().is_empty() {
+        state.file_operation_result = "❌ Set the Local Path to the folder to pull into".to_string();
+        return;
+    }
+    let local_dir = PathBuf::from(state.local_path.trim());
+    let remote_paths: Vec<String> = state.remote_selection.iter()
+        .map(|name| remote_join(&state.remote_path, name))
+        .collect();
+    
+    submit_remote_file_op(state, format!("Pull {} item(s)", remote_paths.len()), move |client, serial, job| {
+        std::fs::create_dir_all(&local_dir).map_err(|e| format!("Failed to create {}: {}", local_dir.display(), e))?;
+        let mut files = 0;
+        let mut bytes = 0;
+        for (i, remote) in remote_paths.iter().enumerate() {
+            if job.is_cancelled() {
+                return Err("Cancelled".to_string());
+            }
+            job.log(format!("Pulling {}", remote));
+            let stats = client.pull(serial, remote, &local_dir).map_err(|e| format!("Pull of {} failed: {}", remote, e))?;
+            files += stats.files;
+            bytes += stats.bytes;
+            job.set_progress((i + 1) as f32 / remote_paths.len() as f32);
+        }
+        Ok(Some(format!("✅ Pulled {} file(s), {} into {}", files, format_bytes(bytes), local_dir.display())))
+    });
+}
+
+fn push_into_remote_folder(state: &mut AdbToolsState) {
+    if state.local_path.trim().is_empty() {
+        state.file_operation_result = "❌ Set the Local Path to the file or folder to push".to_string();
+        return;
+    }
+    let local = PathBuf::from(state.local_path.trim());
+    let remote_dir = normalize_remote_dir(&state.remote_path);
+    
+    submit_remote_file_op(state, format!("Push {}", local.display()), move |client, serial, _| {
+        let stats = client.push(serial, &local, &remote_dir).map_err(|e| format!("Push failed: {}", e))?;
+        Ok(Some(format!("✅ Pushed {} file(s), {} into {}", stats.files, format_bytes(stats.bytes), remote_dir)))
+    });
+}
+
+fn delete_remote_selection(state: &mut AdbToolsState) {
+    let targets = quoted_remote_selection(state);
+    if targets.is_empty() {
+        return;
+    }
+    let command = format!("rm -rf {}", targets.join(" "));
+    let count = targets.len();
+    submit_remote_file_op(state, format!("Delete {} item(s)", count), move |client, serial, _| {
+        client.shell(serial, &command).map_err(|e| format!("Delete failed: {}", e))?;
+        Ok(Some(format!("✅ Deleted {} item(s)", count)))
+    });
+}
+
+fn rename_remote_selection(state: &mut AdbToolsState) {
+    let Some(name) = state.remote_selection.iter().next().cloned() else {
+        return;
+    };
+    let new_name = state.remote_rename_to.trim().to_string();
+    let from = remote_join(&state.remote_path, &name);
+    let to = remote_join(&state.remote_path, &new_name);
+    state.remote_rename_to.clear();
+    
+    submit_remote_file_op(state, format!("Rename {}", name), move |client, serial, _| {
+        client.shell(serial, &format!("mv {} {}", shell_quote(&from), shell_quote(&to)))
+            .map_err(|e| format!("Rename failed: {}", e))?;
+        Ok(Some(format!("✅ Renamed {} to {}", name, new_name)))
+    });
+}
+
+fn make_remote_folder(state: &mut AdbToolsState) {
+    let name = state.remote_new_folder.trim().to_string();
+    let path = remote_join(&state.remote_path, &name);
+    state.remote_new_folder.clear();
+    
+    submit_remote_file_op(state, format!("Create {}", name), move |client, serial, _| {
+        client.shell(serial, &format!("mkdir -p {}", shell_quote(&path)))
+            .map_err(|e| format!("Create folder failed: {}", e))?;
+        Ok(Some(format!("✅ Created {}", path)))
+    });
+}
+
+fn chmod_remote_selection(state: &mut AdbToolsState) {
+    let mode = state.remote_chmod_mode.trim().to_string();
+    let targets = quoted_remote_selection(state);
+    let command = format!("chmod {} {}", shell_quote(&mode), targets.join(" "));
+    
+    submit_remote_file_op(state, format!("chmod {}", mode), move |client, serial, _| {
+        client.shell(serial, &command).map_err(|e| format!("chmod failed: {}", e))?;
+        Ok(Some(format!("✅ Changed mode of {} item(s) to {}", targets.len(), mode)))
+    });
+}
+
+fn compare_sync_folders(state: &mut AdbToolsState) {
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        let options = SyncOptions {
+            local_dir: state.sync_options.local_dir.trim().to_string(),
+            remote_dir: normalize_remote_dir(&state.sync_options.remote_dir),
+            ..state.sync_options.clone()
+        };
+        state.sync_plan = None;
+        state.jobs.submit(AdbFunction::FileOperations, "Compare folders", move |job| {
+            let client = job.client(&client);
+            plan_sync(job, &client, &device_id, &options).map(AdbJobOutput::SyncPlanned)
+        });
+    }
+}
+
+fn apply_sync_plan(state: &mut AdbToolsState) {
+    let Some(plan) = state.sync_plan.clone() else {
+        return;
+    };
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        let bytes = plan.transfer_bytes();
+        state.jobs.submit(AdbFunction::FileOperations, "Sync folders", move |job| {
+            let client = job.client(&client);
+            let summary = apply_sync(job, &client, &device_id, &plan)?;
+            Ok(AdbJobOutput::SyncApplied(format!("✅ {} ({})", summary, format_bytes(bytes))))
+        });
+    }
+}
+
+fn execute_shell_command(state: &mut AdbToolsState) {
+    if let Some(device_id) = state.selected_device.clone() {
+        if !state.shell_command.is_empty() {
+            let client = state.adb_client.clone();
+            let command = state.shell_command.clone();
+            push_history(&mut state.shell_history, &command);
+            state.jobs.submit(AdbFunction::ShellCommands, command.clone(), move |job| {
+                match job.client(&client).shell(&device_id, &command) {
+                    Ok(output) => Ok(AdbJobOutput::Text(output)),
+                    Err(e) => Err(format!("Command failed: {}", e)),
+                }
+            });
+        }
+    }
+}
+
+fn execute_broadcast_command(state: &mut AdbToolsState) {
+    let command = state.shell_command.trim().to_string();
+    let targets: Vec<(String, String)> = state.devices.iter()
+        .filter(|d| state.shell_broadcast_targets.contains(&d.id))
+        .map(|d| (d.id.clone(), d.model.clone()))
+        .collect();
+    if command.is_empty() || targets.is_empty() {
+        state.shell_output = "Pick at least one device and enter a command".to_string();
+        return;
+    }
+
+    push_history(&mut state.shell_history, &command);
+    let client = state.adb_client.clone();
+    let label = format!("{} (on {} devices)", command, targets.len());
+    state.jobs.submit(AdbFunction::ShellCommands, label, move |job| {
+        let client = job.client(&client);
+        run_broadcast(job, &client, targets, &command).map(AdbJobOutput::Broadcast)
+    });
+}
+
+/// Run a snippet or macro, asking for placeholder values first when it has any
+fn run_snippet(state: &mut AdbToolsState, target: PromptTarget) {
+    let names = match &target {
+        PromptTarget::Snippet(snippet) => placeholders(&snippet.command),
+        PromptTarget::Macro(shell_macro) => shell_macro.placeholders(),
+    };
+    let values = names.into_iter()
+        .map(|name| {
+            let value = state.snippet_values.get(&name).cloned().unwrap_or_default();
+            (name, value)
+        })
+        .collect::<Vec<_>>();
+    let prompt = SnippetPrompt { target, values };
+    if prompt.values.is_empty() {
+        run_snippet_with(state, prompt, false);
+    } else {
+        state.snippet_prompt = Some(prompt);
     }
 }
 
-// ADB Command Implementation Functions
-fn refresh_devices(state: &mut AdbToolsState) {
-    match execute_adb_command(&["devices", "-l"]) {
-        Ok(output) => {
-            state.devices.clear();
-            for line in output.lines().skip(1) {
-                if line.trim().is_empty() || line.contains("List of devices") {
-                    continue;
-                }
-                
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 2 {
-                    let device = AdbDevice {
-                        id: parts[0].to_string(),
-                        status: parts[1].to_string(),
-                        model: extract_device_property(line, "model:"),
-                        product: extract_device_property(line, "product:"),
-                        transport_id: extract_device_property(line, "transport_id:"),
-                    };
-                    state.devices.push(device);
+fn run_snippet_with(state: &mut AdbToolsState, prompt: SnippetPrompt, insert_only: bool) {
+    let values = prompt.values();
+    match &prompt.target {
+        PromptTarget::Snippet(snippet) => match fill_placeholders(&snippet.command, &values) {
+            Ok(command) => {
+                state.shell_command = command;
+                if insert_only {
+                    return;
                 }
-            }
-            
-            // Auto-connect to device if there's only one device available
-            if state.devices.len() == 1 {
-                let device_id = state.devices[0].id.clone();
-                state.selected_device = Some(device_id);
-                log::info!("Auto-connected to single device: {}", state.devices[0].id);
-            } else if state.devices.is_empty() {
-                // Clear selection if no devices are available
-                state.selected_device = None;
-            } else {
-                // Multiple devices available - check if current selection is still valid
-                if let Some(ref selected_id) = state.selected_device {
-                    let device_still_exists = state.devices.iter().any(|d| d.id == *selected_id);
-                    if !device_still_exists {
-                        // Current selection is no longer available, clear it
-                        state.selected_device = None;
-                        log::info!("Previously selected device is no longer available, cleared selection");
-                    }
+                if state.shell_broadcast {
+                    execute_broadcast_command(state);
+                } else {
+                    execute_shell_command(state);
                 }
             }
-            
-            state.last_refresh = chrono::Utc::now().format("%H:%M:%S").to_string();
-        }
-        Err(e) => {
-            log::error!("Failed to refresh devices: {}", e);
-        }
+            Err(e) => state.snippet_result = e,
+        },
+        PromptTarget::Macro(shell_macro) => match shell_macro.fill(&values) {
+            Ok(steps) => execute_macro(state, &shell_macro.name, steps),
+            Err(e) => state.snippet_result = e,
+        },
     }
 }
 
-fn get_device_info(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        state.device_info.clear();
-        
-        // Get various device properties
-        let properties = [
-            ("Model", "ro.product.model"),
-            ("Brand", "ro.product.brand"),
-            ("Manufacturer", "ro.product.manufacturer"),
-            ("Android Version", "ro.build.version.release"),
-            ("API Level", "ro.build.version.sdk"),
-            ("Build ID", "ro.build.id"),
-            ("Serial", "ro.serialno"),
-            ("ABI", "ro.product.cpu.abi"),
-            ("Fingerprint", "ro.build.fingerprint"),
-        ];
-        
-        for (key, prop) in properties {
-            if let Ok(value) = execute_adb_command(&["-s", device_id, "shell", "getprop", prop]) {
-                state.device_info.insert(key.to_string(), value.trim().to_string());
-            }
+fn execute_macro(state: &mut AdbToolsState, name: &str, steps: Vec<MacroStep>) {
+    let Some(device_id) = state.selected_device.clone() else {
+        state.snippet_result = "Select a device to run the macro on".to_string();
+        return;
+    };
+    let client = state.adb_client.clone();
+    state.jobs.submit(AdbFunction::ShellCommands, format!("Macro: {}", name), move |job| {
+        let client = job.client(&client);
+        run_macro(job, &client, &device_id, &steps).map(AdbJobOutput::Text)
+    });
+}
+
+fn import_snippets(state: &mut AdbToolsState) {
+    let path = PathBuf::from(state.snippet_file_path.trim());
+    state.snippet_result = match SnippetLibrary::import(&path) {
+        Ok(library) => {
+            let count = state.snippet_library.merge(library);
+            format!("Imported {} entries from {}", count, path.display())
         }
-    }
+        Err(e) => e,
+    };
 }
 
-fn get_battery_info(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        if let Ok(output) = execute_adb_command(&["-s", device_id, "shell", "dumpsys", "battery"]) {
-            state.device_info.insert("Battery Info".to_string(), output);
+fn export_snippets(state: &mut AdbToolsState) {
+    let path = PathBuf::from(state.snippet_file_path.trim());
+    state.snippet_result = match state.snippet_library.export(&path) {
+        Ok(()) => format!(
+            "Exported {} snippets and {} macros to {}",
+            state.snippet_library.snippets.len(),
+            state.snippet_library.macros.len(),
+            path.display()
+        ),
+        Err(e) => e,
+    };
+}
+
+fn open_terminal(state: &mut AdbToolsState, device_id: &str, ctx: &egui::Context) {
+    match TerminalSession::open(&state.adb_client, device_id, DEFAULT_ROWS, DEFAULT_COLS, Some(ctx.clone())) {
+        Ok(session) => {
+            state.terminal_sessions.insert(device_id.to_string(), session);
         }
+        Err(e) => state.shell_output = e,
     }
 }
 
-fn get_display_info(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        if let Ok(output) = execute_adb_command(&["-s", device_id, "shell", "wm", "size"]) {
-            state.device_info.insert("Display Size".to_string(), output.trim().to_string());
-        }
-        if let Ok(output) = execute_adb_command(&["-s", device_id, "shell", "wm", "density"]) {
-            state.device_info.insert("Display Density".to_string(), output.trim().to_string());
-        }
+fn close_terminal(state: &mut AdbToolsState, device_id: &str) {
+    if let Some(session) = state.terminal_sessions.get_mut(device_id) {
+        session.close();
     }
 }
 
-fn list_packages(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let mut cmd = vec!["-s", device_id, "shell", "pm", "list", "packages"];
-        if !state.package_filter.is_empty() {
-            cmd.push(&state.package_filter);
-        }
-        
-        if let Ok(output) = execute_adb_command(&cmd) {
-            state.installed_packages = output
-                .lines()
-                .map(|line| line.replace("package:", ""))
-                .collect();
+fn start_logcat(state: &mut AdbToolsState, ctx: &egui::Context) {
+    if let Some(device_id) = state.selected_device.clone() {
+        let capacity = state.logcat_buffer_lines;
+        let session = state.logcat_sessions
+            .entry(device_id.clone())
+            .or_insert_with(|| LogcatSession::new(&device_id, capacity));
+        if let Err(e) = session.set_filter(&state.logcat_filters) {
+            state.logcat_filter_error = Some(e);
         }
+        session.start(&state.adb_client, Vec::new(), Some(ctx.clone()));
+        resolve_logcat_package(state);
     }
 }
 
-fn install_apk(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        if !state.apk_path.is_empty() {
-            match execute_adb_command(&["-s", device_id, "install", &state.apk_path]) {
-                Ok(output) => {
-                    state.file_operation_result = format!("Install result: {}", output);
-                }
-                Err(e) => {
-                    state.file_operation_result = format!("Install failed: {}", e);
-                }
-            }
-        }
+/// Look up the PIDs of the filtered package so entries can be matched by process
+fn resolve_logcat_package(state: &mut AdbToolsState) {
+    let package = state.logcat_filters.package.trim().to_string();
+    if package.is_empty() {
+        return;
+    }
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        state.jobs.submit(AdbFunction::Logcat, format!("Resolve PIDs of {}", package), move |job| {
+            let client = job.client(&client);
+            // pidof exits non-zero when the app isn't running, which just means no PIDs yet
+            let output = client.shell_v2(&device_id, &format!("pidof {}", shell_quote(&package)))
+                .map_err(|e| format!("Failed to resolve package: {}", e))?;
+            let pids = output.stdout_string()
+                .split_whitespace()
+                .filter_map(|pid| pid.parse().ok())
+                .collect();
+            Ok(AdbJobOutput::LogcatPids { serial: device_id, package, pids })
+        });
     }
 }
 
-fn uninstall_package(state: &mut AdbToolsState, package: &str) {
-    if let Some(device_id) = &state.selected_device {
-        match execute_adb_command(&["-s", device_id, "uninstall", package]) {
-            Ok(output) => {
-                state.file_operation_result = format!("Uninstall result: {}", output);
-                list_packages(state); // Refresh package list
-            }
-            Err(e) => {
-                state.file_operation_result = format!("Uninstall failed: {}", e);
-            }
-        }
+fn import_logcat_file(state: &mut AdbToolsState) {
+    let path = state.logcat_import_path.trim().to_string();
+    if path.is_empty() {
+        state.logcat_file_result = "❌ Please enter a log file path".to_string();
+        return;
     }
+    
+    state.jobs.submit(AdbFunction::Logcat, format!("Open {}", path), move |job| {
+        let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        job.set_progress(0.5);
+        let entries = parse_logcat_text(&String::from_utf8_lossy(&bytes));
+        let name = Path::new(&path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or(path);
+        Ok(AdbJobOutput::LogcatFile { name, entries })
+    });
 }
 
-fn push_file(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        if !state.local_path.is_empty() && !state.remote_path.is_empty() {
-            match execute_adb_command(&["-s", device_id, "push", &state.local_path, &state.remote_path]) {
-                Ok(output) => {
-                    state.file_operation_result = format!("Push successful: {}", output);
-                }
-                Err(e) => {
-                    state.file_operation_result = format!("Push failed: {}", e);
-                }
-            }
-        }
+fn export_logcat_file(state: &mut AdbToolsState) {
+    let path = state.logcat_export_path.trim().to_string();
+    if path.is_empty() {
+        state.logcat_file_result = "❌ Please enter a path to save to".to_string();
+        return;
     }
+    
+    let filtered_only = state.logcat_export_filtered;
+    let Some(session) = current_logcat_session(state) else {
+        state.logcat_file_result = "❌ No logcat lines to save".to_string();
+        return;
+    };
+    let text = session.export_text(filtered_only);
+    let line_count = text.lines().count();
+    
+    state.jobs.submit(AdbFunction::Logcat, format!("Save logcat to {}", path), move |_job| {
+        std::fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        Ok(AdbJobOutput::Text(format!("✅ Saved {} lines to {}", line_count, path)))
+    });
 }
 
-fn pull_file(state: &mut AdbToolsState) {
+fn stop_logcat(state: &mut AdbToolsState) {
     if let Some(device_id) = &state.selected_device {
-        if !state.local_path.is_empty() && !state.remote_path.is_empty() {
-            match execute_adb_command(&["-s", device_id, "pull", &state.remote_path, &state.local_path]) {
-                Ok(output) => {
-                    state.file_operation_result = format!("Pull successful: {}", output);
-                }
-                Err(e) => {
-                    state.file_operation_result = format!("Pull failed: {}", e);
-                }
-            }
+        if let Some(session) = state.logcat_sessions.get_mut(device_id) {
+            session.stop();
         }
     }
 }
 
-fn list_remote_directory(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        if !state.remote_path.is_empty() {
-            match execute_adb_command(&["-s", device_id, "shell", "ls", "-la", &state.remote_path]) {
-                Ok(output) => {
-                    state.file_operation_result = output;
-                }
-                Err(e) => {
-                    state.file_operation_result = format!("List failed: {}", e);
-                }
-            }
+fn clear_logcat(state: &mut AdbToolsState) {
+    if let Some(device_id) = state.selected_device.clone() {
+        if let Some(session) = state.logcat_sessions.get_mut(&device_id) {
+            session.clear();
         }
+        let client = state.adb_client.clone();
+        state.jobs.submit(AdbFunction::Logcat, "Clear logcat", move |job| {
+            job.client(&client)
+                .shell(&device_id, "logcat -c")
+                .map(|_| AdbJobOutput::Text("✅ Device log buffer cleared".to_string()))
+                .map_err(|e| e.to_string())
+        });
     }
 }
 
-fn execute_shell_command(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        if !state.shell_command.is_empty() {
-            match execute_adb_command(&["-s", device_id, "shell", &state.shell_command]) {
-                Ok(output) => {
-                    state.shell_output = output;
-                }
-                Err(e) => {
-                    state.shell_output = format!("Command failed: {}", e);
-                }
+fn take_screenshot(state: &mut AdbToolsState) {
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        let screenshot_path = state.screenshot_path.clone();
+        state.jobs.submit(AdbFunction::ScreenCapture, "Take screenshot", move |job| {
+            // exec: keeps the PNG bytes intact, so nothing has to be staged on the device
+            let png = job
+                .client(&client)
+                .exec(&device_id, "screencap -p")
+                .map_err(|e| format!("Screenshot failed: {}", e))?;
+            if !png.starts_with(b"\x89PNG") {
+                return Err(format!("Screenshot failed: {}", String::from_utf8_lossy(&png).trim()));
             }
-        }
+            
+            match std::fs::write(&screenshot_path, &png) {
+                Ok(()) => Ok(AdbJobOutput::Text(format!("Screenshot saved to: {}", screenshot_path))),
+                Err(e) => Err(format!("Screenshot failed: {}", e)),
+            }
+        });
     }
 }
 
-fn start_logcat(state: &mut AdbToolsState) {
-    // This is a simplified version - in a real implementation, you'd want to run this in a background thread
-    if let Some(device_id) = &state.selected_device {
-        let mut cmd = vec!["-s", device_id, "logcat"];
-        if !state.logcat_filter.is_empty() {
-            cmd.push("-s");
-            cmd.push(&state.logcat_filter);
+fn is_screen_recording(state: &AdbToolsState) -> bool {
+    state.screen_record_job
+        .and_then(|id| state.jobs.job(id))
+        .is_some_and(|job| job.is_running())
+}
+
+fn start_screen_record(state: &mut AdbToolsState) {
+    if let Some(device_id) = state.selected_device.clone() {
+        if let Err(e) = state.screen_record_options.validate() {
+            state.screen_result = format!("❌ {}", e);
+            return;
         }
-        cmd.push("-d"); // Dump existing logs
         
-        if let Ok(output) = execute_adb_command(&cmd) {
-            state.logcat_output = output;
-            state.logcat_running = true;
-        }
+        let client = state.adb_client.clone();
+        let options = state.screen_record_options.clone();
+        let local_path = PathBuf::from(&state.screen_record_path);
+        let stop = Arc::new(AtomicBool::new(false));
+        state.screen_record_stop = stop.clone();
+        
+        let id = state.jobs.submit(AdbFunction::ScreenCapture, "Screen recording", move |job| {
+            let saved = record_screen(job, &client, &device_id, &options, &local_path, &stop)?;
+            let files: Vec<String> = saved.iter().map(|p| p.display().to_string()).collect();
+            match files.len() {
+                0 => Err("No recording was made".to_string()),
+                1 => Ok(AdbJobOutput::Text(format!("✅ Recording saved to: {}", files[0]))),
+                n => Ok(AdbJobOutput::Text(format!("✅ Recording saved as {} segments:\n{}", n, files.join("\n")))),
+            }
+        });
+        state.screen_record_job = Some(id);
     }
 }
 
-fn stop_logcat(state: &mut AdbToolsState) {
-    state.logcat_running = false;
+fn stop_screen_record(state: &mut AdbToolsState) {
+    // The job stops the device process and pulls what was recorded so far
+    state.screen_record_stop.store(true, Ordering::Relaxed);
+    state.screen_result = "Stopping recording...".to_string();
 }
 
-fn clear_logcat(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let _ = execute_adb_command(&["-s", device_id, "logcat", "-c"]);
-        state.logcat_output.clear();
+/// Run a forwarding change on the selected device, then reload the list from the server
+fn submit_forward_action<F>(state: &mut AdbToolsState, label: String, action: F)
+where
+    F: FnOnce(&AdbClient, &str) -> Result<String, String> + Send + 'static,
+{
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        state.jobs.submit(AdbFunction::PortForwarding, label, move |job| {
+            let client = job.client(&client);
+            let message = action(&client, &device_id);
+            let rules = list_forwards(&client, &device_id)?;
+            Ok(AdbJobOutput::Forwards {
+                serial: device_id,
+                rules,
+                message: Some(message.unwrap_or_else(|e| e)),
+            })
+        });
     }
 }
 
-fn take_screenshot(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let remote_path = "/sdcard/screenshot.png";
-        
-        // Take screenshot on device
-        if execute_adb_command(&["-s", device_id, "shell", "screencap", "-p", remote_path]).is_ok() {
-            // Pull to local path
-            match execute_adb_command(&["-s", device_id, "pull", remote_path, &state.screenshot_path]) {
-                Ok(_) => {
-                    state.file_operation_result = format!("Screenshot saved to: {}", state.screenshot_path);
-                }
-                Err(e) => {
-                    state.file_operation_result = format!("Screenshot failed: {}", e);
+fn list_port_forwards(state: &mut AdbToolsState) {
+    state.port_forwards_device = state.selected_device.clone();
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        state.jobs.submit(AdbFunction::PortForwarding, "List forwards", move |job| {
+            let client = job.client(&client);
+            let rules = list_forwards(&client, &device_id)?;
+            Ok(AdbJobOutput::Forwards { serial: device_id, rules, message: None })
+        });
+    }
+}
+
+fn add_port_forward(state: &mut AdbToolsState) {
+    if state.local_port.trim().is_empty() || state.remote_port.trim().is_empty() {
+        state.forward_result = "Both ends of the forward are required".to_string();
+        return;
+    }
+    let rule = ForwardRule {
+        direction: state.forward_direction,
+        local: state.forward_local_kind.spec(&state.local_port),
+        remote: state.forward_remote_kind.spec(&state.remote_port),
+    };
+    submit_forward_action(state, format!("Add {}", rule.describe()), move |client, serial| {
+        Ok(match add_forward(client, serial, &rule)? {
+            Some(port) => format!("✅ Added {} (host port {})", rule.describe(), port.trim()),
+            None => format!("✅ Added {}", rule.describe()),
+        })
+    });
+}
+
+fn remove_port_forward(state: &mut AdbToolsState, rule: ForwardRule) {
+    submit_forward_action(state, format!("Remove {}", rule.describe()), move |client, serial| {
+        remove_forward(client, serial, &rule)?;
+        Ok(format!("Removed {}", rule.describe()))
+    });
+}
+
+fn remove_all_port_forwards(state: &mut AdbToolsState, direction: ForwardDirection) {
+    let kind = match direction {
+        ForwardDirection::Forward => "forwards",
+        ForwardDirection::Reverse => "reverses",
+    };
+    submit_forward_action(state, format!("Remove all {}", kind), move |client, serial| {
+        let removed = remove_all_forwards(client, serial, direction)?;
+        Ok(format!("Removed {} {} from {}", removed, kind, serial))
+    });
+}
+
+fn save_forward_profile(state: &mut AdbToolsState) {
+    let name = state.forward_profile_name.trim().to_string();
+    let rules = state.port_forwards.clone();
+    state.forward_result = format!("Saved profile {} with {} rule(s)", name, rules.len());
+    match state.forward_profiles.iter_mut().find(|p| p.name == name) {
+        Some(profile) => profile.rules = rules,
+        None => state.forward_profiles.push(ForwardProfile { name, rules, ..Default::default() }),
+    }
+    state.forward_profile_name.clear();
+}
+
+/// Apply auto-apply profiles to devices that came online since the last check
+fn check_forward_profiles(state: &mut AdbToolsState) {
+    let checking = state.forward_check_job.and_then(|id| state.jobs.job(id)).is_some_and(|job| job.is_running());
+    if checking || state.jobs.is_running(AdbFunction::PortForwarding) {
+        return;
+    }
+    let due = state
+        .forward_last_check
+        .is_none_or(|last| last.elapsed() >= PROFILE_CHECK_INTERVAL);
+    if !due || !state.forward_profiles.iter().any(|p| p.auto_apply && !p.rules.is_empty()) {
+        return;
+    }
+    state.forward_last_check = Some(Instant::now());
+
+    let client = state.adb_client.clone();
+    let profiles = state.forward_profiles.clone();
+    let previous = state.forward_profile_online.clone();
+    let id = state.jobs.submit_background(AdbFunction::PortForwarding, "Check forwarding profiles", move |job| {
+        let client = job.client(&client);
+        let devices = client.devices().map_err(|e| e.to_string())?;
+        let online: HashSet<String> = devices.into_iter().filter(|d| d.status == "device").map(|d| d.id).collect();
+
+        let mut applied = Vec::new();
+        let mut errors = Vec::new();
+        for serial in online.difference(&previous) {
+            for profile in profiles.iter().filter(|p| p.auto_apply && p.matches(serial)) {
+                match apply_profile(&client, serial, profile) {
+                    Ok(added) => {
+                        job.log(format!("{}: {} rule(s) from {}", serial, added, profile.name));
+                        if added > 0 && !applied.contains(serial) {
+                            applied.push(serial.clone());
+                        }
+                    }
+                    Err(e) => errors.push(format!("{} on {}: {}", profile.name, serial, e)),
                 }
             }
         }
+
+        let message = if !errors.is_empty() {
+            Some(format!("❌ {}", errors.join("; ")))
+        } else if !applied.is_empty() {
+            Some(format!("🔁 Re-applied forwarding profiles on {}", applied.join(", ")))
+        } else {
+            None
+        };
+        Ok(AdbJobOutput::ForwardProfilesChecked { online, applied, message })
+    });
+    state.forward_check_job = Some(id);
+}
+
+// Device Monitoring Functions
+fn update_monitoring_data(state: &mut AdbToolsState) {
+    // Don't pile up samples behind a slow device
+    if state.monitor_job.and_then(|id| state.jobs.job(id)).is_some_and(|job| job.is_running()) {
+        return;
+    }
+    
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        let app_target = state.app_tracking.as_ref().map(|t| (t.target.clone(), t.ticks()));
+        let id = state.jobs.submit_background(AdbFunction::DeviceMonitor, "Update monitoring data", move |job| {
+            let client = job.client(&client);
+            let (cpu_usage, cpu) = get_cpu_usage(&client, &device_id);
+            let (thermal_info, thermal) = get_thermal_info(&client, &device_id);
+            let app = app_target.map(|(target, ticks)| {
+                let sample = sample_app(&client, &device_id, &target, ticks);
+                (target, sample)
+            });
+            Ok(AdbJobOutput::Monitoring(MonitoringSnapshot {
+                cpu_usage,
+                cpu,
+                memory_info: get_memory_info(&client, &device_id),
+                battery_info: get_battery_monitoring_info(&client, &device_id),
+                thermal_info,
+                thermal,
+                network: get_network_stats(&client, &device_id),
+                process_list: get_process_list(&client, &device_id),
+                app,
+            }))
+        });
+        state.monitor_job = Some(id);
     }
 }
 
-fn start_screen_record(state: &mut AdbToolsState) {
-    // This would typically be run in a background thread
-    state.file_operation_result = "Screen recording started (not implemented in demo)".to_string();
+fn start_heap_dump(state: &mut AdbToolsState) {
+    let target = state.heap_dump_target.trim().to_string();
+    if target.is_empty() {
+        return;
+    }
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        let local_dir = PathBuf::from(&state.heap_dump_dir);
+        state.monitor_result.clear();
+        state.jobs.submit(AdbFunction::DeviceMonitor, format!("Dump heap of {}", target), move |job| {
+            dump_heap(job, &client, &device_id, &target, &local_dir).map(AdbJobOutput::HeapDump)
+        });
+    }
 }
 
-fn stop_screen_record(state: &mut AdbToolsState) {
-    state.file_operation_result = "Screen recording stopped (not implemented in demo)".to_string();
+fn start_app_tracking(state: &mut AdbToolsState) {
+    let tracking = AppTracking::new(&state.app_track_target);
+    state.time_series.process = ProcessSeries {
+        label: tracking.target.clone(),
+        ..Default::default()
+    };
+    state.app_tracking = Some(tracking);
+    
+    // Tracking rides on the monitoring updates
+    if state.time_series.start_time.is_none() {
+        state.time_series.start_time = Some(Instant::now());
+    }
+    state.monitoring_enabled = true;
+    update_monitoring_data(state);
+    state.last_update_time = Some(Instant::now());
 }
 
-fn forward_port(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let forward_spec = format!("tcp:{}:tcp:{}", state.local_port, state.remote_port);
-        match execute_adb_command(&["-s", device_id, "forward", &forward_spec]) {
-            Ok(_) => {
-                state.forwarded_ports.push((state.local_port.clone(), state.remote_port.clone()));
-                state.file_operation_result = format!("Port forwarding established: {}:{}", state.local_port, state.remote_port);
-            }
-            Err(e) => {
-                state.file_operation_result = format!("Port forward failed: {}", e);
+fn apply_monitoring_snapshot(state: &mut AdbToolsState, snapshot: MonitoringSnapshot) {
+    state.cpu_usage = snapshot.cpu_usage;
+    state.cpu_utilisation = snapshot.cpu;
+    state.memory_info = snapshot.memory_info;
+    state.battery_info = snapshot.battery_info;
+    state.thermal_info = snapshot.thermal_info;
+    state.thermal = snapshot.thermal;
+    // Rates need two samples; the first one only primes the counters
+    match snapshot.network {
+        Some(counters) => {
+            state.network_rates = match &state.network_counters {
+                Some(previous) => counters.rates(Some(previous)),
+                None => Vec::new(),
+            };
+            state.network_counters = Some(counters);
+        }
+        None => {
+            state.network_rates.clear();
+            state.network_counters = None;
+        }
+    }
+    state.process_list = snapshot.process_list;
+    if let (Some(tracking), Some((target, sample))) = (&mut state.app_tracking, snapshot.app) {
+        // Ignore a sample taken for a target that has since been replaced
+        if tracking.target == target {
+            match sample {
+                Ok(sample) => tracking.record(sample),
+                Err(e) => tracking.error = Some(e),
             }
         }
     }
+    
+    // Update timestamp
+    state.last_monitor_update = chrono::Utc::now().format("%H:%M:%S").to_string();
+    
+    // Add data points to time series
+    add_time_series_data(state);
 }
 
-fn remove_port_forward(state: &mut AdbToolsState, local_port: &str) {
-    if let Some(device_id) = &state.selected_device {
-        let forward_spec = format!("tcp:{}", local_port);
-        let _ = execute_adb_command(&["-s", device_id, "forward", "--remove", &forward_spec]);
-        state.forwarded_ports.retain(|(lp, _)| lp != local_port);
+fn get_cpu_usage(client: &AdbClient, device_id: &str) -> (String, Option<CpuUtilisation>) {
+    // Utilisation between two reads of /proc/stat
+    match sample_cpu(client, device_id) {
+        Ok(cpu) => (cpu.summary(), Some(cpu)),
+        Err(e) => {
+            log::debug!("CPU sample failed: {}", e);
+            ("CPU usage unavailable".to_string(), None)
+        }
     }
 }
 
-fn remove_all_forwards(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let _ = execute_adb_command(&["-s", device_id, "forward", "--remove-all"]);
-        state.forwarded_ports.clear();
+fn get_memory_info(client: &AdbClient, device_id: &str) -> HashMap<String, String> {
+    let mut memory_info = HashMap::new();
+    
+    // Get memory information from /proc/meminfo
+    if let Ok(output) = client.shell(device_id, "cat /proc/meminfo") {
+        for line in output.lines().take(10) { // Get first 10 lines
+            if let Some(colon_pos) = line.find(':') {
+                let key = line[..colon_pos].trim();
+                let value = line[colon_pos + 1..].trim();
+                
+                // Format important memory values
+                let label = match key {
+                    "MemTotal" => "Total Memory",
+                    "MemFree" => "Free Memory",
+                    "MemAvailable" => "Available Memory",
+                    "Buffers" => "Buffers",
+                    "Cached" => "Cached",
+                    "SwapTotal" => "Swap Total",
+                    "SwapFree" => "Swap Free",
+                    _ => continue,
+                };
+                memory_info.insert(label.to_string(), value.to_string());
+            }
+        }
     }
-}
-
-// Device Monitoring Functions
-fn update_monitoring_data(state: &mut AdbToolsState) {
-    if let Some(device_id) = state.selected_device.clone() {
-        // Update CPU usage
-        get_cpu_usage(state, &device_id);
-        
-        // Update memory information
-        get_memory_info(state, &device_id);
-        
-        // Update battery info
-        get_battery_monitoring_info(state, &device_id);
-        
-        // Update thermal information
-        get_thermal_info(state, &device_id);
-        
-        // Update network statistics
-        get_network_stats(state, &device_id);
-        
-        // Update process list
-        update_process_list(state);
-        
-        // Update timestamp
-        state.last_monitor_update = chrono::Utc::now().format("%H:%M:%S").to_string();
-        
-        // Add data points to time series
-        add_time_series_data(state);
+    
+    // Calculate memory usage percentage
+    if let (Some(total), Some(available)) = (
+        memory_info.get("Total Memory").and_then(|s| extract_kb_value(s)),
+        memory_info.get("Available Memory").and_then(|s| extract_kb_value(s))
+    ) {
+        let used = total - available;
+        let usage_percent = (used as f64 / total as f64) * 100.0;
+        memory_info.insert("Memory Usage".to_string(), format!("{:.1}%", usage_percent));
     }
+    
+    memory_info
 }
 
-fn get_cpu_usage(state: &mut AdbToolsState, device_id: &str) {
-    // Get CPU usage from /proc/stat
-    if let Ok(output) = execute_adb_command(&["-s", device_id, "shell", "cat", "/proc/loadavg"]) {
-        let parts: Vec<&str> = output.trim().split_whitespace().collect();
-        if parts.len() >= 3 {
-            state.cpu_usage = format!("Load: {} {} {} (1m 5m 15m)",
-                parts[0], parts[1], parts[2]);
+fn get_battery_monitoring_info(client: &AdbClient, device_id: &str) -> HashMap<String, String> {
+    let mut battery_info = HashMap::new();
+    
+    if let Ok(output) = client.shell(device_id, "dumpsys battery") {
+        for line in output.lines() {
+            if let Some((key, value)) = line.split_once(':') {
+                let key = key.trim();
+                let value = value.trim();
+                
+                match key {
+                    "level" => { battery_info.insert("Battery Level".to_string(), format!("{}%", value)); }
+                    "temperature" => { 
+                        if let Ok(temp) = value.parse::<f32>() {
+                            battery_info.insert("Temperature".to_string(), format!("{:.1}°C", temp / 10.0));
+                        }
+                    }
+                    "voltage" => { 
+                        if let Ok(voltage) = value.parse::<f32>() {
+                            battery_info.insert("Voltage".to_string(), format!("{:.2}V", voltage / 1000.0));
+                        }
+                    }
+                    "health" => { battery_info.insert("Health".to_string(), value.to_string()); }
+                    "status" => { battery_info.insert("Status".to_string(), value.to_string()); }
+                    "AC powered" => { battery_info.insert("AC Powered".to_string(), value.to_string()); }
+                    "USB powered" => { battery_info.insert("USB Powered".to_string(), value.to_string()); }
+                    _ => {}
+                }
+            }
         }
-    } else {
-        state.cpu_usage = "CPU usage unavailable".to_string();
     }
     
-    // Try to get more detailed CPU info
-    if let Ok(output) = execute_adb_command(&["-s", device_id, "shell", "cat", "/proc/cpuinfo"]) {
-        let cpu_count = output.lines()
-            .filter(|line| line.starts_with("processor"))
-            .count();
-        
-        if cpu_count > 0 {
-            state.cpu_usage += &format!(" | {} cores", cpu_count);
+    battery_info
+}
+
+fn get_thermal_info(client: &AdbClient, device_id: &str) -> (String, Option<ThermalInfo>) {
+    // Every thermal zone and cooling device, plus thermalservice where it exists
+    match sample_thermal(client, device_id) {
+        Ok(thermal) if !thermal.zones.is_empty() || thermal.service.is_some() => (thermal.summary(), Some(thermal)),
+        Ok(_) => ("Not available".to_string(), None),
+        Err(e) => {
+            log::debug!("Thermal sample failed: {}", e);
+            ("Not available".to_string(), None)
         }
     }
 }
 
-fn get_memory_info(state: &mut AdbToolsState, device_id: &str) {
-    state.memory_info.clear();
+fn get_network_stats(client: &AdbClient, device_id: &str) -> Option<NetworkCounters> {
+    // Counters for every interface in /proc/net/dev; rates are worked out between samples
+    sample_network(client, device_id)
+        .map_err(|e| log::debug!("Network sample failed: {}", e))
+        .ok()
+}
+
+fn refresh_uid_traffic(state: &mut AdbToolsState) {
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        state.jobs.submit(AdbFunction::DeviceMonitor, "Read per-UID traffic", move |job| {
+            let client = job.client(&client);
+            let (source, entries) = sample_uid_traffic(&client, &device_id)?;
+            Ok(AdbJobOutput::UidTraffic { source, entries })
+        });
+    }
+}
+
+fn update_process_list(state: &mut AdbToolsState) {
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        state.jobs.submit(AdbFunction::DeviceMonitor, "Refresh processes", move |job| {
+            let client = job.client(&client);
+            Ok(AdbJobOutput::Processes(get_process_list(&client, &device_id)))
+        });
+    }
+}
+
+fn get_process_list(client: &AdbClient, device_id: &str) -> Vec<ProcessInfo> {
+    let mut process_list = Vec::new();
     
-    // Get memory information from /proc/meminfo
-    if let Ok(output) = execute_adb_command(&["-s", device_id, "shell", "cat", "/proc/meminfo"]) {
-        for line in output.lines().take(10) { // Get first 10 lines
-            if let Some(colon_pos) = line.find(':') {
-                let key = line[..colon_pos].trim();
-                let value = line[colon_pos + 1..].trim();
-                
-                // Format important memory values
-                match key {
-                    "MemTotal" => state.memory_info.insert("Total Memory".to_string(), value.to_string()),
-                    "MemFree" => state.memory_info.insert("Free Memory".to_string(), value.to_string()),
-                    "MemAvailable" => state.memory_info.insert("Available Memory".to_string(), value.to_string()),
-                    "Buffers" => state.memory_info.insert("Buffers".to_string(), value.to_string()),
-                    "Cached" => state.memory_info.insert("Cached".to_string(), value.to_string()),
-                    "SwapTotal" => state.memory_info.insert("Swap Total".to_string(), value.to_string()),
-                    "SwapFree" => state.memory_info.insert("Swap Free".to_string(), value.to_string()),
-                    _ => None,
+    // Get process information using ps command; columns: PID USER %CPU RSS S NAME
+    if let Ok(output) = client.shell(device_id, "ps -A -o pid,user,%cpu,rss,s,name") {
+        for line in output.lines().skip(1) { // Skip header
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() >= 6 {
+                let process = ProcessInfo {
+                    pid: parts[0].to_string(),
+                    name: parts[5..].join(" "),
+                    cpu_percent: parts[2].to_string(),
+                    memory_kb: format!("{} KB", parts[3]),
+                    user: parts[1].to_string(),
+                    state: parts[4].to_string(),
                 };
+                process_list.push(process);
             }
         }
     }
     
-    // Calculate memory usage percentage
-    if let (Some(total), Some(available)) = (
-        state.memory_info.get("Total Memory").and_then(|s| extract_kb_value(s)),
-        state.memory_info.get("Available Memory").and_then(|s| extract_kb_value(s))
-    ) {
-        let used = total - available;
-        let usage_percent = (used as f64 / total as f64) * 100.0;
-        state.memory_info.insert("Memory Usage".to_string(), format!("{:.1}%", usage_percent));
-    }
-}
-
-fn get_battery_monitoring_info(state: &mut AdbToolsState, device_id: &str) {
-    state.battery_info.clear();
-    
-    if let Ok(output) = execute_adb_command(&["-s", device_id, "shell", "dumpsys", "battery"]) {
-        for line in output.lines() {
-            if line.contains(':') {
-                let parts: Vec<&str> = line.splitn(2, ':').collect();
-                if parts.len() == 2 {
-                    let key = parts[0].trim();
-                    let value = parts[1].trim();
-                    
-                    match key {
-                        "level" => { state.battery_info.insert("Battery Level".to_string(), format!("{}%", value)); }
-                        "temperature" => { 
-                            if let Ok(temp) = value.parse::<f32>() {
-                                state.battery_info.insert("Temperature".to_string(), format!("{:.1}°C", temp / 10.0));
-                            }
-                        }
-                        "voltage" => { 
-                            if let Ok(voltage) = value.parse::<f32>() {
-                                state.battery_info.insert("Voltage".to_string(), format!("{:.2}V", voltage / 1000.0));
-                            }
-                        }
-                        "health" => { state.battery_info.insert("Health".to_string(), value.to_string()); }
-                        "status" => { state.battery_info.insert("Status".to_string(), value.to_string()); }
-                        "AC powered" => { state.battery_info.insert("AC Powered".to_string(), value.to_string()); }
-                        "USB powered" => { state.battery_info.insert("USB Powered".to_string(), value.to_string()); }
-                        _ => {}
-                    }
+    // Toolbox ps before Android 8 has no -o; it ignores the options and prints
+    // USER PID PPID VSIZE RSS WCHAN PC S NAME
+    if process_list.is_empty() {
+        if let Ok(output) = client.shell(device_id, "ps") {
+            for line in output.lines().skip(1) {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                if parts.len() >= 9 {
+                    let process = ProcessInfo {
+                        pid: parts[1].to_string(),
+                        name: parts[8].to_string(),
+                        cpu_percent: "N/A".to_string(),
+                        memory_kb: format!("{} KB", parts[4]),
+                        user: parts[0].to_string(),
+                        state: parts[7].to_string(),
+                    };
+                    process_list.push(process);
                 }
             }
         }
     }
+    
+    // Sort by PID for consistency
+    process_list.sort_by(|a, b| {
+        a.pid.parse::<u32>().unwrap_or(0).cmp(&b.pid.parse::<u32>().unwrap_or(0))
+    });
+    
+    process_list
 }
 
-fn get_thermal_info(state: &mut AdbToolsState, device_id: &str) {
-    // Try to get thermal information
-    if let Ok(output) = execute_adb_command(&["-s", device_id, "shell", "cat", "/sys/class/thermal/thermal_zone0/temp"]) {
-        if let Ok(temp) = output.trim().parse::<f32>() {
-            state.thermal_info = format!("{:.1}°C", temp / 1000.0);
-        }
-    } else {
-        state.thermal_info = "Not available".to_string();
+fn kill_process(state: &mut AdbToolsState, pid: &str) {
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        let pid = pid.to_string();
+        state.jobs.submit(AdbFunction::DeviceMonitor, format!("Kill process {}", pid), move |job| {
+            match job.client(&client).shell(&device_id, &format!("kill {}", shell_quote(&pid))) {
+                Ok(_) => Ok(AdbJobOutput::ProcessKilled(format!("Process {} killed successfully", pid))),
+                Err(e) => Ok(AdbJobOutput::ProcessKilled(format!("Failed to kill process {}: {}", pid, e))),
+            }
+        });
     }
 }
 
-fn get_network_stats(state: &mut AdbToolsState, device_id: &str) {
-    state.network_stats.clear();
-    
-    // Get network interface statistics
-    if let Ok(output) = execute_adb_command(&["-s", device_id, "shell", "cat", "/proc/net/dev"]) {
-        for line in output.lines().skip(2) { // Skip header lines
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 10 {
-                let interface = parts[0].trim_end_matches(':');
-                if interface == "wlan0" || interface == "rmnet0" || interface == "eth0" {
-                    let rx_bytes = parts[1];
-                    let tx_bytes = parts[9];
-                    
-                    if let (Ok(rx), Ok(tx)) = (rx_bytes.parse::<u64>(), tx_bytes.parse::<u64>()) {
-                        state.network_stats.insert(
-                            format!("{} RX", interface),
-                            format_bytes(rx)
-                        );
-                        state.network_stats.insert(
-                            format!("{} TX", interface),
-                            format_bytes(tx)
-                        );
-                    }
+// Job result handling
+fn apply_job_results(state: &mut AdbToolsState) {
+    for finished in state.jobs.poll() {
+        log::debug!("Job {} '{}' finished", finished.id, finished.label);
+        match finished.result {
+            Ok(output) => apply_job_output(state, finished.function, output),
+            Err(message) => {
+                log::warn!("Job '{}' failed: {}", finished.label, message);
+                // A failed periodic check would otherwise overwrite the tab's result every few seconds
+                if finished.background {
+                    continue;
+                }
+                if let Some(target) = job_text_target(state, finished.function) {
+                    *target = message;
                 }
             }
         }
     }
 }
 
-fn update_process_list(state: &mut AdbToolsState) {
-    if let Some(device_id) = state.selected_device.clone() {
-        state.process_list.clear();
-        
-        // Get process information using ps command
-        if let Ok(output) = execute_adb_command(&["-s", &device_id, "shell", "ps", "-o", "user,group,pid,ppid,pgid,etime,nice,rgroup,ruser,time,tty,vsz,sid,stat,rss,comm,args,label"]) {
-            for line in output.lines().skip(1) { // Skip header
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 6 {
-                    let process = ProcessInfo {
-                        pid: parts[0].to_string(),
-                        name: parts[1].to_string(),
-                        cpu_percent: parts[2].to_string(),
-                        memory_kb: format!("{} KB", parts[3]),
-                        user: parts[4].to_string(),
-                        state: parts[5].to_string(),
-                    };
-                    state.process_list.push(process);
+fn apply_job_output(state: &mut AdbToolsState, function: AdbFunction, output: AdbJobOutput) {
+    match output {
+        AdbJobOutput::Devices(devices) => set_devices(state, devices),
+        AdbJobOutput::DeviceInfo(info) => state.device_info.extend(info),
+        AdbJobOutput::Packages(packages) => {
+            state.package_selection.retain(|package| packages.contains(package));
+            state.installed_packages = packages;
+        }
+        AdbJobOutput::PackageUninstalled(message) => {
+            state.app_result = message;
+            list_packages(state); // Refresh package list
+        }
+        AdbJobOutput::PackageBatch { results, refresh } => {
+            state.package_batch_results = results;
+            if refresh {
+                list_packages(state);
+            }
+        }
+        AdbJobOutput::PackageDetails { details, app_ops, message } => {
+            // A slow reload may land after another package was selected
+            if state.selected_package.as_deref() == Some(details.package.as_str()) {
+                state.package_details = Some(details);
+                state.package_app_ops = app_ops;
+            }
+            if let Some(message) = message {
+                state.app_result = message;
+            }
+        }
+        AdbJobOutput::ApkInspected { path, info, splits, message } => {
+            // Ignore results for a path the user has since edited
+            if path == state.apk_path.trim() {
+                state.app_result = message;
+                state.apk_info = info;
+                state.apk_inspected = true;
+                state.apk_splits = splits;
+            }
+        }
+        AdbJobOutput::Monitoring(snapshot) => apply_monitoring_snapshot(state, snapshot),
+        AdbJobOutput::Processes(processes) => state.process_list = processes,
+        AdbJobOutput::UidTraffic { source, entries } => {
+            state.uid_traffic_source = source;
+            state.uid_traffic = entries;
+        }
+        AdbJobOutput::ProcessKilled(message) => {
+            state.monitor_result = message;
+            update_process_list(state);
+        }
+        AdbJobOutput::LogcatPids { serial, package, pids } => {
+            if let Some(session) = state.logcat_sessions.get_mut(&serial) {
+                session.set_package_pids(&package, pids);
+            }
+        }
+        AdbJobOutput::LogcatFile { name, entries } => {
+            state.logcat_file_result = format!("✅ Opened {} ({} lines)", name, entries.len());
+            state.logcat_offline = Some(LogcatSession::from_entries(&name, entries));
+            state.logcat_show_offline = true;
+        }
+        AdbJobOutput::RemoteListing { path, entries, message } => {
+            // Ignore listings of a folder the user has already left
+            if normalize_remote_dir(&state.remote_path) == path {
+                state.remote_entries = entries;
+                state.remote_selection.retain(|name| state.remote_entries.iter().any(|e| e.name == *name));
+            }
+            if let Some(message) = message {
+                state.file_operation_result = message;
+            }
+        }
+        AdbJobOutput::SyncPlanned(plan) => {
+            state.file_operation_result = format!(
+                "Sync preview: {} change(s), {} unchanged",
+                plan.actions.len(),
+                plan.unchanged
+            );
+            state.sync_plan = Some(plan);
+        }
+        AdbJobOutput::SyncApplied(message) => {
+            state.file_operation_result = message;
+            state.sync_plan = None;
+        }
+        AdbJobOutput::HeapDump(result) => {
+            state.monitor_result = format!(
+                "✅ Heap of {} saved to {} (Android format: {})",
+                result.target,
+                result.path.display(),
+                result.android_path.display()
+            );
+            state.heap_dump = Some(result);
+        }
+        AdbJobOutput::Broadcast(run) => {
+            state.shell_output.clear();
+            state.shell_broadcast_run = Some(run);
+        }
+        AdbJobOutput::Forwards { serial, rules, message } => {
+            if state.selected_device.as_deref() == Some(serial.as_str()) {
+                state.port_forwards = rules;
+            }
+            if let Some(message) = message {
+                state.forward_result = message;
+            }
+        }
+        AdbJobOutput::ForwardProfilesChecked { online, applied, message } => {
+            state.forward_profile_online = online;
+            if state.selected_device.as_ref().is_some_and(|serial| applied.contains(serial)) {
+                // Reload the list on the next frame
+                state.port_forwards_device = None;
+            }
+            if let Some(message) = message {
+                state.forward_result = message;
+            }
+        }
+        AdbJobOutput::Wireless { devices, connected, message } => {
+            if let Some(devices) = devices {
+                // Skip no-op updates so the periodic check leaves the selection alone
+                let changed = devices.len() != state.devices.len()
+                    || devices.iter().zip(&state.devices).any(|(a, b)| a.id != b.id || a.status != b.status);
+                if changed {
+                    set_devices(state, devices);
                 }
             }
-        } else {
-            // Fallback to simpler ps command
-            if let Ok(output) = execute_adb_command(&["-s", &device_id, "shell", "ps"]) {
-                for line in output.lines().skip(1) {
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() >= 9 {
-                        let process = ProcessInfo {
-                            pid: parts[1].to_string(),
-                            name: parts[8].to_string(),
-                            cpu_percent: "N/A".to_string(),
-                            memory_kb: format!("{} KB", parts[4]),
-                            user: parts[0].to_string(),
-                            state: parts[2].to_string(),
-                        };
-                        state.process_list.push(process);
-                    }
+            if let Some(address) = connected {
+                if !state.wireless_endpoints.iter().any(|e| normalize_address(&e.address) == address) {
+                    state.wireless_endpoints.push(WirelessEndpoint { address, ..Default::default() });
                 }
             }
+            if let Some(message) = message {
+                state.wireless_result = message;
+            }
+        }
+        AdbJobOutput::MdnsServices(services) => {
+            state.wireless_result = format!("Found {} mDNS service(s)", services.len());
+            state.mdns_services = services;
+        }
+        AdbJobOutput::Text(text) => {
+            if let Some(target) = job_text_target(state, function) {
+                *target = text;
+            }
         }
-        
-        // Sort by PID for consistency
-        state.process_list.sort_by(|a, b| {
-            a.pid.parse::<u32>().unwrap_or(0).cmp(&b.pid.parse::<u32>().unwrap_or(0))
-        });
     }
 }
 
-fn kill_process(state: &mut AdbToolsState, pid: &str) {
-    if let Some(device_id) = state.selected_device.clone() {
-        match execute_adb_command(&["-s", &device_id, "shell", "kill", pid]) {
-            Ok(_) => {
-                state.file_operation_result = format!("Process {} killed successfully", pid);
-                // Refresh process list
-                update_process_list(state);
+/// The output field a function's plain-text job results are written to
+fn job_text_target(state: &mut AdbToolsState, function: AdbFunction) -> Option<&mut String> {
+    match function {
+        AdbFunction::ShellCommands => Some(&mut state.shell_output),
+        AdbFunction::Logcat => Some(&mut state.logcat_file_result),
+        AdbFunction::SelinuxManagement => Some(&mut state.selinux_output),
+        AdbFunction::SystemdManagement => Some(&mut state.systemd_output),
+        AdbFunction::WirelessDebugging => Some(&mut state.wireless_result),
+        AdbFunction::AppManagement => Some(&mut state.app_result),
+        AdbFunction::FileOperations => Some(&mut state.file_operation_result),
+        AdbFunction::ScreenCapture => Some(&mut state.screen_result),
+        AdbFunction::PortForwarding => Some(&mut state.forward_result),
+        AdbFunction::DeviceMonitor => Some(&mut state.monitor_result),
+        AdbFunction::DeviceInfo => None,
+    }
+}
+
+fn show_job_status(ui: &mut Ui, state: &mut AdbToolsState, function: AdbFunction) {
+    let mut job_to_cancel = None;
+    
+    for job in state.jobs.running_jobs(function) {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label(format!("{} ({:.1}s)", job.label, job.elapsed().as_secs_f32()));
+            if let Some(progress) = job.progress {
+                ui.add(egui::ProgressBar::new(progress).desired_width(150.0).show_percentage());
             }
-            Err(e) => {
-                state.file_operation_result = format!("Failed to kill process {}: {}", pid, e);
+            if ui.small_button("✖ Cancel").clicked() {
+                job_to_cancel = Some(job.id);
             }
+        });
+        if let Some(line) = job.log.last() {
+            ui.label(RichText::new(line).small().weak());
         }
     }
+    
+    if let Some(id) = job_to_cancel {
+        state.jobs.cancel(id);
+    }
+}
+
+fn show_jobs_panel(ui: &mut Ui, state: &mut AdbToolsState) {
+    if state.jobs.jobs().is_empty() {
+        return;
+    }
+    
+    let header = format!("⏳ Jobs ({} running)", state.jobs.running_count());
+    ui.collapsing(header, |ui| {
+        let mut job_to_cancel = None;
+        
+        ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+            for job in state.jobs.jobs().iter().rev() {
+                ui.horizontal(|ui| {
+                    ui.label(job.status.icon());
+                    ui.label(RichText::new(job.function.name()).weak());
+                    ui.label(&job.label);
+                    ui.label(RichText::new(format!("{:.1}s", job.elapsed().as_secs_f32())).weak());
+                    if job.is_running() && ui.small_button("✖").on_hover_text("Cancel job").clicked() {
+                        job_to_cancel = Some(job.id);
+                    }
+                    if let Some(error) = &job.error {
+                        ui.label(RichText::new(error).small().color(egui::Color32::from_rgb(200, 80, 80)));
+                    }
+                });
+            }
+        });
+        
+        if let Some(id) = job_to_cancel {
+            state.jobs.cancel(id);
+        }
+        
+        if ui.small_button("🗑️ Clear Finished").clicked() {
+            state.jobs.clear_finished();
+        }
+    });
 }
 
 // SELinux Management Tab
 fn show_selinux_tab(ui: &mut Ui, state: &mut AdbToolsState) {
+    show_job_status(ui, state, AdbFunction::SelinuxManagement);
+    
     ui.horizontal(|ui| {
         if ui.button("🔒 Get SELinux Status").clicked() {
             get_selinux_status(state);
@@ -1579,230 +5760,132 @@ fn show_selinux_tab(ui: &mut Ui, state: &mut AdbToolsState) {
 }
 
 fn get_selinux_status(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "getenforce"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let status = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.selinux_output = format!(
-                    "=== SELinux Status ===\n{}\n{}",
-                    status.trim(),
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.selinux_output = format!("Failed to get SELinux status: {}", e);
-            }
-        }
-    }
+    submit_shell_report(
+        state,
+        AdbFunction::SelinuxManagement,
+        "=== SELinux Status ===".to_string(),
+        &["getenforce"],
+        None,
+        "Failed to get SELinux status",
+    );
 }
 
 fn get_selinux_contexts(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "ls", "-Z", "/"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let contexts = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.selinux_output = format!(
-                    "=== SELinux Contexts (Root Directory) ===\n{}\n{}",
-                    contexts.trim(),
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.selinux_output = format!("Failed to get SELinux contexts: {}", e);
-            }
-        }
-    }
+    submit_shell_report(
+        state,
+        AdbFunction::SelinuxManagement,
+        "=== SELinux Contexts (Root Directory) ===".to_string(),
+        &["ls", "-Z", "/"],
+        None,
+        "Failed to get SELinux contexts",
+    );
 }
 
 fn get_selinux_policy(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "cat", "/sys/fs/selinux/policy"])
-            .output();
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        state.jobs.submit(AdbFunction::SelinuxManagement, "Get SELinux policy", move |job| {
+            let client = job.client(&client);
+            let result = client
+                .shell_v2(&device_id, "cat /sys/fs/selinux/policy")
+                .map_err(|e| format!("Failed to access SELinux policy: {}", e))?;
             
-        match output {
-            Ok(result) => {
-                if result.stdout.is_empty() {
-                    // Try alternative method
-                    let alt_output = Command::new("adb")
-                        .args(["-s", device_id, "shell", "ls", "-la", "/sys/fs/selinux/"])
-                        .output();
-                        
-                    match alt_output {
-                        Ok(alt_result) => {
-                            let policy_info = String::from_utf8_lossy(&alt_result.stdout);
-                            state.selinux_output = format!(
-                                "=== SELinux Policy Information ===\n{}\n\nNote: Policy file is binary. Showing directory listing instead.",
-                                policy_info.trim()
-                            );
-                        }
-                        Err(e) => {
-                            state.selinux_output = format!("Failed to get SELinux policy info: {}", e);
-                        }
-                    }
-                } else {
-                    state.selinux_output = "=== SELinux Policy ===\nPolicy file is binary and cannot be displayed as text.".to_string();
-                }
-            }
-            Err(e) => {
-                state.selinux_output = format!("Failed to access SELinux policy: {}", e);
+            if !result.stdout.is_empty() {
+                return Ok(AdbJobOutput::Text(
+                    "=== SELinux Policy ===\nPolicy file is binary and cannot be displayed as text.".to_string(),
+                ));
             }
-        }
+            
+            // Try alternative method
+            let alt_result = client
+                .shell_v2(&device_id, "ls -la /sys/fs/selinux/")
+                .map_err(|e| format!("Failed to get SELinux policy info: {}", e))?;
+            let policy_info = alt_result.stdout_string();
+            Ok(AdbJobOutput::Text(format!(
+                "=== SELinux Policy Information ===\n{}\n\nNote: Policy file is binary. Showing directory listing instead.",
+                policy_info.trim()
+            )))
+        });
     }
 }
 
 fn set_selinux_enforcing(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "su", "-c", "setenforce 1"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let success = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.selinux_output = format!(
-                    "=== Set SELinux to Enforcing ===\n{}\n{}",
-                    if success.trim().is_empty() { "Command executed successfully" } else { success.trim() },
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.selinux_output = format!("Failed to set SELinux enforcing: {}", e);
-            }
-        }
-    }
+    submit_shell_report(
+        state,
+        AdbFunction::SelinuxManagement,
+        "=== Set SELinux to Enforcing ===".to_string(),
+        &["su", "-c", "setenforce 1"],
+        Some("Command executed successfully"),
+        "Failed to set SELinux enforcing",
+    );
 }
 
 fn set_selinux_permissive(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "su", "-c", "setenforce 0"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let success = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.selinux_output = format!(
-                    "=== Set SELinux to Permissive ===\n{}\n{}",
-                    if success.trim().is_empty() { "Command executed successfully" } else { success.trim() },
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.selinux_output = format!("Failed to set SELinux permissive: {}", e);
-            }
-        }
-    }
+    submit_shell_report(
+        state,
+        AdbFunction::SelinuxManagement,
+        "=== Set SELinux to Permissive ===".to_string(),
+        &["su", "-c", "setenforce 0"],
+        Some("Command executed successfully"),
+        "Failed to set SELinux permissive",
+    );
 }
 
 fn get_file_selinux_context(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        if state.selinux_file_path.trim().is_empty() {
-            state.selinux_output = "Please enter a file path first.".to_string();
-            return;
-        }
-        
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "ls", "-Z", &state.selinux_file_path])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let context = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.selinux_output = format!(
-                    "=== File SELinux Context: {} ===\n{}\n{}",
-                    state.selinux_file_path,
-                    context.trim(),
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.selinux_output = format!("Failed to get file SELinux context: {}", e);
-            }
-        }
+    if state.selinux_file_path.trim().is_empty() {
+        state.selinux_output = "Please enter a file path first.".to_string();
+        return;
     }
+    
+    let file_path = state.selinux_file_path.clone();
+    submit_shell_report(
+        state,
+        AdbFunction::SelinuxManagement,
+        format!("=== File SELinux Context: {} ===", file_path),
+        &["ls", "-Z", &file_path],
+        None,
+        "Failed to get file SELinux context",
+    );
 }
 
 fn set_file_selinux_context(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        if state.selinux_file_path.trim().is_empty() || state.selinux_new_context.trim().is_empty() {
-            state.selinux_output = "Please enter both file path and new context.".to_string();
-            return;
-        }
-        
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "su", "-c", &format!("chcon {} {}", state.selinux_new_context, state.selinux_file_path)])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let success = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.selinux_output = format!(
-                    "=== Set File SELinux Context ===\nFile: {}\nNew Context: {}\n{}\n{}",
-                    state.selinux_file_path,
-                    state.selinux_new_context,
-                    if success.trim().is_empty() { "Command executed successfully" } else { success.trim() },
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.selinux_output = format!("Failed to set file SELinux context: {}", e);
-            }
-        }
+    if state.selinux_file_path.trim().is_empty() || state.selinux_new_context.trim().is_empty() {
+        state.selinux_output = "Please enter both file path and new context.".to_string();
+        return;
     }
+    
+    let file_path = state.selinux_file_path.clone();
+    let new_context = state.selinux_new_context.clone();
+    submit_shell_report(
+        state,
+        AdbFunction::SelinuxManagement,
+        format!("=== Set File SELinux Context ===\nFile: {}\nNew Context: {}", file_path, new_context),
+        &["su", "-c", &format!("chcon {} {}", new_context, file_path)],
+        Some("Command executed successfully"),
+        "Failed to set file SELinux context",
+    );
 }
 
 fn get_process_selinux_contexts(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        if state.selinux_process_query.trim().is_empty() {
-            state.selinux_output = "Please enter a process name or PID.".to_string();
-            return;
-        }
-        
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "ps", "-Z", "|", "grep", &state.selinux_process_query])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let contexts = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.selinux_output = format!(
-                    "=== Process SELinux Contexts for: {} ===\n{}\n{}",
-                    state.selinux_process_query,
-                    if contexts.trim().is_empty() { "No matching processes found" } else { contexts.trim() },
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.selinux_output = format!("Failed to get process SELinux contexts: {}", e);
-            }
-        }
+    if state.selinux_process_query.trim().is_empty() {
+        state.selinux_output = "Please enter a process name or PID.".to_string();
+        return;
     }
+    
+    let query = state.selinux_process_query.clone();
+    submit_shell_report(
+        state,
+        AdbFunction::SelinuxManagement,
+        format!("=== Process SELinux Contexts for: {} ===", query),
+        &["ps", "-Z", "|", "grep", &query],
+        Some("No matching processes found"),
+        "Failed to get process SELinux contexts",
+    );
 }
 
 fn show_systemd_tab(ui: &mut Ui, state: &mut AdbToolsState) {
+    show_job_status(ui, state, AdbFunction::SystemdManagement);
+    
     // Check systemd availability first
     ui.horizontal(|ui| {
         if ui.button("🔍 Check Systemd").clicked() {
@@ -1985,675 +6068,323 @@ fn show_systemd_tab(ui: &mut Ui, state: &mut AdbToolsState) {
                 if ui.button("🗑️ Clear").clicked() {
                     state.systemd_output.clear();
                 }
-            });
-        });
-    }
-}
-
-fn check_systemd_availability(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "which", "systemctl"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let stdout = String::from_utf8_lossy(&result.stdout);
-                let stderr = String::from_utf8_lossy(&result.stderr);
-                
-                if !stdout.trim().is_empty() {
-                    state.systemd_output = format!(
-                        "=== Systemd Availability Check ===\n✅ Systemd is available at: {}\n",
-                        stdout.trim()
-                    );
-                    
-                    // Also check systemd version
-                    let version_output = Command::new("adb")
-                        .args(["-s", device_id, "shell", "systemctl", "--version"])
-                        .output();
-                        
-                    if let Ok(version_result) = version_output {
-                        let version = String::from_utf8_lossy(&version_result.stdout);
-                        state.systemd_output.push_str(&format!("\n{}", version));
-                    }
-                } else {
-                    state.systemd_output = format!(
-                        "=== Systemd Availability Check ===\n❌ Systemd not found on this device.\nThis feature requires a Linux system with systemd.\n{}",
-                        if !stderr.is_empty() { format!("Error: {}", stderr.trim()) } else { String::new() }
-                    );
-                }
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to check systemd availability: {}", e);
-            }
-        }
-    }
-}
-
-fn get_systemd_status(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "systemctl", "status"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let status = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== System Status ===\n{}\n{}",
-                    status,
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to get system status: {}", e);
-            }
-        }
-    }
-}
-
-fn systemd_daemon_reload(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "su", "-c", "systemctl daemon-reload"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let stdout = String::from_utf8_lossy(&result.stdout);
-                let stderr = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Daemon Reload ===\n{}\n{}",
-                    if stdout.trim().is_empty() { "Daemon reload completed successfully" } else { stdout.trim() },
-                    if !stderr.is_empty() { format!("Error: {}", stderr.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to reload daemon: {}", e);
-            }
-        }
-    }
-}
-
-fn systemd_start_service(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        if state.systemd_service_name.trim().is_empty() {
-            state.systemd_output = "Please enter a service name first.".to_string();
-            return;
-        }
-        
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "su", "-c", &format!("systemctl start {}", state.systemd_service_name)])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let stdout = String::from_utf8_lossy(&result.stdout);
-                let stderr = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Start Service: {} ===\n{}\n{}",
-                    state.systemd_service_name,
-                    if stdout.trim().is_empty() { "Service started successfully" } else { stdout.trim() },
-                    if !stderr.is_empty() { format!("Error: {}", stderr.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to start service: {}", e);
-            }
-        }
+            });
+        });
     }
 }
 
-fn systemd_stop_service(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        if state.systemd_service_name.trim().is_empty() {
-            state.systemd_output = "Please enter a service name first.".to_string();
-            return;
-        }
-        
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "su", "-c", &format!("systemctl stop {}", state.systemd_service_name)])
-            .output();
+fn check_systemd_availability(state: &mut AdbToolsState) {
+    if let Some(device_id) = state.selected_device.clone() {
+        let client = state.adb_client.clone();
+        state.jobs.submit(AdbFunction::SystemdManagement, "Check systemd availability", move |job| {
+            let client = job.client(&client);
+            let result = client
+                .shell_v2(&device_id, "which systemctl")
+                .map_err(|e| format!("Failed to check systemd availability: {}", e))?;
+            let stdout = result.stdout_string();
+            let stderr = result.stderr_string();
             
-        match output {
-            Ok(result) => {
-                let stdout = String::from_utf8_lossy(&result.stdout);
-                let stderr = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Stop Service: {} ===\n{}\n{}",
-                    state.systemd_service_name,
-                    if stdout.trim().is_empty() { "Service stopped successfully" } else { stdout.trim() },
+            if stdout.trim().is_empty() {
+                return Ok(AdbJobOutput::Text(format!(
+                    "=== Systemd Availability Check ===\n❌ Systemd not found on this device.\nThis feature requires a Linux system with systemd.\n{}",
                     if !stderr.is_empty() { format!("Error: {}", stderr.trim()) } else { String::new() }
-                );
+                )));
             }
-            Err(e) => {
-                state.systemd_output = format!("Failed to stop service: {}", e);
+            
+            let mut report = format!(
+                "=== Systemd Availability Check ===\n✅ Systemd is available at: {}\n",
+                stdout.trim()
+            );
+            
+            // Also check systemd version
+            if let Ok(version_result) = client.shell_v2(&device_id, "systemctl --version") {
+                let version = version_result.stdout_string();
+                report.push_str(&format!("\n{}", version));
             }
-        }
+            
+            Ok(AdbJobOutput::Text(report))
+        });
     }
 }
 
-fn systemd_restart_service(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        if state.systemd_service_name.trim().is_empty() {
-            state.systemd_output = "Please enter a service name first.".to_string();
-            return;
-        }
-        
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "su", "-c", &format!("systemctl restart {}", state.systemd_service_name)])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let stdout = String::from_utf8_lossy(&result.stdout);
-                let stderr = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Restart Service: {} ===\n{}\n{}",
-                    state.systemd_service_name,
-                    if stdout.trim().is_empty() { "Service restarted successfully" } else { stdout.trim() },
-                    if !stderr.is_empty() { format!("Error: {}", stderr.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to restart service: {}", e);
-            }
-        }
+fn get_systemd_status(state: &mut AdbToolsState) {
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        "=== System Status ===".to_string(),
+        &["systemctl", "status"],
+        None,
+        "Failed to get system status",
+    );
+}
+
+fn systemd_daemon_reload(state: &mut AdbToolsState) {
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        "=== Daemon Reload ===".to_string(),
+        &["su", "-c", "systemctl daemon-reload"],
+        Some("Daemon reload completed successfully"),
+        "Failed to reload daemon",
+    );
+}
+
+/// Run `systemctl <action> <service>` as root for the service in the name field
+fn submit_systemctl_action(state: &mut AdbToolsState, action: &str, title: &str, success: &'static str, failure: &'static str) {
+    if state.systemd_service_name.trim().is_empty() {
+        state.systemd_output = "Please enter a service name first.".to_string();
+        return;
     }
+    
+    let service = state.systemd_service_name.clone();
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        format!("=== {}: {} ===", title, service),
+        &["su", "-c", &format!("systemctl {} {}", action, service)],
+        Some(success),
+        failure,
+    );
+}
+
+fn systemd_start_service(state: &mut AdbToolsState) {
+    submit_systemctl_action(state, "start", "Start Service", "Service started successfully", "Failed to start service");
+}
+
+fn systemd_stop_service(state: &mut AdbToolsState) {
+    submit_systemctl_action(state, "stop", "Stop Service", "Service stopped successfully", "Failed to stop service");
+}
+
+fn systemd_restart_service(state: &mut AdbToolsState) {
+    submit_systemctl_action(state, "restart", "Restart Service", "Service restarted successfully", "Failed to restart service");
 }
 
 fn systemd_reload_service(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        if state.systemd_service_name.trim().is_empty() {
-            state.systemd_output = "Please enter a service name first.".to_string();
-            return;
-        }
-        
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "su", "-c", &format!("systemctl reload {}", state.systemd_service_name)])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let stdout = String::from_utf8_lossy(&result.stdout);
-                let stderr = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Reload Service: {} ===\n{}\n{}",
-                    state.systemd_service_name,
-                    if stdout.trim().is_empty() { "Service configuration reloaded successfully" } else { stdout.trim() },
-                    if !stderr.is_empty() { format!("Error: {}", stderr.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to reload service: {}", e);
-            }
-        }
-    }
+    submit_systemctl_action(state, "reload", "Reload Service", "Service configuration reloaded successfully", "Failed to reload service");
 }
 
 fn systemd_enable_service(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        if state.systemd_service_name.trim().is_empty() {
-            state.systemd_output = "Please enter a service name first.".to_string();
-            return;
-        }
-        
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "su", "-c", &format!("systemctl enable {}", state.systemd_service_name)])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let stdout = String::from_utf8_lossy(&result.stdout);
-                let stderr = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Enable Service: {} ===\n{}\n{}",
-                    state.systemd_service_name,
-                    if stdout.trim().is_empty() { "Service enabled successfully" } else { stdout.trim() },
-                    if !stderr.is_empty() { format!("Error: {}", stderr.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to enable service: {}", e);
-            }
-        }
-    }
+    submit_systemctl_action(state, "enable", "Enable Service", "Service enabled successfully", "Failed to enable service");
 }
 
 fn systemd_disable_service(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        if state.systemd_service_name.trim().is_empty() {
-            state.systemd_output = "Please enter a service name first.".to_string();
-            return;
-        }
-        
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "su", "-c", &format!("systemctl disable {}", state.systemd_service_name)])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let stdout = String::from_utf8_lossy(&result.stdout);
-                let stderr = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Disable Service: {} ===\n{}\n{}",
-                    state.systemd_service_name,
-                    if stdout.trim().is_empty() { "Service disabled successfully" } else { stdout.trim() },
-                    if !stderr.is_empty() { format!("Error: {}", stderr.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to disable service: {}", e);
-            }
-        }
-    }
+    submit_systemctl_action(state, "disable", "Disable Service", "Service disabled successfully", "Failed to disable service");
 }
 
 fn systemd_service_status(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        if state.systemd_service_name.trim().is_empty() {
-            state.systemd_output = "Please enter a service name first.".to_string();
-            return;
-        }
-        
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "systemctl", "status", &state.systemd_service_name])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let status = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Service Status: {} ===\n{}\n{}",
-                    state.systemd_service_name,
-                    status,
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to get service status: {}", e);
-            }
-        }
+    if state.systemd_service_name.trim().is_empty() {
+        state.systemd_output = "Please enter a service name first.".to_string();
+        return;
     }
+    
+    let service = state.systemd_service_name.clone();
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        format!("=== Service Status: {} ===", service),
+        &["systemctl", "status", &service],
+        None,
+        "Failed to get service status",
+    );
 }
 
 fn systemd_list_units(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "systemctl", "list-units", "--no-pager"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let units = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== All Units ===\n{}\n{}",
-                    units,
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to list units: {}", e);
-            }
-        }
-    }
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        "=== All Units ===".to_string(),
+        &["systemctl", "list-units", "--no-pager"],
+        None,
+        "Failed to list units",
+    );
 }
 
 fn systemd_list_services(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "systemctl", "list-units", "--type=service", "--no-pager"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let services = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== All Services ===\n{}\n{}",
-                    services,
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to list services: {}", e);
-            }
-        }
-    }
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        "=== All Services ===".to_string(),
+        &["systemctl", "list-units", "--type=service", "--no-pager"],
+        None,
+        "Failed to list services",
+    );
 }
 
 fn systemd_list_failed(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "systemctl", "list-units", "--failed", "--no-pager"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let failed = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Failed Units ===\n{}\n{}",
-                    if failed.trim().is_empty() { "No failed units found." } else { failed.trim() },
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to list failed units: {}", e);
-            }
-        }
-    }
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        "=== Failed Units ===".to_string(),
+        &["systemctl", "list-units", "--failed", "--no-pager"],
+        Some("No failed units found."),
+        "Failed to list failed units",
+    );
 }
 
 fn systemd_filter_units(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        if state.systemd_unit_filter.trim().is_empty() {
-            state.systemd_output = "Please enter a filter pattern first.".to_string();
-            return;
-        }
-        
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "systemctl", "list-units", "--no-pager", "|", "grep", &state.systemd_unit_filter])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let filtered = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Filtered Units: {} ===\n{}\n{}",
-                    state.systemd_unit_filter,
-                    if filtered.trim().is_empty() { "No matching units found." } else { filtered.trim() },
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to filter units: {}", e);
-            }
-        }
+    if state.systemd_unit_filter.trim().is_empty() {
+        state.systemd_output = "Please enter a filter pattern first.".to_string();
+        return;
     }
+    
+    let filter = state.systemd_unit_filter.clone();
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        format!("=== Filtered Units: {} ===", filter),
+        &["systemctl", "list-units", "--no-pager", "|", "grep", &filter],
+        Some("No matching units found."),
+        "Failed to filter units",
+    );
 }
 
 fn systemd_analyze_time(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "systemd-analyze", "time"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let time_info = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Boot Time Analysis ===\n{}\n{}",
-                    time_info,
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to analyze boot time: {}", e);
-            }
-        }
-    }
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        "=== Boot Time Analysis ===".to_string(),
+        &["systemd-analyze", "time"],
+        None,
+        "Failed to analyze boot time",
+    );
 }
 
 fn systemd_analyze_blame(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "systemd-analyze", "blame"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let blame_info = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Boot Blame Analysis ===\n{}\n{}",
-                    blame_info,
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to analyze blame: {}", e);
-            }
-        }
-    }
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        "=== Boot Blame Analysis ===".to_string(),
+        &["systemd-analyze", "blame"],
+        None,
+        "Failed to analyze blame",
+    );
 }
 
 fn systemd_analyze_critical(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "systemd-analyze", "critical-chain"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let critical_info = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Critical Chain Analysis ===\n{}\n{}",
-                    critical_info,
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to analyze critical chain: {}", e);
-            }
-        }
-    }
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        "=== Critical Chain Analysis ===".to_string(),
+        &["systemd-analyze", "critical-chain"],
+        None,
+        "Failed to analyze critical chain",
+    );
 }
 
 fn systemd_show_environment(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "systemctl", "show-environment"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let env_info = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Systemd Environment ===\n{}\n{}",
-                    env_info,
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to show environment: {}", e);
-            }
-        }
-    }
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        "=== Systemd Environment ===".to_string(),
+        &["systemctl", "show-environment"],
+        None,
+        "Failed to show environment",
+    );
 }
 
 fn systemd_list_dependencies(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "systemctl", "list-dependencies", &state.systemd_service_name])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let deps = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Dependencies for: {} ===\n{}\n{}",
-                    state.systemd_service_name,
-                    deps,
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to list dependencies: {}", e);
-            }
-        }
-    }
+    let service = state.systemd_service_name.clone();
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        format!("=== Dependencies for: {} ===", service),
+        &["systemctl", "list-dependencies", &service],
+        None,
+        "Failed to list dependencies",
+    );
 }
 
 fn systemd_show_journal(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "journalctl", "-n", "50", "--no-pager"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let journal = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Journal (Last 50 entries) ===\n{}\n{}",
-                    journal,
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to show journal: {}", e);
-            }
-        }
-    }
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        "=== Journal (Last 50 entries) ===".to_string(),
+        &["journalctl", "-n", "50", "--no-pager"],
+        None,
+        "Failed to show journal",
+    );
 }
 
 fn systemd_show_journal_errors(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "journalctl", "-p", "err", "-n", "30", "--no-pager"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let errors = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Journal Errors (Last 30) ===\n{}\n{}",
-                    if errors.trim().is_empty() { "No error entries found." } else { errors.trim() },
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to show journal errors: {}", e);
-            }
-        }
-    }
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        "=== Journal Errors (Last 30) ===".to_string(),
+        &["journalctl", "-p", "err", "-n", "30", "--no-pager"],
+        Some("No error entries found."),
+        "Failed to show journal errors",
+    );
 }
 
 fn systemd_show_journal_today(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "journalctl", "--since", "today", "--no-pager"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let today_logs = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Today's Journal Entries ===\n{}\n{}",
-                    if today_logs.trim().is_empty() { "No entries found for today." } else { today_logs.trim() },
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to show today's journal: {}", e);
-            }
-        }
-    }
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        "=== Today's Journal Entries ===".to_string(),
+        &["journalctl", "--since", "today", "--no-pager"],
+        Some("No entries found for today."),
+        "Failed to show today's journal",
+    );
 }
 
 fn systemd_journal_disk_usage(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "journalctl", "--disk-usage"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let usage = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Journal Disk Usage ===\n{}\n{}",
-                    usage,
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to check journal disk usage: {}", e);
-            }
-        }
-    }
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        "=== Journal Disk Usage ===".to_string(),
+        &["journalctl", "--disk-usage"],
+        None,
+        "Failed to check journal disk usage",
+    );
 }
 
 fn systemd_journal_vacuum(state: &mut AdbToolsState) {
-    if let Some(device_id) = &state.selected_device {
-        let output = Command::new("adb")
-            .args(["-s", device_id, "shell", "su", "-c", "journalctl --vacuum-time=1d"])
-            .output();
-            
-        match output {
-            Ok(result) => {
-                let vacuum_result = String::from_utf8_lossy(&result.stdout);
-                let error = String::from_utf8_lossy(&result.stderr);
-                
-                state.systemd_output = format!(
-                    "=== Journal Vacuum (Keep 1 day) ===\n{}\n{}",
-                    vacuum_result,
-                    if !error.is_empty() { format!("Error: {}", error.trim()) } else { String::new() }
-                );
-            }
-            Err(e) => {
-                state.systemd_output = format!("Failed to vacuum journal: {}", e);
-            }
-        }
-    }
+    submit_shell_report(
+        state,
+        AdbFunction::SystemdManagement,
+        "=== Journal Vacuum (Keep 1 day) ===".to_string(),
+        &["su", "-c", "journalctl --vacuum-time=1d"],
+        None,
+        "Failed to vacuum journal",
+    );
 }
 
 // Helper functions
-fn execute_adb_command(args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
-    let output = Command::new("adb")
-        .args(args)
-        .output()?;
-    
-    if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
-    } else {
-        Err(format!("ADB command failed: {}", String::from_utf8_lossy(&output.stderr)).into())
-    }
-}
-
-fn extract_device_property(line: &str, property: &str) -> String {
-    if let Some(start) = line.find(property) {
-        let start = start + property.len();
-        if let Some(end) = line[start..].find(' ') {
-            line[start..start + end].to_string()
-        } else {
-            line[start..].to_string()
-        }
-    } else {
-        String::new()
+/// Run `<args>` in a shell on the selected device as a job and report stdout/stderr under `header`.
+/// `empty_message` replaces blank stdout, `failure` prefixes errors reaching the device.
+fn submit_shell_report(
+    state: &mut AdbToolsState,
+    function: AdbFunction,
+    header: String,
+    shell_args: &[&str],
+    empty_message: Option<&'static str>,
+    failure: &'static str,
+) {
+    if let Some(device_id) = state.selected_device.clone() {
+        let label = header.lines().next().unwrap_or_default().trim_matches('=').trim().to_string();
+        let client = state.adb_client.clone();
+        let command = shell_args.iter().map(|arg| shell_quote(arg)).collect::<Vec<_>>().join(" ");
+        
+        state.jobs.submit(function, label, move |job| {
+            match job.client(&client).shell_v2(&device_id, &command) {
+                Ok(result) => {
+                    let stdout = result.stdout_string();
+                    let stderr = result.stderr_string();
+                    let body = match empty_message {
+                        Some(message) if stdout.trim().is_empty() => message,
+                        _ => stdout.trim(),
+                    };
+                    
+                    Ok(AdbJobOutput::Text(format!(
+                        "{}\n{}\n{}",
+                        header,
+                        body,
+                        if !stderr.is_empty() { format!("Error: {}", stderr.trim()) } else { String::new() }
+                    )))
+                }
+                Err(e) => Err(format!("{}: {}", failure, e)),
+            }
+        });
     }
 }
 
@@ -2685,11 +6416,11 @@ fn add_time_series_data(state: &mut AdbToolsState) {
     if let Some(start_time) = state.time_series.start_time {
         let elapsed = start_time.elapsed().as_secs_f64();
         
-        // Add CPU usage data point
-        if let Some(cpu_load) = parse_cpu_load(&state.cpu_usage) {
+        // Add CPU usage data points, total and per core
+        if let Some(cpu) = &state.cpu_utilisation {
             let data_point = DataPoint {
                 timestamp: elapsed,
-                value: cpu_load,
+                value: cpu.total.busy,
             };
             state.time_series.cpu_usage.push_back(data_point);
             
@@ -2697,6 +6428,17 @@ fn add_time_series_data(state: &mut AdbToolsState) {
             while state.time_series.cpu_usage.len() > state.time_series.max_points {
                 state.time_series.cpu_usage.pop_front();
             }
+            
+            for (core, load) in &cpu.cores {
+                let points = state.time_series.cpu_cores.entry(*core).or_default();
+                points.push_back(DataPoint {
+                    timestamp: elapsed,
+                    value: load.busy,
+                });
+                while points.len() > state.time_series.max_points {
+                    points.pop_front();
+                }
+            }
         }
         
         // Add memory usage data point
@@ -2737,6 +6479,42 @@ fn add_time_series_data(state: &mut AdbToolsState) {
                 state.time_series.battery_temperature.pop_front();
             }
         }
+        
+        // Add thermal zone data points
+        if let Some(thermal) = &state.thermal {
+            for zone in &thermal.zones {
+                let Some(celsius) = zone.celsius else {
+                    continue;
+                };
+                let points = state.time_series.thermal_zones.entry(zone.index).or_default();
+                points.push_back(DataPoint {
+                    timestamp: elapsed,
+                    value: celsius,
+                });
+                while points.len() > state.time_series.max_points {
+                    points.pop_front();
+                }
+            }
+        }
+        
+        // Add network throughput data points
+        for rates in &state.network_rates {
+            state.time_series.network
+                .entry(rates.name.clone())
+                .or_default()
+                .push(elapsed, rates, state.time_series.max_points);
+        }
+        
+        // Add tracked app data points
+        if let Some(tracking) = &mut state.app_tracking {
+            if let Some(sample) = &tracking.last {
+                if tracking.restarted {
+                    state.time_series.process.restarts.push(elapsed);
+                    tracking.restarted = false;
+                }
+                state.time_series.process.push(elapsed, sample, state.time_series.max_points);
+            }
+        }
     }
 }
 
@@ -2747,6 +6525,11 @@ fn trim_time_series_data(state: &mut AdbToolsState) {
     while state.time_series.cpu_usage.len() > max_points {
         state.time_series.cpu_usage.pop_front();
     }
+    for points in state.time_series.cpu_cores.values_mut() {
+        while points.len() > max_points {
+            points.pop_front();
+        }
+    }
     
     // Trim memory usage data
     while state.time_series.memory_usage.len() > max_points {
@@ -2762,13 +6545,35 @@ fn trim_time_series_data(state: &mut AdbToolsState) {
     while state.time_series.battery_temperature.len() > max_points {
         state.time_series.battery_temperature.pop_front();
     }
+    
+    // Trim thermal zone data
+    for points in state.time_series.thermal_zones.values_mut() {
+        while points.len() > max_points {
+            points.pop_front();
+        }
+    }
+    
+    // Trim network throughput data
+    for series in state.time_series.network.values_mut() {
+        series.trim(max_points);
+    }
+    
+    // Trim tracked app data
+    state.time_series.process.trim(max_points);
 }
 
 fn clear_plot_data(state: &mut AdbToolsState) {
     state.time_series.cpu_usage.clear();
+    state.time_series.cpu_cores.clear();
     state.time_series.memory_usage.clear();
     state.time_series.battery_level.clear();
     state.time_series.battery_temperature.clear();
+    state.time_series.thermal_zones.clear();
+    state.time_series.network.clear();
+    state.time_series.process = ProcessSeries {
+        label: state.time_series.process.label.clone(),
+        ..Default::default()
+    };
     state.time_series.start_time = if state.monitoring_enabled {
         Some(Instant::now())
     } else {
@@ -2776,17 +6581,6 @@ fn clear_plot_data(state: &mut AdbToolsState) {
     };
 }
 
-fn parse_cpu_load(cpu_usage: &str) -> Option<f64> {
-    // Parse "Load: 1.23 0.45 0.67 (1m 5m 15m) | 8 cores" format
-    if let Some(start) = cpu_usage.find("Load: ") {
-        let load_part = &cpu_usage[start + 6..];
-        if let Some(space_pos) = load_part.find(' ') {
-            return load_part[..space_pos].parse().ok();
-        }
-    }
-    None
-}
-
 fn parse_memory_usage(memory_info: &HashMap<String, String>) -> Option<f64> {
     // Parse memory usage percentage from "Memory Usage" field
     if let Some(usage_str) = memory_info.get("Memory Usage") {
@@ -0,0 +1,183 @@
+use std::io::Read;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::tools::adb_client::AdbClient;
+use crate::tools::adb_tools::AdbDevice;
+
+pub const DEFAULT_TCPIP_PORT: u16 = 5555;
+/// Advertised by devices with Wireless debugging turned on (Android 11+)
+pub const MDNS_CONNECT_SERVICE: &str = "_adb-tls-connect._tcp";
+/// Advertised while the "Pair device with pairing code" dialog is open
+pub const MDNS_PAIRING_SERVICE: &str = "_adb-tls-pairing._tcp";
+/// Connection attempts after `tcpip`, while adbd restarts in network mode
+const TCPIP_CONNECT_ATTEMPTS: u32 = 5;
+const TCPIP_CONNECT_DELAY: Duration = Duration::from_secs(1);
+/// How often known endpoints are checked and, if dropped, reconnected
+pub const RECONNECT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A `host:port` that is connected over Wi-Fi, kept in the config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WirelessEndpoint {
+    pub address: String,
+    pub name: String,
+    /// Connect again whenever the endpoint is missing or offline
+    pub auto_reconnect: bool,
+}
+
+impl Default for WirelessEndpoint {
+    fn default() -> Self {
+        Self {
+            address: String::new(),
+            name: String::new(),
+            auto_reconnect: true,
+        }
+    }
+}
+
+/// One line of `adb mdns services`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MdnsService {
+    pub instance: String,
+    /// Service type without the trailing dot, e.g. `_adb-tls-connect._tcp`
+    pub service_type: String,
+    pub address: String,
+}
+
+impl MdnsService {
+    pub fn is_connect(&self) -> bool {
+        self.service_type == MDNS_CONNECT_SERVICE
+    }
+
+    pub fn is_pairing(&self) -> bool {
+        self.service_type == MDNS_PAIRING_SERVICE
+    }
+}
+
+/// Append the default port to a bare host
+pub fn normalize_address(address: &str) -> String {
+    let address = address.trim();
+    // A bracketed IPv6 host carries its own colons
+    let host_end = address.rfind(']').unwrap_or(0);
+    if address[host_end..].contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, DEFAULT_TCPIP_PORT)
+    }
+}
+
+/// Parse `host:mdns:services`: `<instance>\t<type>.\t<host:port>` per line
+pub fn parse_mdns_services(output: &str) -> Vec<MdnsService> {
+    output
+        .lines()
+        .filter_map(|line| {
+            // Instance names may contain spaces when the server separates fields with tabs
+            let parts: Vec<&str> = if line.contains('\t') {
+                line.split('\t').map(str::trim).filter(|p| !p.is_empty()).collect()
+            } else {
+                line.split_whitespace().collect()
+            };
+            let [instance, service_type, address] = parts.as_slice() else {
+                return None;
+            };
+            Some(MdnsService {
+                instance: instance.to_string(),
+                service_type: service_type.trim_end_matches('.').to_string(),
+                address: address.to_string(),
+            })
+        })
+        .collect()
+}
+
+pub fn mdns_services(client: &AdbClient) -> Result<Vec<MdnsService>, String> {
+    let output = client.host_query("host:mdns:services").map_err(|e| format!("mDNS discovery failed: {}", e))?;
+    Ok(parse_mdns_services(&output))
+}
+
+/// `adb pair`; the server answers with a sentence rather than a status
+pub fn pair(client: &AdbClient, address: &str, code: &str) -> Result<String, String> {
+    let reply = client
+        .host_query(&format!("host:pair:{}:{}", code.trim(), address.trim()))
+        .map_err(|e| format!("Pairing failed: {}", e))?;
+    if reply.starts_with("Successfully paired") {
+        Ok(reply)
+    } else {
+        Err(reply)
+    }
+}
+
+/// `adb connect`; "already connected" counts as success
+pub fn connect(client: &AdbClient, address: &str) -> Result<String, String> {
+    let reply = client
+        .host_query(&format!("host:connect:{}", normalize_address(address)))
+        .map_err(|e| format!("Connect failed: {}", e))?;
+    if reply.starts_with("connected to") || reply.starts_with("already connected to") {
+        Ok(reply)
+    } else {
+        Err(reply)
+    }
+}
+
+pub fn disconnect(client: &AdbClient, address: &str) -> Result<String, String> {
+    client
+        .host_query(&format!("host:disconnect:{}", normalize_address(address)))
+        .map_err(|e| format!("Disconnect failed: {}", e))
+}
+
+/// IPv4 address of the device's Wi-Fi interface, from `ip -f inet addr show wlan0`
+pub fn wlan_address(client: &AdbClient, serial: &str) -> Option<String> {
+    let output = client.shell(serial, "ip -f inet addr show wlan0").ok()?;
+    output.lines().find_map(|line| {
+        let address = line.trim().strip_prefix("inet ")?.split_whitespace().next()?;
+        Some(address.split('/').next().unwrap_or(address).to_string())
+    })
+}
+
+/// Restart adbd on a USB device in network mode and connect to it, returning the new address
+pub fn enable_tcpip(client: &AdbClient, serial: &str, port: u16) -> Result<String, String> {
+    // Look the address up first: the USB transport goes away once adbd restarts
+    let ip = wlan_address(client, serial).ok_or_else(|| format!("{} has no Wi-Fi address on wlan0", serial))?;
+
+    let mut stream = client
+        .open_service(serial, &format!("tcpip:{}", port))
+        .map_err(|e| format!("tcpip failed: {}", e))?;
+    let mut reply = String::new();
+    let _ = stream.read_to_string(&mut reply);
+    log::info!("{}: {}", serial, reply.trim());
+
+    let address = format!("{}:{}", ip, port);
+    let mut last_error = String::new();
+    for _ in 0..TCPIP_CONNECT_ATTEMPTS {
+        thread::sleep(TCPIP_CONNECT_DELAY);
+        match connect(client, &address) {
+            Ok(_) => return Ok(address),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(format!("{} switched to port {} but connecting failed: {}", serial, port, last_error))
+}
+
+/// Reconnect endpoints that are missing from `devices` or listed offline, returning those that came back
+pub fn reconnect_dropped(client: &AdbClient, endpoints: &[WirelessEndpoint], devices: &[AdbDevice]) -> Vec<String> {
+    let mut reconnected = Vec::new();
+    for endpoint in endpoints.iter().filter(|e| e.auto_reconnect) {
+        let address = normalize_address(&endpoint.address);
+        let status = devices.iter().find(|d| d.id == address).map(|d| d.status.as_str());
+        match status {
+            Some("device") | Some("unauthorized") | Some("authorizing") => continue,
+            // A stale transport has to go before the server will dial again
+            Some(_) => {
+                let _ = disconnect(client, &address);
+            }
+            None => {}
+        }
+        match connect(client, &address) {
+            Ok(_) => reconnected.push(address),
+            Err(e) => log::debug!("Reconnecting {} failed: {}", address, e),
+        }
+    }
+    reconnected
+}
@@ -1,4 +1,20 @@
+pub mod adb_apk;
+pub mod adb_backup;
+pub mod adb_broadcast;
+pub mod adb_client;
+pub mod adb_files;
+pub mod adb_forward;
+pub mod adb_heap;
+pub mod adb_jobs;
+pub mod adb_logcat;
+pub mod adb_monitor;
+pub mod adb_packages;
+pub mod adb_screen;
+pub mod adb_snippets;
+pub mod adb_sync;
+pub mod adb_terminal;
 pub mod adb_tools;
+pub mod adb_wireless;
 pub mod fastboot_tools;
 pub mod qdl_tools;
 pub mod qramdump_tools;